@@ -3,5 +3,11 @@ println(1, " + ", 2, " = ", x)
 
 y = [1.0 2.0 3.0; 4.0 5.0 6.0; 7.0 8.0 9.0]
 println("Before increment: ", y)
-ccall((:incr_array, "libccall"), Cvoid, (Array{Float64},), y)
+ccall((:incr_array, "libccall"), Cvoid, (Array,), y)
 println("After increment: ", y)
+
+try
+    ccall((:incr_array, "libccall"), Cvoid, (Array,), [1, 2, 3])
+catch e
+    println("Caught: ", e)
+end
@@ -12,21 +12,39 @@ pub unsafe extern "C" fn add(a: i32, b: i32) -> i32 {
 }
 
 // Increment every element in an array of `f64`s, it can be called from Julia with:
-// `ccall((:incr_array, "libccall"), Cvoid, (Array{Float64},), arr)`  where `arr` is an 
-// `Array{Float64}`.
+// `ccall((:incr_array, "libccall"), Cvoid, (Array,), arr)`  where `arr` is an `Array{Float64}`.
+// If `arr` has another element type an `ArgumentError` is thrown.
 #[no_mangle]
-pub unsafe extern "C" fn incr_array(a: TypedArray<f64>) {
+pub unsafe extern "C" fn incr_array(a: Array) {
     // We want to mutably borrow the array data but don't need to protect any new values, so we
     // can use `CCall::null` and `CCall::null_frame` to avoid allocations.
-    CCall::null()
-        .null_frame(|frame| {
-            let mut data = a.inline_data_mut(frame)?;
+    let mut ccall = CCall::null();
+    let res = ccall.null_frame(|frame| {
+        let mut data = a.inline_data_mut::<f64, _>(frame)?;
 
-            for x in data.as_mut_slice() {
-                *x += 1.0;
-            }
+        for x in data.as_mut_slice() {
+            *x += 1.0;
+        }
 
-            Ok(())
+        Ok(())
+    });
+
+    // Creating the exception requires a frame that can protect a few values. The error must be
+    // converted to a string before the exception is thrown because Julia won't return here to
+    // drop it.
+    if let Err(e) = res {
+        let msg = format!("incr_array: {}", e);
+        std::mem::drop(e);
+
+        ccall.set_stack_size(8);
+        ccall.throw_exception(|global, frame| {
+            let msg = Value::new(frame, msg)?;
+            let exc = Module::core(global)
+                .function("ArgumentError")?
+                .call1(frame, msg)?
+                .unwrap_or_else(|e| e);
+
+            Ok(exc)
         })
-        .ok();
+    }
 }
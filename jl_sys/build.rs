@@ -59,6 +59,7 @@ fn main() {
     out_path.push("bindings.rs");
 
     println!("cargo:rerun-if-changed=wrapper.h");
+    println!("cargo:rerun-if-changed=src/jlrs_cc.c");
     println!("cargo:rerun-if-env-changed=JULIA_DIR");
     println!("cargo:rerun-if-env-changed=CYGWIN_DIR");
 
@@ -101,8 +102,10 @@ fn main() {
         "jl_call3",
         "jl_compute_fieldtypes",
         "jl_egal",
+        "jl_error",
         "jl_eval_string",
         "jl_exception_occurred",
+        "jl_exit_on_sigint",
         "jl_field_index",
         "jl_field_isdefined",
         "jl_finalize",
@@ -110,6 +113,7 @@ fn main() {
         "jl_gc_collect",
         "jl_gc_enable",
         "jl_gc_is_enabled",
+        "jl_gc_new_weakref",
         "jl_gc_queue_root",
         "jl_gc_safepoint",
         "jl_get_field",
@@ -118,6 +122,7 @@ fn main() {
         "jl_get_nth_field",
         "jl_get_nth_field_noalloc",
         "jl_get_ptls_states",
+        "jl_get_world_counter",
         "jl_init__threading",
         "jl_init_with_image__threading",
         "jl_is_initialized",
@@ -139,6 +144,7 @@ fn main() {
         "jl_subtype",
         "jl_symbol",
         "jl_symbol_n",
+        "jl_throw",
         "jl_tupletype_fill",
         "jl_typename_str",
         "jl_typeof_str",
@@ -174,6 +180,7 @@ fn main() {
         .whitelist_type("jl_fielddesc32_t")
         .whitelist_type("jl_fielddesc8_t")
         .whitelist_type("jl_methtable_t")
+        .whitelist_type("jl_options_t")
         .whitelist_type("jl_taggedvalue_t")
         .whitelist_type("jl_task_t")
         .whitelist_type("jl_typemap_entry_t")
@@ -247,6 +254,7 @@ fn main() {
         .whitelist_var("jl_newvarnode_type")
         .whitelist_var("jl_nothing")
         .whitelist_var("jl_nothing_type")
+        .whitelist_var("jl_options")
         .whitelist_var("jl_number_type")
         .whitelist_var("jl_phicnode_type")
         .whitelist_var("jl_phinode_type")
@@ -300,4 +308,12 @@ fn main() {
     bindings
         .write_to_file(&out_path)
         .expect("Couldn't write bindings!");
+
+    // Helpers that need macros from julia.h, like JL_TRY and JL_CATCH, are written in C.
+    let mut cc = cc::Build::new();
+    cc.file("src/jlrs_cc.c");
+    for flag in flags.iter().filter(|flag| flag.starts_with("-I")) {
+        cc.flag(flag);
+    }
+    cc.compile("jlrs_cc");
 }
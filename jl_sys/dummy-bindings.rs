@@ -4687,6 +4687,9 @@ extern "C" {
 extern "C" {
     pub fn jl_gc_queue_root(root: *mut jl_value_t);
 }
+extern "C" {
+    pub fn jl_gc_new_weakref(value: *mut jl_value_t) -> *mut jl_weakref_t;
+}
 extern "C" {
     pub fn jl_compute_fieldtypes(
         st: *mut jl_datatype_t,
@@ -4762,6 +4765,9 @@ extern "C" {
 extern "C" {
     pub fn jl_get_kwsorter(ty: *mut jl_value_t) -> *mut jl_function_t;
 }
+extern "C" {
+    pub fn jl_get_world_counter() -> usize;
+}
 extern "C" {
     pub fn jl_box_bool(x: i8) -> *mut jl_value_t;
 }
@@ -4926,6 +4932,56 @@ extern "C" {
 extern "C" {
     pub fn jl_exception_occurred() -> *mut jl_value_t;
 }
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct jl_options_t {
+    pub quiet: i8,
+    pub banner: i8,
+    pub julia_bindir: *const ::std::os::raw::c_char,
+    pub julia_bin: *const ::std::os::raw::c_char,
+    pub cmds: *mut *const ::std::os::raw::c_char,
+    pub image_file: *const ::std::os::raw::c_char,
+    pub cpu_target: *const ::std::os::raw::c_char,
+    pub nthreads: i32,
+    pub nprocs: i32,
+    pub machine_file: *const ::std::os::raw::c_char,
+    pub project: *const ::std::os::raw::c_char,
+    pub isinteractive: i8,
+    pub color: i8,
+    pub historyfile: i8,
+    pub startupfile: i8,
+    pub compile_enabled: i8,
+    pub code_coverage: i8,
+    pub malloc_log: i8,
+    pub opt_level: i8,
+    pub debug_level: i8,
+    pub check_bounds: i8,
+    pub depwarn: i8,
+    pub warn_overwrite: i8,
+    pub can_inline: i8,
+    pub polly: i8,
+    pub trace_compile: *const ::std::os::raw::c_char,
+    pub fast_math: i8,
+    pub worker: i8,
+    pub cookie: *const ::std::os::raw::c_char,
+    pub handle_signals: i8,
+    pub use_sysimage_native_code: i8,
+    pub use_compiled_modules: i8,
+    pub bindto: *const ::std::os::raw::c_char,
+    pub outputbc: *const ::std::os::raw::c_char,
+    pub outputunoptbc: *const ::std::os::raw::c_char,
+    pub outputo: *const ::std::os::raw::c_char,
+    pub outputasm: *const ::std::os::raw::c_char,
+    pub outputji: *const ::std::os::raw::c_char,
+    pub output_code_coverage: *const ::std::os::raw::c_char,
+    pub incremental: i8,
+    pub image_file_specified: i8,
+    pub warn_scope: i8,
+    pub image_codegen: i8,
+}
+extern "C" {
+    pub static mut jl_options: jl_options_t;
+}
 extern "C" {
     pub fn jl_init__threading();
 }
@@ -4941,6 +4997,15 @@ extern "C" {
 extern "C" {
     pub fn jl_atexit_hook(status: ::std::os::raw::c_int);
 }
+extern "C" {
+    pub fn jl_exit_on_sigint(on: ::std::os::raw::c_int);
+}
+extern "C" {
+    pub fn jl_error(str_: *const ::std::os::raw::c_char);
+}
+extern "C" {
+    pub fn jl_throw(e: *mut jl_value_t);
+}
 extern "C" {
     pub fn jl_eval_string(str: *const ::std::os::raw::c_char) -> *mut jl_value_t;
 }
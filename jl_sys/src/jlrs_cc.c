@@ -0,0 +1,70 @@
+#include <julia.h>
+
+JL_DLLEXPORT jl_value_t *jl_invoke(jl_value_t *F, jl_value_t **args, uint32_t nargs,
+                                   jl_method_instance_t *meth);
+
+// Invoke the method instance `mi` of `f` with `args` without dispatching on the arguments. Like
+// `jl_call`, the call happens in the latest world and an exception is caught and stored so it
+// can be retrieved with `jl_exception_occurred`, in which case NULL is returned.
+jl_value_t *jlrs_invoke(jl_value_t *f, jl_value_t **args, uint32_t nargs,
+                        jl_method_instance_t *mi)
+{
+    jl_value_t *v;
+    JL_TRY
+    {
+        jl_ptls_t ptls = jl_get_ptls_states();
+        size_t last_age = ptls->world_age;
+        ptls->world_age = jl_get_world_counter();
+        v = jl_invoke(f, args, nargs, mi);
+        ptls->world_age = last_age;
+        jl_exception_clear();
+    }
+    JL_CATCH
+    {
+        jl_get_ptls_states()->previous_exception = jl_current_exception();
+        v = NULL;
+    }
+    return v;
+}
+
+// Allow signals to be delivered to the current thread, which must be in a sigatomic region. If an
+// interrupt is pending, ending the region throws it immediately. It's caught and discarded, which
+// restores the region, so this is retried until the region has been ended.
+void jlrs_enable_interrupts(void)
+{
+    volatile int enabled = 0;
+    while (!enabled)
+    {
+        JL_TRY
+        {
+            jl_sigatomic_end();
+            enabled = 1;
+        }
+        JL_CATCH
+        {
+        }
+    }
+}
+
+// Defer signals that are delivered to the current thread until they're enabled again.
+void jlrs_disable_interrupts(void)
+{
+    jl_sigatomic_begin();
+}
+
+// Discard an interrupt that has been deferred on the current thread, which must be in a sigatomic
+// region. Returns 1 if an interrupt has been discarded, 0 otherwise.
+int jlrs_discard_interrupt(void)
+{
+    volatile int discarded = 0;
+    JL_TRY
+    {
+        jl_sigatomic_end();
+        jl_sigatomic_begin();
+    }
+    JL_CATCH
+    {
+        discarded = 1;
+    }
+    return discarded;
+}
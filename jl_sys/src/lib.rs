@@ -1428,6 +1428,47 @@ pub unsafe fn jl_array_ptr_set(a: *mut c_void, i: usize, x: *mut c_void) -> *mut
     x.cast()
 }
 
+/*
+#define JL_OPTIONS_CHECK_BOUNDS_DEFAULT 0
+#define JL_OPTIONS_CHECK_BOUNDS_ON 1
+#define JL_OPTIONS_CHECK_BOUNDS_OFF 2
+*/
+pub const JL_OPTIONS_CHECK_BOUNDS_DEFAULT: i8 = 0;
+pub const JL_OPTIONS_CHECK_BOUNDS_ON: i8 = 1;
+pub const JL_OPTIONS_CHECK_BOUNDS_OFF: i8 = 2;
+
+/*
+#define JL_OPTIONS_STARTUPFILE_ON 1
+#define JL_OPTIONS_STARTUPFILE_OFF 2
+*/
+pub const JL_OPTIONS_STARTUPFILE_ON: i8 = 1;
+pub const JL_OPTIONS_STARTUPFILE_OFF: i8 = 2;
+
+extern "C" {
+    /// Invoke the method instance `mi` of `f` with `args` without dispatching on the arguments.
+    /// Like `jl_call`, an exception is caught and can be retrieved with `jl_exception_occurred`,
+    /// in which case a null pointer is returned. Defined in `src/jlrs_cc.c`.
+    pub fn jlrs_invoke(
+        f: *mut jl_value_t,
+        args: *mut *mut jl_value_t,
+        nargs: u32,
+        mi: *mut jl_method_instance_t,
+    ) -> *mut jl_value_t;
+
+    /// Allow signals to be delivered to the current thread, which must be in a sigatomic region.
+    /// An interrupt that's pending is discarded. Defined in `src/jlrs_cc.c`.
+    pub fn jlrs_enable_interrupts();
+
+    /// Defer signals that are delivered to the current thread until they're enabled again.
+    /// Defined in `src/jlrs_cc.c`.
+    pub fn jlrs_disable_interrupts();
+
+    /// Discard an interrupt that has been deferred on the current thread, which must be in a
+    /// sigatomic region. Returns 1 if an interrupt has been discarded, 0 otherwise. Defined in
+    /// `src/jlrs_cc.c`.
+    pub fn jlrs_discard_interrupt() -> std::os::raw::c_int;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,435 @@
+//! Configure Julia before initializing it.
+//!
+//! Julia reads a number of options, like the number of threads and the optimization level, from
+//! the command line when the `julia` executable is used. When Julia is embedded there's no
+//! command line to parse, these options must be set before `jl_init` is called instead. A
+//! [`JuliaBuilder`] collects these options and applies them when Julia is initialized, it can be
+//! used to create a [`Julia`], an [`AsyncJulia`] or a [`CCall`].
+//!
+//! [`JuliaBuilder`]: struct.JuliaBuilder.html
+//! [`Julia`]: ../struct.Julia.html
+//! [`AsyncJulia`]: ../multitask/struct.AsyncJulia.html
+//! [`CCall`]: ../struct.CCall.html
+
+use crate::error::{JlrsError, JlrsResult};
+use crate::interrupt;
+use crate::logging::InstalledLogger;
+use crate::persistent;
+use crate::stack::RawStack;
+use crate::stdio::Redirects;
+use crate::{CCall, Julia, INIT};
+use jl_sys::{
+    jl_eval_string, jl_exception_occurred, jl_init, jl_init_with_image__threading,
+    jl_is_initialized, jl_options, jl_true, JL_OPTIONS_CHECK_BOUNDS_OFF,
+    JL_OPTIONS_CHECK_BOUNDS_ON, JL_OPTIONS_STARTUPFILE_OFF, JL_OPTIONS_STARTUPFILE_ON,
+};
+use std::env;
+use std::ffi::CString;
+use std::io::{Error as IOError, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// The options that couldn't be applied after Julia has been initialized, one bit per option.
+static IGNORED_OPTIONS: AtomicU8 = AtomicU8::new(0);
+
+/// An option of a [`JuliaBuilder`] that has been ignored because it couldn't be applied after
+/// Julia has been initialized. The ignored options are returned by [`Julia::ignored_options`] and
+/// [`AsyncJulia::ignored_options`].
+///
+/// [`JuliaBuilder`]: struct.JuliaBuilder.html
+/// [`Julia::ignored_options`]: ../struct.Julia.html#method.ignored_options
+/// [`AsyncJulia::ignored_options`]: ../multitask/struct.AsyncJulia.html#method.ignored_options
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IgnoredOption {
+    /// The heap size hint, this version of Julia doesn't support it.
+    HeapSizeHint,
+    /// The startup file, it couldn't be loaded.
+    StartupFile,
+}
+
+impl IgnoredOption {
+    fn flag(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+pub(crate) fn ignored_options() -> Vec<IgnoredOption> {
+    let flags = IGNORED_OPTIONS.load(Ordering::Acquire);
+    [IgnoredOption::HeapSizeHint, IgnoredOption::StartupFile]
+        .iter()
+        .copied()
+        .filter(|option| flags & option.flag() != 0)
+        .collect()
+}
+
+fn ignore_option(option: IgnoredOption) {
+    IGNORED_OPTIONS.fetch_or(option.flag(), Ordering::AcqRel);
+}
+
+/// Builder that configures and initializes Julia. Every option that isn't set explicitly keeps
+/// the default value Julia would use if it were started with the `julia` executable without any
+/// command line arguments, except the startup file which isn't loaded by default when Julia is
+/// embedded.
+///
+/// Example:
+///
+/// ```no_run
+/// # use jlrs::prelude::*;
+/// # fn main() {
+/// let julia = unsafe {
+///     JuliaBuilder::new()
+///         .stack_size(32)
+///         .n_threads(4)
+///         .optimization_level(3)
+///         .check_bounds(false)
+///         .init()
+///         .unwrap()
+/// };
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct JuliaBuilder {
+    pub(crate) stack_size: usize,
+    pub(crate) max_stack_size: Option<usize>,
+    image: Option<(PathBuf, PathBuf)>,
+    n_threads: Option<usize>,
+    optimization_level: Option<u8>,
+    project: Option<PathBuf>,
+    load_path: Option<Vec<PathBuf>>,
+    depot_path: Option<Vec<PathBuf>>,
+    startup_file: Option<bool>,
+    check_bounds: Option<bool>,
+    heap_size_hint: Option<u64>,
+}
+
+impl JuliaBuilder {
+    /// Create a new builder. The GC stack will have 32 slots unless you change this with
+    /// [`JuliaBuilder::stack_size`], all Julia options are left at their defaults.
+    ///
+    /// [`JuliaBuilder::stack_size`]: struct.JuliaBuilder.html#method.stack_size
+    pub fn new() -> Self {
+        JuliaBuilder {
+            stack_size: 32,
+            max_stack_size: None,
+            image: None,
+            n_threads: None,
+            optimization_level: None,
+            project: None,
+            load_path: None,
+            depot_path: None,
+            startup_file: None,
+            check_bounds: None,
+            heap_size_hint: None,
+        }
+    }
+
+    /// Set the number of slots of the GC stack. See [`Julia::init`] for more information.
+    ///
+    /// [`Julia::init`]: ../struct.Julia.html#method.init
+    pub fn stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = stack_size;
+        self
+    }
+
+    /// Set the maximum number of slots of the GC stack. The stack grows on demand by default, if
+    /// a maximum is set creating a frame or value that would grow the stack beyond this size
+    /// returns `AllocError::StackOverflow` instead. This can be used on targets where memory is
+    /// scarce.
+    pub fn max_stack_size(mut self, max_stack_size: usize) -> Self {
+        self.max_stack_size = Some(max_stack_size);
+        self
+    }
+
+    /// Load a custom system image instead of the default one. `julia_bindir` must be the absolute
+    /// path to a directory that contains a compatible Julia binary (eg `${JULIA_DIR}/bin`),
+    /// `image_path` must be either an absolute or a relative path to a system image. See
+    /// [`Julia::init_with_image`] for more information.
+    ///
+    /// [`Julia::init_with_image`]: ../struct.Julia.html#method.init_with_image
+    pub fn image<P: AsRef<Path>, Q: AsRef<Path>>(mut self, julia_bindir: P, image_path: Q) -> Self {
+        self.image = Some((
+            julia_bindir.as_ref().to_path_buf(),
+            image_path.as_ref().to_path_buf(),
+        ));
+        self
+    }
+
+    /// Set the number of threads Julia can use, this is equivalent to `--threads`. If `n_threads`
+    /// is 0 Julia uses as many threads as there are CPU threads, which is equivalent to
+    /// `--threads=auto`. If this option isn't set, the `JULIA_NUM_THREADS` environment variable
+    /// is used.
+    pub fn n_threads(mut self, n_threads: usize) -> Self {
+        self.n_threads = Some(n_threads);
+        self
+    }
+
+    /// Set the optimization level, this is equivalent to `-O`. Valid levels are 0, 1, 2 and 3,
+    /// initializing Julia with a different level returns an error.
+    pub fn optimization_level(mut self, level: u8) -> Self {
+        self.optimization_level = Some(level);
+        self
+    }
+
+    /// Set the project that is activated when Julia starts, this is equivalent to `--project`.
+    /// Another project can be activated after Julia has been initialized with
+    /// [`Julia::activate_project`].
+    ///
+    /// [`Julia::activate_project`]: ../struct.Julia.html#method.activate_project
+    pub fn project<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.project = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the load path, this is equivalent to setting the `JULIA_LOAD_PATH` environment
+    /// variable. Entries can be paths to environments or one of Julia's special entries like
+    /// `@stdlib`. Note that this updates the environment variable of the current process when
+    /// Julia is initialized.
+    pub fn load_path<I, P>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        self.load_path = Some(
+            paths
+                .into_iter()
+                .map(|p| p.as_ref().to_path_buf())
+                .collect(),
+        );
+        self
+    }
+
+    /// Set the depot path, this is equivalent to setting the `JULIA_DEPOT_PATH` environment
+    /// variable; Julia has no command line option for it. Note that this updates the environment
+    /// variable of the current process when Julia is initialized.
+    pub fn depot_path<I, P>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        self.depot_path = Some(
+            paths
+                .into_iter()
+                .map(|p| p.as_ref().to_path_buf())
+                .collect(),
+        );
+        self
+    }
+
+    /// Enable or disable loading `~/.julia/config/startup.jl`, this is equivalent to
+    /// `--startup-file`. Embedded Julia doesn't load the startup file on its own, if this option
+    /// is enabled it's loaded right after the runtime has been initialized. If loading it fails,
+    /// Julia is initialized without it and [`IgnoredOption::StartupFile`] is reported.
+    ///
+    /// [`IgnoredOption::StartupFile`]: enum.IgnoredOption.html#variant.StartupFile
+    pub fn startup_file(mut self, load: bool) -> Self {
+        self.startup_file = Some(load);
+        self
+    }
+
+    /// Force bounds checking on or off everywhere, this is equivalent to `--check-bounds`. If
+    /// this option isn't set, `@inbounds` annotations are respected.
+    pub fn check_bounds(mut self, check: bool) -> Self {
+        self.check_bounds = Some(check);
+        self
+    }
+
+    /// Set a hint for the amount of memory the garbage collector should try to stay under, in
+    /// bytes. This is equivalent to `--heap-size-hint`. Older versions of Julia don't support
+    /// this option, the hint is ignored and [`IgnoredOption::HeapSizeHint`] is reported in that
+    /// case.
+    ///
+    /// [`IgnoredOption::HeapSizeHint`]: enum.IgnoredOption.html#variant.HeapSizeHint
+    pub fn heap_size_hint(mut self, bytes: u64) -> Self {
+        self.heap_size_hint = Some(bytes);
+        self
+    }
+
+    /// Initialize Julia with the options of this builder. This function can only be called once,
+    /// if Julia has already been initialized this returns an error. See [`Julia::init`] for more
+    /// information.
+    ///
+    /// This function is unsafe because this crate provides you with a way to execute arbitrary
+    /// Julia code which can't be checked for correctness.
+    ///
+    /// [`Julia::init`]: ../struct.Julia.html#method.init
+    pub unsafe fn init(self) -> JlrsResult<Julia> {
+        self.init_runtime()?;
+
+        let mut jl = Julia {
+            stack: RawStack::new(self.stack_size, self.max_stack_size, "julia"),
+            redirects: Redirects::default(),
+            logger: InstalledLogger::default(),
+        };
+
+        persistent::set_julia_thread(true);
+        jl.init_jlrs();
+        self.configure_runtime();
+
+        Ok(jl)
+    }
+
+    /// Create a new [`CCall`] with the stack size and maximum stack size of this builder. When
+    /// Rust is called from Julia the runtime has already been initialized, so all other options
+    /// are ignored. The same restrictions as [`CCall::new`] apply.
+    ///
+    /// [`CCall`]: ../struct.CCall.html
+    /// [`CCall::new`]: ../struct.CCall.html#method.new
+    pub unsafe fn ccall(&self) -> CCall {
+        let mut ccall = CCall::new(self.stack_size);
+        ccall.max_stack_size = self.max_stack_size;
+        ccall
+    }
+
+    // Apply all options that must be set before Julia is initialized and initialize it.
+    pub(crate) unsafe fn init_runtime(&self) -> JlrsResult<()> {
+        if let Some((ref julia_bindir, ref image_path)) = self.image {
+            if !julia_bindir.exists() {
+                let julia_bindir_str = julia_bindir.to_string_lossy().to_string();
+                let io_err = IOError::new(ErrorKind::NotFound, julia_bindir_str);
+                return Err(JlrsError::other(io_err))?;
+            }
+
+            if !image_path.exists() {
+                let image_path_str = image_path.to_string_lossy().to_string();
+                let io_err = IOError::new(ErrorKind::NotFound, image_path_str);
+                return Err(JlrsError::other(io_err))?;
+            }
+        }
+
+        if let Some(level) = self.optimization_level {
+            if level > 3 {
+                Err(JlrsError::InvalidOption(format!(
+                    "optimization level must be 0, 1, 2 or 3, got {}",
+                    level
+                )))?;
+            }
+        }
+
+        // Julia keeps using the project path after initialization, so it's leaked when Julia is
+        // initialized.
+        let project = match self.project {
+            Some(ref project) => Some(
+                CString::new(project.to_string_lossy().as_ref()).map_err(|e| {
+                    JlrsError::InvalidOption(format!("invalid project path: {}", e))
+                })?,
+            ),
+            None => None,
+        };
+
+        let load_path = match self.load_path {
+            Some(ref load_path) => Some(
+                env::join_paths(load_path)
+                    .map_err(|e| JlrsError::InvalidOption(format!("invalid load path: {}", e)))?,
+            ),
+            None => None,
+        };
+
+        let depot_path = match self.depot_path {
+            Some(ref depot_path) => Some(
+                env::join_paths(depot_path)
+                    .map_err(|e| JlrsError::InvalidOption(format!("invalid depot path: {}", e)))?,
+            ),
+            None => None,
+        };
+
+        let image = match self.image {
+            Some((ref julia_bindir, ref image_path)) => {
+                let bindir =
+                    CString::new(julia_bindir.to_string_lossy().as_ref()).map_err(|e| {
+                        JlrsError::InvalidOption(format!("invalid Julia bindir: {}", e))
+                    })?;
+                let im_rel_path = CString::new(image_path.to_string_lossy().as_ref())
+                    .map_err(|e| JlrsError::InvalidOption(format!("invalid image path: {}", e)))?;
+                Some((bindir, im_rel_path))
+            }
+            None => None,
+        };
+
+        // All options are valid, nothing can fail between claiming initialization and `jl_init`.
+        if jl_is_initialized() != 0 || INIT.swap(true, Ordering::SeqCst) {
+            return Err(JlrsError::AlreadyInitialized.into());
+        }
+
+        if let Some(n_threads) = self.n_threads {
+            // -1 is used by Julia for --threads=auto
+            jl_options.nthreads = if n_threads == 0 { -1 } else { n_threads as i32 };
+        }
+
+        if let Some(level) = self.optimization_level {
+            jl_options.opt_level = level as i8;
+        }
+
+        if let Some(project) = project {
+            jl_options.project = project.into_raw();
+        }
+
+        if let Some(load_path) = load_path {
+            env::set_var("JULIA_LOAD_PATH", load_path);
+        }
+
+        if let Some(depot_path) = depot_path {
+            env::set_var("JULIA_DEPOT_PATH", depot_path);
+        }
+
+        if let Some(load) = self.startup_file {
+            jl_options.startupfile = if load {
+                JL_OPTIONS_STARTUPFILE_ON
+            } else {
+                JL_OPTIONS_STARTUPFILE_OFF
+            };
+        }
+
+        if let Some(check) = self.check_bounds {
+            jl_options.check_bounds = if check {
+                JL_OPTIONS_CHECK_BOUNDS_ON
+            } else {
+                JL_OPTIONS_CHECK_BOUNDS_OFF
+            };
+        }
+
+        // The threads started by Julia are needed to interrupt it.
+        let threads = interrupt::threads();
+        match image {
+            Some((bindir, im_rel_path)) => {
+                jl_init_with_image__threading(bindir.as_ptr(), im_rel_path.as_ptr())
+            }
+            None => jl_init(),
+        }
+        interrupt::set_signal_targets(threads);
+
+        Ok(())
+    }
+
+    // Apply all options that can only be applied after Julia has been initialized.
+    pub(crate) unsafe fn configure_runtime(&self) {
+        // Julia can't be initialized again, so these options are applied on a best-effort basis.
+        if let Some(bytes) = self.heap_size_hint {
+            let cmd = format!(
+                "try ccall(:jl_gc_set_max_memory, Cvoid, (UInt64,), {}); true catch; false end",
+                bytes
+            );
+
+            if !eval_bool(&cmd) {
+                ignore_option(IgnoredOption::HeapSizeHint);
+            }
+        }
+
+        if self.startup_file == Some(true)
+            && !eval_bool("try Base.load_julia_startup(); true catch; false end")
+        {
+            ignore_option(IgnoredOption::StartupFile);
+        }
+    }
+}
+
+impl Default for JuliaBuilder {
+    fn default() -> Self {
+        JuliaBuilder::new()
+    }
+}
+
+unsafe fn eval_bool(cmd: &str) -> bool {
+    let cmd = CString::new(cmd).expect("Invalid command");
+    let res = jl_eval_string(cmd.as_ptr());
+    !res.is_null() && jl_exception_occurred().is_null() && res == jl_true
+}
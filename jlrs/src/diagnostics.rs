@@ -0,0 +1,243 @@
+//! Diagnostics about the usage of the GC stack.
+//!
+//! Choosing the capacity of a [`StaticFrame`] and the size of the GC stack is largely a matter of
+//! trial and error. If the `gc-diagnostics` feature is enabled, jlrs records how many slots are
+//! used by every frame and stack. Frames are grouped by the location in your code where they're
+//! created, stacks by what they're used for; the stack of [`Julia`], the stacks of [`CCall`], and
+//! the stacks of the async runtime and its tasks.
+//!
+//! A [`Report`] of the current statistics can be generated with [`report`]. It lists frames
+//! whose capacity was exceeded at least once, and static frames that are over-provisioned
+//! because their peak usage never exceeded half of their capacity. Collecting these statistics
+//! has a cost, so this feature should only be enabled to tune your application.
+//!
+//! Example:
+//!
+//! ```no_run
+//! # use jlrs::prelude::*;
+//! # use jlrs::diagnostics;
+//! # fn main() {
+//! # let mut julia = unsafe { Julia::init(16).unwrap() };
+//! julia.frame(8, |_global, frame| {
+//!     Value::new(frame, 1u64)?;
+//!     Ok(())
+//! }).unwrap();
+//!
+//! let report = diagnostics::report();
+//! for frame in report.over_provisioned() {
+//!     println!("{}: capacity {}, peak {}", frame.location, frame.capacity, frame.peak);
+//! }
+//! # }
+//! ```
+//!
+//! [`StaticFrame`]: ../frame/struct.StaticFrame.html
+//! [`Julia`]: ../struct.Julia.html
+//! [`CCall`]: ../struct.CCall.html
+//! [`Report`]: struct.Report.html
+//! [`report`]: fn.report.html
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::panic::Location;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+static FRAMES: Mutex<BTreeMap<Location<'static>, FrameStats>> = Mutex::new(BTreeMap::new());
+static STACKS: Mutex<BTreeMap<String, Arc<StackCounters>>> = Mutex::new(BTreeMap::new());
+
+/// The kind of frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FrameKind {
+    Static,
+    Dynamic,
+}
+
+/// Statistics of all frames that have been created at the same location.
+#[derive(Clone, Debug)]
+pub struct FrameStats {
+    /// The location where the frames have been created.
+    pub location: &'static Location<'static>,
+    /// The kind of frame.
+    pub kind: FrameKind,
+    /// The number of frames that have been created.
+    pub created: u64,
+    /// The largest capacity of these frames. Dynamic frames have no capacity, this is always 0.
+    pub capacity: usize,
+    /// The largest number of slots used by one of these frames.
+    pub peak: usize,
+    /// The number of times one of these frames ran out of slots.
+    pub overflows: u64,
+}
+
+impl FrameStats {
+    /// Returns `true` if the capacity of one of these frames has been exceeded.
+    pub fn exceeded_capacity(&self) -> bool {
+        self.overflows > 0
+    }
+
+    /// Returns `true` if these are static frames that never used more than half of their
+    /// capacity.
+    pub fn is_over_provisioned(&self) -> bool {
+        self.kind == FrameKind::Static && self.overflows == 0 && self.peak * 2 < self.capacity
+    }
+}
+
+/// Statistics of a GC stack.
+#[derive(Clone, Debug)]
+pub struct StackStats {
+    /// What the stack is used for, eg `julia`, `ccall` or `async task 0`.
+    pub name: String,
+    /// The current number of slots of the stack.
+    pub size: usize,
+    /// The largest number of slots that have been in use at the same time.
+    pub peak: usize,
+}
+
+/// A snapshot of the statistics of all frames and stacks.
+#[derive(Clone, Debug)]
+pub struct Report {
+    /// The statistics of all frames, sorted by location.
+    pub frames: Vec<FrameStats>,
+    /// The statistics of all stacks, sorted by name.
+    pub stacks: Vec<StackStats>,
+}
+
+impl Report {
+    /// Returns the frames whose capacity has been exceeded.
+    pub fn exceeded(&self) -> impl Iterator<Item = &FrameStats> {
+        self.frames.iter().filter(|f| f.exceeded_capacity())
+    }
+
+    /// Returns the static frames that never used more than half of their capacity.
+    pub fn over_provisioned(&self) -> impl Iterator<Item = &FrameStats> {
+        self.frames.iter().filter(|f| f.is_over_provisioned())
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        writeln!(f, "Frames:")?;
+        for frame in self.frames.iter() {
+            let note = if frame.exceeded_capacity() {
+                " (capacity exceeded)"
+            } else if frame.is_over_provisioned() {
+                " (over-provisioned)"
+            } else {
+                ""
+            };
+
+            match frame.kind {
+                FrameKind::Static => writeln!(
+                    f,
+                    "  {}: static, created {}, capacity {}, peak {}, overflows {}{}",
+                    frame.location,
+                    frame.created,
+                    frame.capacity,
+                    frame.peak,
+                    frame.overflows,
+                    note
+                )?,
+                FrameKind::Dynamic => writeln!(
+                    f,
+                    "  {}: dynamic, created {}, peak {}",
+                    frame.location, frame.created, frame.peak
+                )?,
+            }
+        }
+
+        writeln!(f, "Stacks:")?;
+        for stack in self.stacks.iter() {
+            writeln!(
+                f,
+                "  {}: size {}, peak {}",
+                stack.name, stack.size, stack.peak
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns a snapshot of the statistics that have been collected.
+pub fn report() -> Report {
+    let frames = FRAMES
+        .lock()
+        .expect("Diagnostics are poisoned")
+        .values()
+        .cloned()
+        .collect();
+
+    let stacks = STACKS
+        .lock()
+        .expect("Diagnostics are poisoned")
+        .iter()
+        .map(|(name, counters)| StackStats {
+            name: name.clone(),
+            size: counters.size.load(Ordering::Relaxed),
+            peak: counters.peak.load(Ordering::Relaxed),
+        })
+        .collect();
+
+    Report { frames, stacks }
+}
+
+/// Clear the statistics of all frames and reset the peak usage of all stacks.
+pub fn reset() {
+    FRAMES.lock().expect("Diagnostics are poisoned").clear();
+    for counters in STACKS.lock().expect("Diagnostics are poisoned").values() {
+        counters.peak.store(0, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn record_frame(
+    location: &'static Location<'static>,
+    capacity: Option<usize>,
+    len: usize,
+    overflows: u64,
+) {
+    let mut frames = FRAMES.lock().expect("Diagnostics are poisoned");
+    let stats = frames.entry(*location).or_insert_with(|| FrameStats {
+        location,
+        kind: if capacity.is_some() {
+            FrameKind::Static
+        } else {
+            FrameKind::Dynamic
+        },
+        created: 0,
+        capacity: 0,
+        peak: 0,
+        overflows: 0,
+    });
+
+    stats.created += 1;
+    stats.capacity = stats.capacity.max(capacity.unwrap_or(0));
+    stats.peak = stats.peak.max(len);
+    stats.overflows += overflows;
+}
+
+// Stacks with the same name share their counters.
+pub(crate) struct StackCounters {
+    size: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl StackCounters {
+    pub(crate) fn update(&self, size: usize, used: usize) {
+        self.size.store(size, Ordering::Relaxed);
+        self.peak.fetch_max(used, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn register_stack(name: &str) -> Arc<StackCounters> {
+    STACKS
+        .lock()
+        .expect("Diagnostics are poisoned")
+        .entry(name.into())
+        .or_insert_with(|| {
+            Arc::new(StackCounters {
+                size: AtomicUsize::new(0),
+                peak: AtomicUsize::new(0),
+            })
+        })
+        .clone()
+}
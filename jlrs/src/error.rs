@@ -1,8 +1,17 @@
 //! Everything related to errors.
 
-use crate::value::array::Dimensions;
+use crate::traits::Frame;
+use crate::value::array::{Array, Dimensions};
+use crate::value::module::Module;
+use crate::value::Value;
+use jl_sys::{
+    jl_boundserror_type, jl_interrupt_exception, jl_methoderror_type, jl_typeis, jl_typeof,
+    jl_undefvarerror_type,
+};
+use std::any::Any;
 use std::error::Error;
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::panic::{self, AssertUnwindSafe};
 
 /// Alias that is used for most `Result`s in this crate.
 pub type JlrsResult<T> = Result<T, Box<JlrsError>>;
@@ -11,8 +20,10 @@ pub type JlrsResult<T> = Result<T, Box<JlrsError>>;
 #[derive(Debug)]
 pub enum JlrsError {
     Other(Box<dyn Error + Send + Sync>),
-    Exception(String),
+    Exception(JuliaException),
     AlreadyInitialized,
+    InvalidOption(String),
+    Panic(String),
     ConstAlreadyExists(String),
     NotAnArray,
     Nothing,
@@ -21,6 +32,8 @@ pub enum JlrsError {
     NotAMethodInstance,
     NotACodeInstance,
     NotAWeakRef,
+    NotOpaque(String),
+    OpaqueDropped(String),
     NotATypeMapEntry,
     NotATypeMapLevel,
     NotAnExpr,
@@ -41,6 +54,8 @@ pub enum JlrsError {
     FunctionNotFound(String),
     IncludeNotFound(String),
     IncludeError(String, String),
+    ProjectNotFound(String),
+    MissingPackages(Vec<String>),
     NoSuchField(String),
     InvalidArrayType,
     InvalidLayout,
@@ -63,7 +78,7 @@ pub enum JlrsError {
 }
 
 /// Create a new `JlrsError::Exception` and wrap it in a `JlrsResult::Err`.
-pub fn exception<T>(exc: String) -> JlrsResult<T> {
+pub fn exception<T>(exc: JuliaException) -> JlrsResult<T> {
     Err(JlrsError::Exception(exc))?
 }
 
@@ -85,6 +100,33 @@ impl JlrsError {
     pub(crate) fn alloc_error(a: AllocError) -> Self {
         JlrsError::AllocError(a)
     }
+
+    pub(crate) fn panic(payload: Box<dyn Any + Send>) -> Self {
+        JlrsError::Panic(panic_message(payload))
+    }
+}
+
+// Call `func` and convert a panic to a `JlrsError::Panic`, this must be used at every point
+// where a closure provided by the user is called so a panic never unwinds into Julia.
+pub(crate) fn catch_panic<T, F>(func: F) -> JlrsResult<T>
+where
+    F: FnOnce() -> JlrsResult<T>,
+{
+    match panic::catch_unwind(AssertUnwindSafe(func)) {
+        Ok(res) => res,
+        Err(payload) => Err(JlrsError::panic(payload))?,
+    }
+}
+
+// Extract the message of a panic, panics usually have either a `&str` or a `String` payload.
+pub(crate) fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(msg) => *msg,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(msg) => (*msg).into(),
+            Err(_) => "Box<dyn Any>".into(),
+        },
+    }
 }
 
 impl Display for JlrsError {
@@ -94,6 +136,8 @@ impl Display for JlrsError {
             JlrsError::AlreadyInitialized => {
                 write!(formatter, "The runtime was already initialized")
             }
+            JlrsError::InvalidOption(reason) => write!(formatter, "Invalid option: {}", reason),
+            JlrsError::Panic(msg) => write!(formatter, "A Rust panic occurred: {}", msg),
             JlrsError::Exception(exc) => write!(formatter, "An exception was thrown: {}", exc),
             JlrsError::NotAnArray => write!(formatter, "This is not an array"),
             JlrsError::NotAString => write!(formatter, "This is not a string"),
@@ -116,6 +160,14 @@ impl Display for JlrsError {
                 "The file {} could not be included successfully. Exception type: {}",
                 inc, err_type
             ),
+            JlrsError::ProjectNotFound(project) => {
+                write!(formatter, "The project {} could not be found", project)
+            }
+            JlrsError::MissingPackages(packages) => write!(
+                formatter,
+                "The following packages are not installed: {}",
+                packages.join(", ")
+            ),
             JlrsError::InvalidArrayType => write!(formatter, "Invalid array type"),
             JlrsError::InvalidCharacter => write!(formatter, "Invalid character"),
             JlrsError::NullFrame => write!(
@@ -152,6 +204,16 @@ impl Display for JlrsError {
             ),
             JlrsError::NotACodeInstance => write!(formatter, "This is not a code instance"),
             JlrsError::NotAWeakRef => write!(formatter, "This is not a weak ref"),
+            JlrsError::NotOpaque(ty) => {
+                write!(formatter, "This is not an opaque value of type {}", ty)
+            }
+            JlrsError::OpaqueDropped(ty) => {
+                write!(
+                    formatter,
+                    "The opaque value of type {} has been dropped",
+                    ty
+                )
+            }
             JlrsError::Immutable => write!(formatter, "This value is immutable"),
             JlrsError::NotSubtype => {
                 write!(formatter, "Value type is not a subtype of the field type")
@@ -215,6 +277,345 @@ impl Into<Box<JlrsError>> for Box<dyn Error + Send + Sync + 'static> {
     }
 }
 
+/// The fields of some common exceptions, converted to Rust. See [`JuliaException::kind`].
+///
+/// [`JuliaException::kind`]: struct.JuliaException.html#method.kind
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExceptionKind {
+    /// A `MethodError`: no method of `function` matches the types of the arguments in the given
+    /// world age.
+    MethodError {
+        function: String,
+        arg_types: Vec<String>,
+        world: u64,
+    },
+    /// A `BoundsError`: `index` is not a valid index of `array`. Both fields are optional in
+    /// Julia, `array` contains a summary of the indexed value.
+    BoundsError {
+        array: Option<String>,
+        index: Option<String>,
+    },
+    /// An `UndefVarError`: the variable `var` is not defined.
+    UndefVarError { var: String },
+    /// An `InterruptException`: the code was interrupted, eg with an [`InterruptHandle`].
+    ///
+    /// [`InterruptHandle`]: ../interrupt/struct.InterruptHandle.html
+    InterruptException,
+    /// Any other exception.
+    Other,
+}
+
+/// An exception that was thrown by Julia, converted to Rust so it can be used after the frame
+/// that protected the exception has been dropped.
+///
+/// The message is rendered with `Base.showerror`, the fields of `MethodError`, `BoundsError`
+/// and `UndefVarError` are available through [`JuliaException::kind`]. If the exception is a
+/// `Jlrs.TracedException`, which is thrown by functions wrapped with
+/// [`Value::attach_stacktrace`], the original exception is converted and its stacktrace is
+/// available as a [`Backtrace`] through [`JuliaException::backtrace`].
+///
+/// [`JuliaException::kind`]: struct.JuliaException.html#method.kind
+/// [`JuliaException::backtrace`]: struct.JuliaException.html#method.backtrace
+/// [`Value::attach_stacktrace`]: ../value/struct.Value.html#method.attach_stacktrace
+/// [`Backtrace`]: struct.Backtrace.html
+#[derive(Clone, Debug)]
+pub struct JuliaException {
+    type_name: String,
+    message: String,
+    kind: ExceptionKind,
+    backtrace: Option<Backtrace>,
+}
+
+impl JuliaException {
+    /// Convert `exception` to a `JuliaException`. This requires calling Julia, a new dynamic
+    /// frame is created which needs three slots on the GC stack.
+    pub fn new<'frame, F>(frame: &mut F, exception: Value) -> JlrsResult<Self>
+    where
+        F: Frame<'frame>,
+    {
+        frame.dynamic_frame(|frame| unsafe {
+            let global = frame.global();
+            let info = Module::main(global)
+                .submodule("Jlrs")?
+                .function("exceptioninfo")?
+                .call1(frame, exception)?
+                .map_err(|e| {
+                    JlrsError::Other(format!("Cannot convert exception: {}", e.type_name()).into())
+                })?;
+
+            let info = info.cast::<Array>()?.value_data(frame)?;
+            let (exception, message, details, backtrace) = match info.as_slice() {
+                &[exception, message, details, backtrace] => {
+                    (exception, message, details, backtrace)
+                }
+                fields => Err(JlrsError::Other(
+                    format!(
+                        "Cannot convert exception: expected 4 fields, found {}",
+                        fields.len()
+                    )
+                    .into(),
+                ))?,
+            };
+
+            let message = message.cast::<String>()?;
+            let details = details
+                .cast::<Array>()?
+                .value_data(frame)?
+                .as_slice()
+                .iter()
+                .map(|v| {
+                    if v.is_nothing() {
+                        Ok(None)
+                    } else {
+                        v.cast::<String>().map(Some)
+                    }
+                })
+                .collect::<JlrsResult<Vec<_>>>()?;
+
+            let backtrace = if backtrace.is_nothing() {
+                None
+            } else {
+                Some(Backtrace::from_info(frame, backtrace)?)
+            };
+
+            let mut details = details.into_iter();
+            let ptr = exception.ptr();
+            let kind = if jl_typeis(ptr, jl_methoderror_type) {
+                let function = details.next().flatten().unwrap_or_default();
+                let world = details
+                    .next()
+                    .flatten()
+                    .and_then(|w| w.parse().ok())
+                    .unwrap_or_default();
+                let arg_types = details.flatten().collect();
+
+                ExceptionKind::MethodError {
+                    function,
+                    arg_types,
+                    world,
+                }
+            } else if jl_typeis(ptr, jl_boundserror_type) {
+                ExceptionKind::BoundsError {
+                    array: details.next().flatten(),
+                    index: details.next().flatten(),
+                }
+            } else if jl_typeis(ptr, jl_undefvarerror_type) {
+                ExceptionKind::UndefVarError {
+                    var: details.next().flatten().unwrap_or_default(),
+                }
+            } else if jl_typeof(ptr) == jl_typeof(jl_interrupt_exception) {
+                ExceptionKind::InterruptException
+            } else {
+                ExceptionKind::Other
+            };
+
+            Ok(JuliaException {
+                type_name: exception.type_name().into(),
+                message,
+                kind,
+                backtrace,
+            })
+        })
+    }
+
+    /// The name of the exception's type, eg `"MethodError"`.
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// The message of this exception as rendered by `Base.showerror`.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The fields of this exception if it's a `MethodError`, `BoundsError` or `UndefVarError`.
+    pub fn kind(&self) -> &ExceptionKind {
+        &self.kind
+    }
+
+    /// The backtrace if the exception was thrown by a function wrapped with
+    /// [`Value::attach_stacktrace`].
+    ///
+    /// [`Value::attach_stacktrace`]: ../value/struct.Value.html#method.attach_stacktrace
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_ref()
+    }
+}
+
+impl Display for JuliaException {
+    fn fmt(&self, formatter: &mut Formatter) -> FmtResult {
+        write!(formatter, "{}", self.message)
+    }
+}
+
+impl Error for JuliaException {}
+
+impl From<JuliaException> for JlrsError {
+    fn from(exc: JuliaException) -> Self {
+        JlrsError::Exception(exc)
+    }
+}
+
+impl From<JuliaException> for Box<JlrsError> {
+    fn from(exc: JuliaException) -> Self {
+        Box::new(JlrsError::Exception(exc))
+    }
+}
+
+/// A single frame of a [`Backtrace`], converted from a `Base.StackTraces.StackFrame`.
+///
+/// [`Backtrace`]: struct.Backtrace.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct StackFrame {
+    func: String,
+    file: String,
+    line: isize,
+    inlined: bool,
+    module: Option<String>,
+}
+
+impl StackFrame {
+    /// The name of the function.
+    pub fn func(&self) -> &str {
+        &self.func
+    }
+
+    /// The file that contains the function.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The line number in that file.
+    pub fn line(&self) -> isize {
+        self.line
+    }
+
+    /// Returns `true` if the function has been inlined.
+    pub fn is_inlined(&self) -> bool {
+        self.inlined
+    }
+
+    /// The module the function is defined in, if it's known.
+    pub fn module(&self) -> Option<&str> {
+        self.module.as_deref()
+    }
+}
+
+impl Display for StackFrame {
+    fn fmt(&self, formatter: &mut Formatter) -> FmtResult {
+        write!(formatter, "{} at {}:{}", self.func, self.file, self.line)?;
+
+        if let Some(ref module) = self.module {
+            write!(formatter, " @ {}", module)?;
+        }
+
+        if self.inlined {
+            write!(formatter, " [inlined]")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A Julia backtrace, converted to Rust. The first frame is the one where the exception was
+/// thrown. Its `Display` implementation prints one frame per line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Backtrace {
+    frames: Vec<StackFrame>,
+}
+
+impl Backtrace {
+    /// Convert `stacktrace`, which must be a `Base.StackTraces.StackTrace` like the `stacktrace`
+    /// field of a `Jlrs.TracedException`, to a `Backtrace`. This requires calling Julia, a new
+    /// dynamic frame is created which needs three slots on the GC stack.
+    pub fn new<'frame, F>(frame: &mut F, stacktrace: Value) -> JlrsResult<Self>
+    where
+        F: Frame<'frame>,
+    {
+        frame.dynamic_frame(|frame| unsafe {
+            let global = frame.global();
+            let info = Module::main(global)
+                .submodule("Jlrs")?
+                .function("stackframeinfo")?
+                .call1(frame, stacktrace)?
+                .map_err(|e| {
+                    JlrsError::Other(format!("Cannot convert stacktrace: {}", e.type_name()).into())
+                })?;
+
+            Backtrace::from_info(frame, info)
+        })
+    }
+
+    /// The frames of this backtrace.
+    pub fn frames(&self) -> &[StackFrame] {
+        &self.frames
+    }
+
+    // Convert the output of `Jlrs.stackframeinfo`, which must be protected from garbage
+    // collection.
+    unsafe fn from_info<'frame, F>(frame: &F, info: Value) -> JlrsResult<Self>
+    where
+        F: Frame<'frame>,
+    {
+        let strings = |idx: usize| -> JlrsResult<Vec<Option<String>>> {
+            info.get_nth_field_noalloc(idx)?
+                .cast::<Array>()?
+                .value_data(frame)?
+                .as_slice()
+                .iter()
+                .map(|v| {
+                    if v.is_nothing() {
+                        Ok(None)
+                    } else {
+                        v.cast::<String>().map(Some)
+                    }
+                })
+                .collect()
+        };
+
+        let funcs = strings(0)?;
+        let files = strings(1)?;
+        let (lines, _) = info
+            .get_nth_field_noalloc(2)?
+            .cast::<Array>()?
+            .copy_inline_data::<isize>()?
+            .splat();
+        let (inlined, _) = info
+            .get_nth_field_noalloc(3)?
+            .cast::<Array>()?
+            .copy_inline_data::<bool>()?
+            .splat();
+        let modules = strings(4)?;
+
+        let frames = funcs
+            .into_iter()
+            .zip(files)
+            .zip(lines)
+            .zip(inlined)
+            .zip(modules)
+            .map(|((((func, file), line), inlined), module)| StackFrame {
+                func: func.unwrap_or_default(),
+                file: file.unwrap_or_default(),
+                line,
+                inlined,
+                module,
+            })
+            .collect();
+
+        Ok(Backtrace { frames })
+    }
+}
+
+impl Display for Backtrace {
+    fn fmt(&self, formatter: &mut Formatter) -> FmtResult {
+        for (i, frame) in self.frames.iter().enumerate() {
+            writeln!(formatter, " [{}] {}", i + 1, frame)?;
+        }
+
+        Ok(())
+    }
+}
+
 /// Frames and data they protect have a memory cost. If the memory set aside for containing frames
 /// or the frame itself is exhausted, this error is returned.
 #[derive(Copy, Clone, Debug)]
@@ -42,7 +42,53 @@ use crate::CCall;
 use std::marker::PhantomData;
 
 #[derive(Copy, Clone, Default)]
-pub struct FrameIdx(pub(crate) usize);
+pub struct FrameIdx {
+    pub(crate) segment: usize,
+    pub(crate) offset: usize,
+}
+
+impl FrameIdx {
+    pub(crate) fn new(segment: usize, offset: usize) -> Self {
+        FrameIdx { segment, offset }
+    }
+}
+
+// The location where a frame has been created. If the `gc-diagnostics` feature is enabled, the
+// usage of a frame is recorded when it's dropped, otherwise this is a zero-sized type.
+#[derive(Copy, Clone)]
+pub(crate) struct FrameSite {
+    #[cfg(feature = "gc-diagnostics")]
+    location: &'static std::panic::Location<'static>,
+    #[cfg(feature = "gc-diagnostics")]
+    overflows: u64,
+}
+
+impl FrameSite {
+    #[cfg_attr(feature = "gc-diagnostics", track_caller)]
+    pub(crate) fn caller() -> Self {
+        FrameSite {
+            #[cfg(feature = "gc-diagnostics")]
+            location: std::panic::Location::caller(),
+            #[cfg(feature = "gc-diagnostics")]
+            overflows: 0,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn overflow(&mut self) {
+        #[cfg(feature = "gc-diagnostics")]
+        {
+            self.overflows += 1;
+        }
+    }
+
+    #[cfg_attr(not(feature = "gc-diagnostics"), allow(unused_variables))]
+    #[inline(always)]
+    fn record(&self, capacity: Option<usize>, len: usize) {
+        #[cfg(feature = "gc-diagnostics")]
+        crate::diagnostics::record_frame(self.location, capacity, len, self.overflows);
+    }
+}
 
 /// A `StaticFrame` is a frame that has a definite number of slots on the GC stack. With some
 /// exceptions, creating new `Value`s and calling them require one slot each. Rather than using
@@ -63,6 +109,7 @@ where
     pub(crate) memory: StackView<'frame, U, Static>,
     pub(crate) capacity: usize,
     pub(crate) len: usize,
+    pub(crate) site: FrameSite,
 }
 
 impl<'frame, M: Mode> StaticFrame<'frame, M> {
@@ -70,26 +117,29 @@ impl<'frame, M: Mode> StaticFrame<'frame, M> {
         idx: FrameIdx,
         capacity: usize,
         memory: StackView<'frame, M, Static>,
+        site: FrameSite,
     ) -> StaticFrame<'frame, M> {
         StaticFrame {
             idx,
             memory,
             capacity,
             len: 0,
+            site,
         }
     }
 
     pub(crate) unsafe fn nested_frame<'nested>(
         &'nested mut self,
         capacity: usize,
+        site: FrameSite,
     ) -> JlrsResult<StaticFrame<'nested, M>> {
         let idx = self.memory.new_frame(capacity)?;
-        Ok(StaticFrame {
+        Ok(StaticFrame::with_capacity(
             idx,
-            memory: self.memory.nest_static(),
             capacity,
-            len: 0,
-        })
+            self.memory.nest_static(),
+            site,
+        ))
     }
 
     /// Returns the total number of slots.
@@ -103,6 +153,7 @@ where
     U: Mode,
 {
     fn drop(&mut self) {
+        self.site.record(Some(self.capacity), self.len);
         unsafe {
             self.memory.pop_frame(self.idx);
         }
@@ -125,28 +176,36 @@ where
     U: Mode,
 {
     pub(crate) idx: FrameIdx,
+    // If the frame runs out of space in its segment of the GC stack, it's extended by pushing a
+    // new frame to the next segment. This is the last frame that has been pushed.
+    pub(crate) tail: FrameIdx,
     pub(crate) memory: StackView<'frame, U, Dynamic>,
     pub(crate) len: usize,
+    pub(crate) site: FrameSite,
 }
 
 impl<'frame, M: Mode> DynamicFrame<'frame, M> {
-    pub(crate) unsafe fn new(idx: FrameIdx, memory: StackView<'frame, M, Dynamic>) -> Self {
+    pub(crate) unsafe fn new(
+        idx: FrameIdx,
+        memory: StackView<'frame, M, Dynamic>,
+        site: FrameSite,
+    ) -> Self {
         DynamicFrame {
             idx,
+            tail: idx,
             memory,
             len: 0,
+            site,
         }
     }
 
     pub(crate) unsafe fn nested_frame<'nested>(
         &'nested mut self,
+        site: FrameSite,
     ) -> JlrsResult<DynamicFrame<'nested, M>> {
-        let idx = self.memory.new_frame()?;
-        Ok(DynamicFrame {
-            idx,
-            memory: self.memory.nest_dynamic(),
-            len: 0,
-        })
+        let mut memory = self.memory.nest_dynamic();
+        let idx = memory.new_frame()?;
+        Ok(DynamicFrame::new(idx, memory, site))
     }
 }
 
@@ -155,6 +214,7 @@ where
     U: Mode,
 {
     fn drop(&mut self) {
+        self.site.record(None, self.len);
         unsafe {
             self.memory.pop_frame(self.idx);
         }
@@ -167,13 +227,15 @@ where
 ///
 /// [`Frame::output`]: ../traits/trait.Frame.html#method.output
 pub struct Output<'frame> {
+    pub(crate) segment: usize,
     pub(crate) offset: usize,
     _marker: PhantomData<&'frame ()>,
 }
 
 impl<'frame> Output<'frame> {
-    pub(crate) unsafe fn new(offset: usize) -> Self {
+    pub(crate) unsafe fn new(segment: usize, offset: usize) -> Self {
         Output {
+            segment,
             offset,
             _marker: PhantomData,
         }
@@ -203,21 +265,29 @@ impl<'frame> NullFrame<'frame> {
 #[cfg(all(feature = "async", target_os = "linux"))]
 pub struct AsyncFrame<'frame> {
     pub(crate) idx: FrameIdx,
+    pub(crate) tail: FrameIdx,
     pub(crate) memory: StackView<'frame, Async, Dynamic>,
     pub(crate) len: usize,
 }
 
 #[cfg(all(feature = "async", target_os = "linux"))]
 impl<'frame> AsyncFrame<'frame> {
+    pub(crate) unsafe fn new(idx: FrameIdx, memory: StackView<'frame, Async, Dynamic>) -> Self {
+        AsyncFrame {
+            idx,
+            tail: idx,
+            memory,
+            len: 0,
+        }
+    }
+
     pub(crate) unsafe fn nested_frame<'nested>(
         &'nested mut self,
+        site: FrameSite,
     ) -> JlrsResult<DynamicFrame<'nested, Async>> {
-        let idx = self.memory.new_frame()?;
-        Ok(DynamicFrame {
-            idx,
-            memory: self.memory.nest_dynamic(),
-            len: 0,
-        })
+        let mut memory = self.memory.nest_dynamic();
+        let idx = memory.new_frame()?;
+        Ok(DynamicFrame::new(idx, memory, site))
     }
 }
 
@@ -0,0 +1,343 @@
+//! Interrupt running Julia code from another thread.
+//!
+//! A Julia function that never returns blocks the thread it's called from, in the case of
+//! [`AsyncJulia`] this is the thread that runs the async runtime. An [`InterruptHandle`] can be
+//! acquired with [`Julia::interrupt_handle`] or [`AsyncJulia::interrupt_handle`]. It can be
+//! cloned and sent to other threads, [`InterruptHandle::interrupt`] throws an
+//! `InterruptException` in the Julia code that has been called from Rust and is currently
+//! running. The interrupted call returns this exception like any other exception that's thrown
+//! by Julia, its [`ExceptionKind`] is `ExceptionKind::InterruptException`.
+//!
+//! A call from the thread that initialized Julia is interrupted the same way as when Ctrl-C is
+//! pressed in the REPL: a `SIGINT` is sent to Julia's signal handling thread, and Julia throws
+//! the exception in the running code at its next safepoint. A safepoint is reached whenever
+//! memory is allocated or the code waits, so CPU-bound code is interrupted as well as code that
+//! sleeps or waits for IO. Only a tight loop that neither allocates nor waits can't be
+//! interrupted. Signals are deferred on this thread while no call is running, an interrupt that
+//! arrives when nothing is running is dropped rather than thrown in a later call. Julia is
+//! configured to throw an `InterruptException` instead of exiting when it receives a `SIGINT`,
+//! and at most one signal is sent every 500 milliseconds. Interrupting a call this way is only
+//! supported on Linux.
+//!
+//! Julia can only deliver signals to the thread that initialized it, the tasks that have been
+//! started with [`Value::call_async`] run on other threads. These tasks are interrupted by
+//! scheduling the exception in them, which happens when they're waiting, eg for IO, a `Task` or
+//! by calling `sleep`.
+//!
+//! [`AsyncJulia`]: ../multitask/struct.AsyncJulia.html
+//! [`InterruptHandle`]: struct.InterruptHandle.html
+//! [`InterruptHandle::interrupt`]: struct.InterruptHandle.html#method.interrupt
+//! [`Julia::interrupt_handle`]: ../struct.Julia.html#method.interrupt_handle
+//! [`AsyncJulia::interrupt_handle`]: ../multitask/struct.AsyncJulia.html#method.interrupt_handle
+//! [`Value::call_async`]: ../value/struct.Value.html#method.call_async
+//! [`ExceptionKind`]: ../error/enum.ExceptionKind.html
+
+use crate::error::{JlrsError, JlrsResult};
+use crate::global::Global;
+use crate::persistent;
+use crate::value::module::Module;
+use jl_sys::{
+    jl_call, jl_call0, jl_call1, jl_call2, jl_call3, jl_eval_string, jl_exception_occurred,
+    jl_function_t, jl_interrupt_exception, jl_method_instance_t, jl_typeof, jl_value_t,
+    jlrs_disable_interrupts, jlrs_discard_interrupt, jlrs_enable_interrupts, jlrs_invoke,
+};
+use std::ffi::c_void;
+use std::marker::PhantomData;
+use std::os::raw::{c_char, c_int};
+use std::ptr::null_mut;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// The `uv_async_t` handle of the `AsyncCondition` that interrupts the waiting tasks started with
+// `Value::call_async` and `uv_async_send`, the only libuv function that can be called from any
+// thread.
+static CONDITION: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+static UV_ASYNC_SEND: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+
+// Set once signals are deferred on the thread that initialized Julia.
+static SIGNALS_DEFERRED: AtomicBool = AtomicBool::new(false);
+
+// Julia forces an interrupt that ignores deferred signals if several are received in quick
+// succession, so at most one signal is sent per interval.
+const MIN_SIGNAL_INTERVAL: Duration = Duration::from_millis(500);
+
+// How long a call that has returned waits for a signal that hasn't been handled yet.
+const SIGNAL_TIMEOUT: Duration = Duration::from_millis(100);
+
+// The calls into Julia that are running on the thread that initialized it.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+struct Calls {
+    depth: usize,
+    signalled: bool,
+    last_signal: Option<Instant>,
+    // The threads started by Julia that block `SIGINT`, one of them handles it.
+    targets: Vec<i32>,
+}
+
+static CALLS: Mutex<Calls> = Mutex::new(Calls {
+    depth: 0,
+    signalled: false,
+    last_signal: None,
+    targets: Vec::new(),
+});
+
+fn calls() -> MutexGuard<'static, Calls> {
+    CALLS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A handle that can be used to interrupt the Julia code that is currently running. See the
+/// [`interrupt`] module for more information.
+///
+/// [`interrupt`]: index.html
+#[derive(Clone, Debug)]
+pub struct InterruptHandle {
+    _private: PhantomData<()>,
+}
+
+impl InterruptHandle {
+    pub(crate) fn new() -> Self {
+        InterruptHandle {
+            _private: PhantomData,
+        }
+    }
+
+    /// Throw an `InterruptException` in the Julia code that has been called from Rust and is
+    /// currently running. If no Julia code is running the interrupt is dropped. Returns an error
+    /// if Julia hasn't been initialized yet, or if it can't be notified.
+    pub fn interrupt(&self) -> JlrsResult<()> {
+        let condition = CONDITION.load(Ordering::Acquire);
+        let send = UV_ASYNC_SEND.load(Ordering::Acquire);
+        if condition.is_null() || send.is_null() {
+            Err(JlrsError::Other("Julia has not been initialized".into()))?;
+        }
+
+        unsafe {
+            let send = std::mem::transmute::<*mut c_void, unsafe extern "C" fn(*mut c_void) -> c_int>(
+                send,
+            );
+            if send(condition) != 0 {
+                Err(JlrsError::Other(
+                    "Could not notify Julia's event loop".into(),
+                ))?;
+            }
+        }
+
+        signal_call()
+    }
+}
+
+// Send a `SIGINT` to Julia if a call is running on the thread that initialized it.
+#[cfg(target_os = "linux")]
+fn signal_call() -> JlrsResult<()> {
+    let mut calls = calls();
+    if calls.depth == 0 {
+        return Ok(());
+    }
+
+    if let Some(last_signal) = calls.last_signal {
+        if last_signal.elapsed() < MIN_SIGNAL_INTERVAL {
+            return Ok(());
+        }
+    }
+
+    let mut sent = false;
+    unsafe {
+        let pid = libc::getpid();
+        for &tid in calls.targets.iter() {
+            sent |= libc::syscall(libc::SYS_tgkill, pid, tid, libc::SIGINT) == 0;
+        }
+    }
+
+    if !sent {
+        Err(JlrsError::other(std::io::Error::last_os_error()))?;
+    }
+
+    calls.signalled = true;
+    calls.last_signal = Some(Instant::now());
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn signal_call() -> JlrsResult<()> {
+    Ok(())
+}
+
+// Returns the threads of this process. The threads Julia starts while it's initialized are
+// found by comparing the threads before and after initialization.
+#[cfg(target_os = "linux")]
+pub(crate) fn threads() -> Vec<i32> {
+    std::fs::read_dir("/proc/self/task")
+        .map(|tasks| {
+            tasks
+                .filter_map(|task| task.ok()?.file_name().to_str()?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn threads() -> Vec<i32> {
+    Vec::new()
+}
+
+// Julia handles `SIGINT` in a thread that waits for it, which requires blocking it. A signal that
+// is sent to a thread that doesn't block it terminates the process, so only threads that have
+// been started by Julia and block `SIGINT` are targeted.
+#[cfg(target_os = "linux")]
+pub(crate) fn set_signal_targets(before: Vec<i32>) {
+    let blocks_sigint = |tid: i32| {
+        std::fs::read_to_string(format!("/proc/self/task/{}/status", tid))
+            .ok()
+            .and_then(|status| {
+                let mask = status
+                    .lines()
+                    .find_map(|line| line.strip_prefix("SigBlk:"))?;
+                u64::from_str_radix(mask.trim(), 16).ok()
+            })
+            .is_some_and(|mask| mask & (1 << (libc::SIGINT - 1)) != 0)
+    };
+
+    calls().targets = threads()
+        .into_iter()
+        .filter(|&tid| !before.contains(&tid) && blocks_sigint(tid))
+        .collect();
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn set_signal_targets(_before: Vec<i32>) {}
+
+// Create the condition that interrupts the tasks started with `Value::call_async` and defer
+// signals on the current thread, this must be called from the thread that initialized Julia.
+// The results are pointers that are converted immediately, so they don't need to be rooted.
+pub(crate) unsafe fn init_interrupt(global: Global) -> JlrsResult<()> {
+    let jlrs = Module::main(global).submodule("Jlrs")?;
+    let condition = jlrs
+        .function("initinterrupt")?
+        .call_unprotected::<_, ()>(global, &mut [])
+        .map_err(|e| JlrsError::Other(format!("initinterrupt threw {}", e.type_name()).into()))?
+        .cast::<*mut c_void>()?;
+
+    let send = jlrs
+        .function("uvasyncsend")?
+        .call_unprotected::<_, ()>(global, &mut [])
+        .map_err(|e| JlrsError::Other(format!("uvasyncsend threw {}", e.type_name()).into()))?
+        .cast::<*mut c_void>()?;
+
+    CONDITION.store(condition, Ordering::Release);
+    UV_ASYNC_SEND.store(send, Ordering::Release);
+
+    #[cfg(target_os = "linux")]
+    {
+        jl_sys::jl_exit_on_sigint(0);
+        jlrs_disable_interrupts();
+        SIGNALS_DEFERRED.store(true, Ordering::Release);
+    }
+
+    Ok(())
+}
+
+// Make a call into Julia that catches exceptions. If it's made from the thread that initialized
+// Julia, signals are allowed while the outermost call is running so it can be interrupted. When
+// it returns they're deferred again and a signal that's still pending is discarded.
+unsafe fn interruptible<F>(call: F) -> *mut jl_value_t
+where
+    F: FnOnce() -> *mut jl_value_t,
+{
+    if !SIGNALS_DEFERRED.load(Ordering::Acquire) || !persistent::is_julia_thread() {
+        return call();
+    }
+
+    // Only this thread changes the depth, signals are allowed before a signal can be sent.
+    let outermost = calls().depth == 0;
+    if outermost {
+        jlrs_enable_interrupts();
+    }
+    calls().depth += 1;
+
+    let res = call();
+
+    let signalled = {
+        let mut calls = calls();
+        calls.depth -= 1;
+        if outermost {
+            std::mem::replace(&mut calls.signalled, false)
+        } else {
+            false
+        }
+    };
+
+    if outermost {
+        // No signal is sent after the depth has been decreased, Rust code doesn't reach a
+        // safepoint so a pending signal can't be thrown before signals are deferred.
+        jlrs_disable_interrupts();
+
+        if signalled && !interrupted(res) {
+            // The signal hasn't been handled yet, wait for it so it's not thrown in a later call.
+            let start = Instant::now();
+            while jlrs_discard_interrupt() == 0 && start.elapsed() < SIGNAL_TIMEOUT {
+                thread::sleep(Duration::from_millis(1));
+            }
+        } else {
+            jlrs_discard_interrupt();
+        }
+    }
+
+    res
+}
+
+unsafe fn interrupted(res: *mut jl_value_t) -> bool {
+    if !res.is_null() {
+        return false;
+    }
+
+    let exc = jl_exception_occurred();
+    !exc.is_null() && jl_typeof(exc) == jl_typeof(jl_interrupt_exception)
+}
+
+pub(crate) unsafe fn call(
+    f: *mut jl_function_t,
+    args: *mut *mut jl_value_t,
+    nargs: i32,
+) -> *mut jl_value_t {
+    interruptible(|| jl_call(f, args, nargs))
+}
+
+pub(crate) unsafe fn call0(f: *mut jl_function_t) -> *mut jl_value_t {
+    interruptible(|| jl_call0(f))
+}
+
+pub(crate) unsafe fn call1(f: *mut jl_function_t, a: *mut jl_value_t) -> *mut jl_value_t {
+    interruptible(|| jl_call1(f, a))
+}
+
+pub(crate) unsafe fn call2(
+    f: *mut jl_function_t,
+    a: *mut jl_value_t,
+    b: *mut jl_value_t,
+) -> *mut jl_value_t {
+    interruptible(|| jl_call2(f, a, b))
+}
+
+pub(crate) unsafe fn call3(
+    f: *mut jl_function_t,
+    a: *mut jl_value_t,
+    b: *mut jl_value_t,
+    c: *mut jl_value_t,
+) -> *mut jl_value_t {
+    interruptible(|| jl_call3(f, a, b, c))
+}
+
+pub(crate) unsafe fn eval_string(cmd: *const c_char) -> *mut jl_value_t {
+    interruptible(|| jl_eval_string(cmd))
+}
+
+pub(crate) unsafe fn invoke(
+    f: *mut jl_value_t,
+    args: *mut *mut jl_value_t,
+    nargs: u32,
+    mi: *mut jl_method_instance_t,
+) -> *mut jl_value_t {
+    interruptible(|| jlrs_invoke(f, args, nargs, mi))
+}
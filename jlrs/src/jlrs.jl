@@ -1,5 +1,7 @@
 module Jlrs
 using Base.StackTraces
+import Base.CoreLogging: AbstractLogger, LogLevel, global_logger, handle_message, shouldlog,
+    min_enabled_level, catch_exceptions
 
 struct TracedException
     exc
@@ -8,11 +10,59 @@ end
 
 const wakerust = Ref{Ptr{Cvoid}}(C_NULL)
 const droparray = Ref{Ptr{Cvoid}}(C_NULL)
+const runfinalizer = Ref{Ptr{Cvoid}}(C_NULL)
+const callrust = Ref{Ptr{Cvoid}}(C_NULL)
+const writerust = Ref{Ptr{Cvoid}}(C_NULL)
+const logrust = Ref{Ptr{Cvoid}}(C_NULL)
+const gcstart = Ref{Ptr{Cvoid}}(C_NULL)
+const gcend = Ref{Ptr{Cvoid}}(C_NULL)
+const accountbytes = Ref{Ptr{Cvoid}}(C_NULL)
+
+# The lock is held while the sink is used, so it can be freed once the stream has been restored.
+mutable struct RustIO <: IO
+    sink::Ptr{Cvoid}
+    fallback::IO
+    lock::ReentrantLock
+end
+
+RustIO(sink::Ptr{Cvoid}, fallback::IO) = RustIO(sink, fallback, ReentrantLock())
+
+const redirects = Dict{Tuple{Symbol,Ptr{Cvoid}},RustIO}()
+
+# The lock is held while the logger is used, so it can be freed once it has been uninstalled.
+mutable struct RustLogger <: AbstractLogger
+    logger::Ptr{Cvoid}
+    min_level::LogLevel
+    previous::AbstractLogger
+    lock::ReentrantLock
+end
+
+RustLogger(logger::Ptr{Cvoid}, min_level::LogLevel, previous::AbstractLogger) =
+    RustLogger(logger, min_level, previous, ReentrantLock())
+
+const loggers = Dict{Ptr{Cvoid},RustLogger}()
+
+mutable struct OpaqueRust{Name,Id}
+    ptr::Ptr{Cvoid}
+    drop::Ptr{Cvoid}
+end
+
+const roots = Dict{UInt,Any}()
+const rootslock = ReentrantLock()
+
+const interrupttargets = Set{Task}()
+const interruptlock = ReentrantLock()
+const interruptcondition = Ref{Any}(nothing)
+
+const pkgid = Base.PkgId(Base.UUID("44cfe95a-1eb2-52ea-b672-e2afdf69b78f"), "Pkg")
 
 function runasync(func::Function, wakeptr::Ptr{Cvoid}, args...)::Any
+    task = current_task()
+    lock(() -> push!(interrupttargets, task), interruptlock)
     try
         func(args...)
     finally
+        lock(() -> delete!(interrupttargets, task), interruptlock)
         ccall(wakerust[], Cvoid, (Ptr{Cvoid},), wakeptr)
     end
 end
@@ -22,6 +72,29 @@ function asynccall(func::Function, wakeptr::Ptr{Cvoid}, args...)::Task
     Base.Threads.@spawn runasync(func, wakeptr, args...)
 end
 
+# Called by the event loop after a Rust thread has signalled the interrupt condition. The tasks
+# started by `asynccall` run on other threads than the one Rust calls into Julia from, so they
+# can't be interrupted with a signal. A task that's running can't be interrupted safely, only
+# tasks that are waiting are.
+function interrupttasks()::Nothing
+    lock(interruptlock) do
+        for task in interrupttargets
+            if task.queue !== nothing && !istaskdone(task)
+                schedule(task, InterruptException(); error=true)
+            end
+        end
+    end
+    nothing
+end
+
+function initinterrupt()::Ptr{Cvoid}
+    condition = Base.AsyncCondition(_ -> interrupttasks())
+    interruptcondition[] = condition
+    condition.handle
+end
+
+uvasyncsend()::Ptr{Cvoid} = cglobal(:uv_async_send)
+
 function tracingcall(func::Function)::Function
     function wrapper(args...)
         try
@@ -51,8 +124,281 @@ function attachstacktrace(func::Function)::Function
     wrapper
 end
 
+function framemodule(frame::StackFrame)::Union{Nothing,String}
+    linfo = frame.linfo
+    if linfo isa Core.MethodInstance
+        def = linfo.def
+        def isa Method && return string(def.module)
+        def isa Module && return string(def)
+    end
+
+    nothing
+end
+
+function stackframeinfo(st::StackTrace)
+    n = length(st)
+    funcs = Vector{String}(undef, n)
+    files = Vector{String}(undef, n)
+    lines = Vector{Int}(undef, n)
+    inlined = Vector{Bool}(undef, n)
+    modules = Vector{Union{Nothing,String}}(undef, n)
+
+    for (i, frame) in enumerate(st)
+        funcs[i] = string(frame.func)
+        files[i] = string(frame.file)
+        lines[i] = frame.line
+        inlined[i] = frame.inlined
+        modules[i] = framemodule(frame)
+    end
+
+    (funcs, files, lines, inlined, modules)
+end
+
+function exceptioninfo(exc)
+    backtrace = nothing
+    if exc isa TracedException
+        backtrace = stackframeinfo(exc.stacktrace)
+        exc = exc.exc
+    end
+
+    message = try
+        sprint(showerror, exc)
+    catch
+        string(typeof(exc))
+    end
+
+    details = Any[]
+    if exc isa MethodError
+        push!(details, sprint(show, exc.f), string(exc.world))
+        for arg in exc.args
+            push!(details, string(typeof(arg)))
+        end
+    elseif exc isa BoundsError
+        push!(details, isdefined(exc, :a) ? summary(exc.a) : nothing)
+        push!(details, isdefined(exc, :i) ? sprint(show, exc.i) : nothing)
+    elseif exc isa UndefVarError
+        push!(details, string(exc.var))
+    end
+
+    # A Vector{Any} is returned rather than a tuple because a tuple would store `nothing` and
+    # singleton exceptions inline.
+    Any[exc, message, details, backtrace]
+end
+
+function Base.unsafe_write(io::RustIO, p::Ptr{UInt8}, n::UInt)
+    lock(io.lock)
+    try
+        if io.sink != C_NULL
+            @assert writerust[] != C_NULL "writerust is null"
+            return ccall(writerust[], Csize_t, (Ptr{Cvoid}, Ptr{UInt8}, Csize_t), io.sink, p, n)
+        end
+    finally
+        unlock(io.lock)
+    end
+
+    unsafe_write(io.fallback, p, n)
+end
+
+Base.write(io::RustIO, b::UInt8) = unsafe_write(io, Ref(b), 1)
+Base.isopen(io::RustIO) = true
+Base.flush(io::RustIO) = nothing
+Base.displaysize(io::RustIO) = displaysize(io.fallback)
+Base.get(io::RustIO, key, default) = get(io.fallback, key, default)
+
+function redirectoutput(stream::Symbol, sink::Ptr{Cvoid})::Nothing
+    current = getfield(Base, stream)
+    io = RustIO(sink, current)
+    redirects[(stream, sink)] = io
+    Core.eval(Base, Expr(:(=), stream, io))
+    nothing
+end
+
+function restoreoutput(stream::Symbol, sink::Ptr{Cvoid})::Nothing
+    io = pop!(redirects, (stream, sink))
+    lock(() -> io.sink = C_NULL, io.lock)
+    if getfield(Base, stream) === io
+        Core.eval(Base, Expr(:(=), stream, io.fallback))
+    end
+    nothing
+end
+
+min_enabled_level(logger::RustLogger) = logger.min_level
+shouldlog(logger::RustLogger, level, _module, group, id) = true
+catch_exceptions(logger::RustLogger) = true
+
+function handle_message(logger::RustLogger, level, message, _module, group, id, file, line;
+                        kwargs...)
+    @assert logrust[] != C_NULL "logrust is null"
+    keys = String[string(k) for (k, _) in kwargs]
+    values = String[string(v) for (_, v) in kwargs]
+    modname = _module === nothing ? "" : string(_module)
+    filename = file === nothing ? "" : string(file)
+    lineno = line isa Integer ? Int(line) : 0
+    msg = string(message)
+    lvl = Int32(convert(LogLevel, level).level)
+
+    lock(logger.lock)
+    try
+        if logger.logger != C_NULL
+            ccall(logrust[], Cvoid,
+                  (Ptr{Cvoid}, Int32, Any, Any, Any, Int, Any, Any),
+                  logger.logger, lvl, msg, modname, filename, lineno, keys, values)
+            return nothing
+        end
+    finally
+        unlock(logger.lock)
+    end
+
+    handle_message(logger.previous, level, message, _module, group, id, file, line; kwargs...)
+end
+
+function installlogger(logger::Ptr{Cvoid}, level::Int32)::Nothing
+    rustlogger = RustLogger(logger, LogLevel(level), global_logger())
+    loggers[logger] = rustlogger
+    global_logger(rustlogger)
+    nothing
+end
+
+function uninstalllogger(logger::Ptr{Cvoid})::Nothing
+    rustlogger = pop!(loggers, logger)
+    lock(() -> rustlogger.logger = C_NULL, rustlogger.lock)
+    if global_logger() === rustlogger
+        global_logger(rustlogger.previous)
+    end
+    nothing
+end
+
+function root(id::UInt, value::Any)::Nothing
+    lock(rootslock) do
+        roots[id] = value
+    end
+    nothing
+end
+
+function unroot(id::UInt)::Nothing
+    lock(rootslock) do
+        delete!(roots, id)
+    end
+    nothing
+end
+
+# Returns false if `path` is neither a project file nor a directory that contains one.
+function activateproject(path::String)::Bool
+    path = abspath(path)
+    if isdir(path)
+        isfile(joinpath(path, "JuliaProject.toml")) || isfile(joinpath(path, "Project.toml")) ||
+            return false
+    elseif !isfile(path)
+        return false
+    end
+
+    Base.ACTIVE_PROJECT[] = path
+    true
+end
+
+function missingpackages(Pkg::Module)::Vector{String}
+    notfound = String[]
+    for (name, uuid) in Pkg.project().dependencies
+        if Base.locate_package(Base.PkgId(uuid, name)) === nothing
+            push!(notfound, name)
+        end
+    end
+
+    sort!(notfound)
+end
+
+function instantiateoffline()::Vector{String}
+    Pkg = Base.require(pkgid)
+    Base.invokelatest() do
+        offline = Pkg.OFFLINE_MODE[]
+        Pkg.offline(true)
+        try
+            notfound = missingpackages(Pkg)
+            isempty(notfound) && Pkg.instantiate()
+            notfound
+        finally
+            Pkg.offline(offline)
+        end
+    end
+end
+
+function gcstats()::NTuple{9,Int64}
+    num = Base.gc_num()
+    (Base.gc_total_bytes(num) % Int64, Base.gc_live_bytes() % Int64, num.malloc % Int64,
+        num.realloc % Int64, num.poolalloc % Int64, num.bigalloc % Int64, num.pause % Int64,
+        num.full_sweep % Int64, num.total_time % Int64)
+end
+
+function enablegccallbacks()::Nothing
+    @assert gcstart[] != C_NULL "gcstart is null"
+    @assert gcend[] != C_NULL "gcend is null"
+    ccall(:jl_gc_set_cb_pre_gc, Cvoid, (Ptr{Cvoid}, Cint), gcstart[], 1)
+    ccall(:jl_gc_set_cb_post_gc, Cvoid, (Ptr{Cvoid}, Cint), gcend[], 1)
+    nothing
+end
+
+function setheapsizehint(bytes::UInt64)::Bool
+    try
+        ccall(:jl_gc_set_max_memory, Cvoid, (UInt64,), bytes)
+        true
+    catch
+        false
+    end
+end
+
 function clean(a::Array)
     @assert droparray[] != C_NULL "droparray is null"
     ccall(droparray[], Cvoid, (Array,), a)
 end
+
+function definefunction(mod::Module, name::Symbol, func::Ptr{Cvoid})::Function
+    @assert callrust[] != C_NULL "callrust is null"
+    trampoline = callrust[]
+    Core.eval(mod, :(function $name(args...)
+        ccall($trampoline, Any, (Ptr{Cvoid}, Vector{Any}), $func, Any[args...])
+    end))
+end
+
+function methodinstance(@nospecialize(f), types::DataType...)::Core.MethodInstance
+    argtypes = Tuple{types...}
+    tt = Base.signature_type(f, argtypes)
+    m = which(f, argtypes)
+    (ti, env) = ccall(:jl_type_intersection_with_env, Any, (Any, Any), tt, m.sig)::Core.SimpleVector
+    Core.Compiler.specialize_method(m, ti, env)
+end
+
+function cfunction(@nospecialize(f), rettype::DataType, argtypes::DataType...)::Ptr{Cvoid}
+    tt = Tuple{argtypes...}
+    rettypes = Base.return_types(f, tt)
+    isempty(rettypes) && error("No method of $f matches $tt")
+    for rt in rettypes
+        rt <: rettype || error("The return type of $f for $tt is $rt, not $rettype")
+    end
+
+    Core.eval(Jlrs, :(@cfunction($f, $rettype, ($(argtypes...),))))
+end
+
+function addrustfinalizer(value::Any, id::UInt)::Nothing
+    @assert runfinalizer[] != C_NULL "runfinalizer is null"
+    finalizer(value) do _
+        ccall(runfinalizer[], Cvoid, (UInt,), id)
+    end
+    nothing
+end
+
+function newopaque(name::String, id::UInt64, ptr::Ptr{Cvoid}, drop::Ptr{Cvoid})::OpaqueRust
+    finalizer(dropopaque, OpaqueRust{Symbol(name),id}(ptr, drop))
+end
+
+function dropopaque(opaque::OpaqueRust)::Nothing
+    ptr = opaque.ptr
+    opaque.ptr = C_NULL
+    ptr != C_NULL && ccall(opaque.drop, Cvoid, (Ptr{Cvoid},), ptr)
+    nothing
+end
+
+function unaccount(a::Array)
+    @assert accountbytes[] != C_NULL "accountbytes is null"
+    ccall(accountbytes[], Cvoid, (Int,), -sizeof(a))
+end
 end
@@ -1,9 +1,9 @@
 //! A `Future` that represents a function call in Julia running on another thread.
 
-use crate::error::{exception, JlrsResult};
+use crate::error::{JlrsError, JlrsResult};
 use crate::frame::AsyncFrame;
 use crate::global::Global;
-use crate::traits::Frame;
+use crate::traits::{Frame, IntoJlrsResult};
 use crate::value::module::Module;
 use crate::value::task::Task;
 use crate::value::{CallResult, Value};
@@ -68,16 +68,14 @@ impl<'frame, 'data> JuliaFuture<'frame, 'data> {
                 .submodule("Jlrs")?
                 .function("asynccall")?
                 .call(frame, &mut vals)?
-                .map_err(|e| {
-                    exception::<()>(format!("asynccall threw an exception: {:?}", e)).unwrap_err()
-                })?
+                .into_jlrs_result(frame)?
                 .cast_unchecked::<Task>();
 
             {
                 let locked = shared_state.lock();
                 match locked {
                     Ok(mut data) => data.task = Some(task),
-                    _ => exception("Cannot set task".into())?,
+                    _ => Err(JlrsError::Other("Cannot set task".into()))?,
                 }
             }
 
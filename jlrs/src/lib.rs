@@ -19,6 +19,14 @@
 //!  - Structs that can be mapped to Rust include those with type parameters and bits unions.
 //!  - Use these features when calling Rust from Julia through `ccall`.
 //!  - Offload long-running functions to another thread and `.await` the result with the (experimental) async runtime.
+//!  - Forward records logged by Julia to Rust, or to the `log` and `tracing` crates by enabling the
+//!    `log` and `tracing` features.
+//!  - Activate projects, configure the load and depot paths, and instantiate projects offline.
+//!  - Keep values alive independently of frames.
+//!  - Wrap arbitrary Rust data as opaque Julia values.
+//!  - Expose Rust closures to Julia as functions.
+//!  - Find frames whose capacity is too small or too large by enabling the `gc-diagnostics`
+//!    feature.
 //!
 //!
 //! # Generating the bindings
@@ -68,8 +76,9 @@
 //! [`Julia::init`]. Note that this method can only be called once, if you drop [`Julia`] you won't
 //! be able to create a new one and have to restart the entire program. If you want to use a
 //! custom system image, you must call [`Julia::init_with_image`] instead of [`Julia::init`].
-//! If you're calling Rust from Julia everything has already been initialized, you can use `CCall`
-//! instead.
+//! If you want to set other options, like the number of threads Julia can use or the optimization
+//! level, you can use a [`JuliaBuilder`]. If you're calling Rust from Julia everything has
+//! already been initialized, you can use `CCall` instead.
 //!
 //! ## Calling Julia from Rust
 //!
@@ -332,6 +341,7 @@
 //! [`Julia`]: struct.Julia.html
 //! [`CCall`]: struct.CCall.html
 //! [`Julia::init`]: struct.Julia.html#method.init
+//! [`JuliaBuilder`]: builder/struct.JuliaBuilder.html
 //! [`Julia::init_with_image`]: struct.Julia.html#method.init_with_image
 //! [`Julia::include`]: struct.Julia.html#method.include
 //! [`Julia::frame`]: struct.Julia.html#method.frame
@@ -361,37 +371,55 @@
 //! [the instructions for compiling Julia on Windows using Cygwin and MinGW]: https://github.com/JuliaLang/julia/blob/v1.5.2/doc/build/windows.md#cygwin-to-mingw-cross-compiling
 //! [the examples directory of the repo]: https://github.com/Taaitaaiger/jlrs/tree/v0.8/examples
 
+pub mod builder;
+#[cfg(feature = "gc-diagnostics")]
+pub mod diagnostics;
 pub mod error;
 pub mod frame;
 pub mod global;
+pub mod interrupt;
 #[doc(hidden)]
 pub mod jl_sys_export;
 #[cfg(all(feature = "async", target_os = "linux"))]
 pub mod julia_future;
+pub mod logging;
 pub mod mode;
 #[cfg(all(feature = "async", target_os = "linux"))]
 pub mod multitask;
+pub mod persistent;
 pub mod prelude;
+pub mod project;
 mod stack;
+pub mod stdio;
 pub mod traits;
 #[doc(hidden)]
 pub mod util;
 pub mod value;
 
-use error::{JlrsError, JlrsResult};
-use frame::{DynamicFrame, NullFrame, StaticFrame};
+use builder::{IgnoredOption, JuliaBuilder};
+use error::{catch_panic, panic_message, JlrsError, JlrsResult};
+use frame::{DynamicFrame, FrameSite, NullFrame, StaticFrame};
 use global::Global;
-use jl_sys::{jl_atexit_hook, jl_init, jl_init_with_image__threading, jl_is_initialized};
+use interrupt::InterruptHandle;
+use jl_sys::{jl_atexit_hook, jl_error, jl_throw};
+use logging::{InstalledLogger, Logger};
 use mode::Sync;
 use stack::{Dynamic, RawStack, StackView, Static};
-use std::ffi::{c_void, CString};
-use std::io::{Error as IOError, ErrorKind};
-use std::mem::MaybeUninit;
+use std::alloc::{dealloc, Layout};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::ffi::c_void;
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::Path;
 use std::ptr::null_mut;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use stdio::{CapturedOutput, Redirects, SharedBuffer, Stream};
+use traits::{gc, Frame, IntoJlrsResult};
 use value::array::Array;
-use value::module::Module;
+use value::datatype::DataType;
+use value::module::{self, Module};
 use value::Value;
 
 pub(crate) static INIT: AtomicBool = AtomicBool::new(false);
@@ -407,6 +435,8 @@ pub(crate) static JLRS_JL: &'static str = include_str!("jlrs.jl");
 /// [`Julia::init_with_image`]: struct.Julia.html#method.init_with_image
 pub struct Julia {
     stack: RawStack,
+    redirects: Redirects,
+    logger: InstalledLogger,
 }
 
 impl Julia {
@@ -414,42 +444,27 @@ impl Julia {
     /// will return an error. If this struct is dropped, you will need to restart your program to
     /// be able to call Julia code again.
     ///
-    /// You have to choose a stack size when calling this function. This will be the total number
-    /// of slots that will be available for the GC stack. One of these slots will always be in
-    /// use. Each frame needs two slots of overhead, plus one for every value created with that
+    /// You have to choose a stack size when calling this function. This will be the initial
+    /// number of slots that will be available for the GC stack. One of these slots will always be
+    /// in use. Each frame needs two slots of overhead, plus one for every value created with that
     /// frame. A [`StaticFrame`] preallocates its slots, while a [`DynamicFrame`] grows to the
     /// required size. If calling a method requires one or more slots, this amount is explicitly
-    /// documented.
+    /// documented. If the stack runs out of slots it grows, frames that are in use are never
+    /// moved. The size of the stack is unlimited unless a maximum has been set with
+    /// [`JuliaBuilder::max_stack_size`].
+    ///
+    /// If you need to set other options, like the number of threads or the optimization level,
+    /// you can use a [`JuliaBuilder`] instead.
     ///
     /// This function is unsafe because this crate provides you with a way to execute arbitrary
     /// Julia code which can't be checked for correctness.
     ///
     /// [`StaticFrame`]: frame/struct.StaticFrame.html
     /// [`DynamicFrame`]: frame/struct.DynamicFrame.html
+    /// [`JuliaBuilder`]: builder/struct.JuliaBuilder.html
+    /// [`JuliaBuilder::max_stack_size`]: builder/struct.JuliaBuilder.html#method.max_stack_size
     pub unsafe fn init(stack_size: usize) -> JlrsResult<Self> {
-        if jl_is_initialized() != 0 || INIT.swap(true, Ordering::SeqCst) {
-            return Err(JlrsError::AlreadyInitialized.into());
-        }
-
-        jl_init();
-        let mut jl = Julia {
-            stack: RawStack::new(stack_size),
-        };
-
-        jl.frame(2, |global, frame| {
-            Value::eval_string(frame, JLRS_JL)?.expect("Could not load Jlrs module");
-
-            let droparray_fn = Value::new(frame, droparray as *mut c_void)?;
-            Module::main(global)
-                .submodule("Jlrs")?
-                .global("droparray")?
-                .set_nth_field(0, droparray_fn)?;
-
-            Ok(())
-        })
-        .expect("Could not load Jlrs module");
-
-        Ok(jl)
+        JuliaBuilder::new().stack_size(stack_size).init()
     }
 
     /// This function is similar to [`Julia::init`] except that it loads a custom system image. A
@@ -472,54 +487,35 @@ impl Julia {
         julia_bindir: P,
         image_path: P,
     ) -> JlrsResult<Self> {
-        if INIT.swap(true, Ordering::SeqCst) {
-            Err(JlrsError::AlreadyInitialized)?;
-        }
-
-        let julia_bindir_str = julia_bindir.as_ref().to_string_lossy().to_string();
-        let image_path_str = image_path.as_ref().to_string_lossy().to_string();
-
-        if !julia_bindir.as_ref().exists() {
-            let io_err = IOError::new(ErrorKind::NotFound, julia_bindir_str);
-            return Err(JlrsError::other(io_err))?;
-        }
-
-        if !image_path.as_ref().exists() {
-            let io_err = IOError::new(ErrorKind::NotFound, image_path_str);
-            return Err(JlrsError::other(io_err))?;
-        }
-
-        let bindir = CString::new(julia_bindir_str).unwrap();
-        let im_rel_path = CString::new(image_path_str).unwrap();
-
-        jl_init_with_image__threading(bindir.as_ptr(), im_rel_path.as_ptr());
-
-        let mut jl = Julia {
-            stack: RawStack::new(stack_size),
-        };
-
-        jl.frame(2, |global, frame| {
-            Value::eval_string(frame, JLRS_JL)?.expect("Could not load Jlrs module");
-
-            let droparray_fn = Value::new(frame, droparray as *mut c_void)?;
-            Module::main(global)
-                .submodule("Jlrs")?
-                .global("droparray")?
-                .set_nth_field(0, droparray_fn)?;
+        JuliaBuilder::new()
+            .stack_size(stack_size)
+            .image(julia_bindir, image_path)
+            .init()
+    }
 
-            Ok(())
-        })
-        .expect("Could not load Jlrs module");
+    /// Change the stack size to `stack_size`, the maximum stack size is left unchanged.
+    pub fn set_stack_size(&mut self, stack_size: usize) {
+        unsafe { self.stack = RawStack::new(stack_size, self.stack.max_size(), "julia") }
+    }
 
-        Ok(jl)
+    /// Returns a handle that can be used to interrupt Julia code that's running on this thread
+    /// from another thread. See the [`interrupt`] module for more information.
+    ///
+    /// [`interrupt`]: interrupt/index.html
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        InterruptHandle::new()
     }
 
-    /// Change the stack size to `stack_size`.
-    pub fn set_stack_size(&mut self, stack_size: usize) {
-        unsafe { self.stack = RawStack::new(stack_size) }
+    /// Returns the options of the [`JuliaBuilder`] that have been ignored because they couldn't
+    /// be applied after Julia has been initialized.
+    ///
+    /// [`JuliaBuilder`]: builder/struct.JuliaBuilder.html
+    pub fn ignored_options(&self) -> Vec<IgnoredOption> {
+        builder::ignored_options()
     }
 
-    /// Returns the current stack size.
+    /// Returns the current stack size, this includes the slots that have been added because the
+    /// stack has grown.
     pub fn stack_size(&self) -> usize {
         self.stack.size()
     }
@@ -557,11 +553,203 @@ impl Julia {
         Err(JlrsError::IncludeNotFound(path.as_ref().to_string_lossy().into()).into())
     }
 
+    /// Calls `include_string` in the `Main` module in Julia, which evaluates `code` in that
+    /// module as if it were the contents of a file named `name`. Unlike [`Value::eval_string`],
+    /// the file name and line numbers are available in backtraces. Together with
+    /// [`include_julia_dir`] this can be used to load Julia code that's embedded in your
+    /// application. If an exception is thrown it's returned as `JlrsError::Exception`.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// # use jlrs::prelude::*;
+    /// # fn main() {
+    /// # let mut julia = unsafe { Julia::init(16).unwrap() };
+    /// let code = "module MyModule\nfoo() = error(\"bar\")\nend";
+    /// julia.include_string(code, "embedded/MyModule.jl").unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`Value::eval_string`]: value/struct.Value.html#method.eval_string
+    /// [`include_julia_dir`]: prelude/macro.include_julia_dir.html
+    pub fn include_string<C: AsRef<str>, N: AsRef<str>>(
+        &mut self,
+        code: C,
+        name: N,
+    ) -> JlrsResult<()> {
+        self.frame(3, |global, frame| {
+            let code_jl_str = Value::new(frame, code.as_ref())?;
+            let name_jl_str = Value::new(frame, name.as_ref())?;
+            let include_string_func = Module::base(global).function("include_string")?;
+            include_string_func
+                .call3(
+                    frame,
+                    Module::main(global).as_value(),
+                    code_jl_str,
+                    name_jl_str,
+                )?
+                .into_jlrs_result(frame)?;
+
+            Ok(())
+        })
+    }
+
+    /// Activate the project at `path`, which must be either a directory that contains a
+    /// `Project.toml` or `JuliaProject.toml`, or the path to a project file. Returns
+    /// `JlrsError::ProjectNotFound` if `path` doesn't exist or doesn't contain a project file.
+    /// See the [`project`] module for more information.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// # use jlrs::prelude::*;
+    /// # fn main() {
+    /// # let mut julia = unsafe { Julia::init(16).unwrap() };
+    /// julia.activate_project("MyProject").unwrap();
+    /// julia.instantiate_offline().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`project`]: project/index.html
+    pub fn activate_project<P: AsRef<Path>>(&mut self, path: P) -> JlrsResult<()> {
+        self.dynamic_frame(|_, frame| project::activate_project(frame, path))
+    }
+
+    /// Instantiate the active project without accessing the network. Returns
+    /// `JlrsError::MissingPackages` with the names of all dependencies that aren't installed if
+    /// the project can't be instantiated offline. See the [`project`] module for more
+    /// information.
+    ///
+    /// [`project`]: project/index.html
+    pub fn instantiate_offline(&mut self) -> JlrsResult<()> {
+        self.dynamic_frame(|_, frame| project::instantiate_offline(frame))
+    }
+
+    /// Redirect Julia's `stdout` or `stderr` to `writer`, or restore the original stream if
+    /// `writer` is `None`. The writer is used until it's replaced or this struct is dropped. See
+    /// the [`stdio`] module for more information.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// # use jlrs::prelude::*;
+    /// # use jlrs::stdio::{FnWriter, Stream};
+    /// # fn main() {
+    /// # let mut julia = unsafe { Julia::init(16).unwrap() };
+    /// let writer = FnWriter(|bytes: &[u8]| print!("[julia] {}", String::from_utf8_lossy(bytes)));
+    /// julia.redirect_output(Stream::Stdout, Some(Box::new(writer))).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`stdio`]: stdio/index.html
+    pub fn redirect_output(
+        &mut self,
+        stream: Stream,
+        writer: Option<Box<dyn Write + Send>>,
+    ) -> JlrsResult<()> {
+        unsafe { self.redirects.set(stream, writer) }
+    }
+
+    /// Install `logger` as Julia's global logger, or restore the previous global logger if
+    /// `logger` is `None`. The logger is used until it's replaced or this struct is dropped. See
+    /// the [`logging`] module for more information.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// # use jlrs::prelude::*;
+    /// # use jlrs::logging::{Logger, LogLevel};
+    /// # fn main() {
+    /// # let mut julia = unsafe { Julia::init(16).unwrap() };
+    /// let logger = Logger::new(LogLevel::Info, |record| {
+    ///     println!("[{}] {}: {}", record.level, record.module, record.message)
+    /// });
+    /// julia.set_logger(Some(logger)).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`logging`]: logging/index.html
+    pub fn set_logger(&mut self, logger: Option<Logger>) -> JlrsResult<()> {
+        unsafe { self.logger.set(logger) }
+    }
+
+    /// Create a [`StaticFrame`] that can hold `capacity` values and call the given closure while
+    /// everything that's written to Julia's `stdout` and `stderr` is captured. Returns the result
+    /// of the closure and the captured output. The original streams are restored before this
+    /// method returns, even if the closure returns an error.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// # use jlrs::prelude::*;
+    /// # fn main() {
+    /// # let mut julia = unsafe { Julia::init(16).unwrap() };
+    /// let (_, output) = julia.capture_output(1, |_global, frame| {
+    ///     Value::eval_string(frame, "println(\"Hello\")")?.unwrap();
+    ///     Ok(())
+    /// }).unwrap();
+    ///
+    /// assert_eq!(output.stdout, "Hello\n");
+    /// # }
+    /// ```
+    ///
+    /// [`StaticFrame`]: ../frame/struct.StaticFrame.html
+    pub fn capture_output<'base, 'julia: 'base, T, F>(
+        &'julia mut self,
+        capacity: usize,
+        func: F,
+    ) -> JlrsResult<(T, CapturedOutput)>
+    where
+        F: FnOnce(Global<'base>, &mut StaticFrame<'base, Sync>) -> JlrsResult<T>,
+    {
+        let stdout = SharedBuffer::default();
+        let stderr = SharedBuffer::default();
+        let stdout_sink: Box<stdio::Sink> = Box::new(Mutex::new(Box::new(stdout.clone())));
+        let stderr_sink: Box<stdio::Sink> = Box::new(Mutex::new(Box::new(stderr.clone())));
+
+        // If a stream can't be restored, Julia might still use its sink so it must not be dropped.
+        let mut retire = false;
+        let res = self.frame(capacity, |global, frame| unsafe {
+            stdio::redirect(global, Stream::Stdout, &stdout_sink)?;
+            if let Err(e) = stdio::redirect(global, Stream::Stderr, &stderr_sink) {
+                retire = stdio::restore(global, Stream::Stdout, &stdout_sink).is_err();
+                return Err(e);
+            }
+
+            // The streams must be restored if func panics.
+            let res = catch_panic(|| func(global, frame));
+
+            let restored_stderr = stdio::restore(global, Stream::Stderr, &stderr_sink);
+            let restored_stdout = stdio::restore(global, Stream::Stdout, &stdout_sink);
+            retire = restored_stderr.is_err() || restored_stdout.is_err();
+            restored_stdout?;
+            restored_stderr?;
+
+            res
+        });
+
+        if retire {
+            Box::leak(stdout_sink);
+            Box::leak(stderr_sink);
+        }
+
+        let res = res?;
+
+        let output = CapturedOutput {
+            stdout: stdout.take_string(),
+            stderr: stderr.take_string(),
+        };
+
+        Ok((res, output))
+    }
+
     /// Create a [`StaticFrame`] that can hold `capacity` values, and call the given closure.
     /// Returns the result of this closure, or an error if the new frame can't be created because
     /// there's not enough space on the GC stack. The number of required slots on the stack is
     /// `capacity + 2`.
     ///
+    /// If the closure panics the panic is caught and returned as `JlrsError::Panic`.
+    ///
     /// Every output and value you create inside the closure using the [`StaticFrame`], either
     /// directly or through calling a [`Value`], will reduce the available capacity of the
     /// [`StaticFrame`] by 1.
@@ -584,6 +772,7 @@ impl Julia {
     ///
     /// [`StaticFrame`]: ../frame/struct.StaticFrame.html
     /// [`Value`]: ../value/struct.Value.html
+    #[cfg_attr(feature = "gc-diagnostics", track_caller)]
     pub fn frame<'base, 'julia: 'base, T, F>(
         &'julia mut self,
         capacity: usize,
@@ -592,20 +781,23 @@ impl Julia {
     where
         F: FnOnce(Global<'base>, &mut StaticFrame<'base, Sync>) -> JlrsResult<T>,
     {
-        unsafe {
-            let d = self.stack.as_mut();
+        let site = FrameSite::caller();
+        catch_panic(move || unsafe {
+            let d = &mut self.stack;
             let global = Global::new();
             let mut view = StackView::<Sync, Static>::new(d);
             let frame_idx = view.new_frame(capacity)?;
-            let mut frame = StaticFrame::with_capacity(frame_idx, capacity, view);
+            let mut frame = StaticFrame::with_capacity(frame_idx, capacity, view, site);
             func(global, &mut frame)
-        }
+        })
     }
 
     /// Create a [`DynamicFrame`] and call the given closure. Returns the result of this closure,
     /// or an error if the new frame can't be created because the stack is too small. The number
     /// of required slots on the stack is 2.
     ///
+    /// If the closure panics the panic is caught and returned as `JlrsError::Panic`.
+    ///
     /// Every output and value you create inside the closure using the [`DynamicFrame`], either
     /// directly or through calling a [`Value`], will occupy a single slot on the GC stack.
     ///
@@ -627,23 +819,65 @@ impl Julia {
     ///
     /// [`DynamicFrame`]: ../frame/struct.DynamicFrame.html
     /// [`Value`]: ../value/struct.Value.html
+    #[cfg_attr(feature = "gc-diagnostics", track_caller)]
     pub fn dynamic_frame<'base, 'julia: 'base, T, F>(&'julia mut self, func: F) -> JlrsResult<T>
     where
         F: FnOnce(Global<'base>, &mut DynamicFrame<'base, Sync>) -> JlrsResult<T>,
     {
-        unsafe {
-            let d = self.stack.as_mut();
+        let site = FrameSite::caller();
+        catch_panic(move || unsafe {
+            let d = &mut self.stack;
             let global = Global::new();
             let mut view = StackView::<Sync, Dynamic>::new(d);
             let frame_idx = view.new_frame()?;
-            let mut frame = DynamicFrame::new(frame_idx, view);
+            let mut frame = DynamicFrame::new(frame_idx, view, site);
             func(global, &mut frame)
-        }
+        })
+    }
+
+    // Load the Jlrs module and set the pointers to the Rust functions it calls.
+    pub(crate) unsafe fn init_jlrs(&mut self) {
+        self.frame(9, |global, frame| {
+            Value::eval_string(frame, JLRS_JL)?.expect("Could not load Jlrs module");
+
+            set_jlrs_pointers(frame)?;
+
+            interrupt::init_interrupt(global)?;
+            Ok(())
+        })
+        .expect("Could not load Jlrs module");
+    }
+}
+
+// Set the pointers to the Rust functions that are called by the Jlrs module. This is shared by
+// the sync and async runtimes, one slot on the GC stack is used for every pointer.
+pub(crate) unsafe fn set_jlrs_pointers<'frame, F>(frame: &mut F) -> JlrsResult<()>
+where
+    F: Frame<'frame>,
+{
+    let jlrs = Module::main(frame.global()).submodule("Jlrs")?;
+    let pointers = [
+        ("droparray", droparray as *mut c_void),
+        ("runfinalizer", runfinalizer as *mut c_void),
+        ("callrust", module::call_rust_function as *mut c_void),
+        ("writerust", stdio::write_rust as *mut c_void),
+        ("logrust", logging::log_rust as *mut c_void),
+        ("gcstart", gc::gc_start as *mut c_void),
+        ("gcend", gc::gc_end as *mut c_void),
+        ("accountbytes", gc::account_bytes as *mut c_void),
+    ];
+
+    for &(name, pointer) in pointers.iter() {
+        let pointer = Value::new(frame, pointer)?;
+        jlrs.global(name)?.set_nth_field(0, pointer)?;
     }
+
+    Ok(())
 }
 
 impl Drop for Julia {
     fn drop(&mut self) {
+        persistent::set_julia_thread(false);
         unsafe {
             jl_atexit_hook(0);
         }
@@ -668,6 +902,7 @@ impl Drop for Julia {
 pub struct CCall {
     stack: Option<RawStack>,
     stack_size: usize,
+    max_stack_size: Option<usize>,
 }
 
 impl CCall {
@@ -678,9 +913,11 @@ impl CCall {
     ///
     /// [`Julia::init`]: struct.Julia.html#method.init
     pub unsafe fn new(stack_size: usize) -> Self {
+        persistent::set_julia_thread(true);
         CCall {
             stack: None,
             stack_size,
+            max_stack_size: None,
         }
     }
 
@@ -696,7 +933,7 @@ impl CCall {
     pub fn set_stack_size(&mut self, stack_size: usize) {
         self.stack_size = stack_size;
         if self.stack.is_some() {
-            unsafe { self.stack = Some(RawStack::new(stack_size)) }
+            unsafe { self.stack = Some(RawStack::new(stack_size, self.max_stack_size, "ccall")) }
         }
     }
 
@@ -710,12 +947,16 @@ impl CCall {
     /// there's not enough space on the GC stack. The number of required slots on the stack is
     /// `capacity + 2`.
     ///
+    /// If the closure panics the panic is caught and rethrown as a Julia `ErrorException`, the
+    /// stack is freed before the exception is thrown.
+    ///
     /// Every output and value you create inside the closure using the [`StaticFrame`], either
     /// directly or through calling a [`Value`], will reduce the available capacity of the
     /// [`StaticFrame`] by 1.
     ///
     /// [`StaticFrame`]: ../frame/struct.StaticFrame.html
     /// [`Value`]: ../value/struct.Value.html
+    #[cfg_attr(feature = "gc-diagnostics", track_caller)]
     pub fn frame<'base, 'julia: 'base, T, F>(
         &'julia mut self,
         capacity: usize,
@@ -724,17 +965,27 @@ impl CCall {
     where
         F: FnOnce(Global<'base>, &mut StaticFrame<'base, Sync>) -> JlrsResult<T>,
     {
-        unsafe {
-            self.ensure_init_stack()
+        // The closure must borrow self for 'julia, the pointer is only used again after it has
+        // returned.
+        let this = self as *mut Self;
+        let site = FrameSite::caller();
+        let res = panic::catch_unwind(AssertUnwindSafe(move || unsafe {
+            (&mut *this)
+                .ensure_init_stack()
                 .map(|s| {
-                    let d = s.as_mut();
+                    let d = s;
                     let global = Global::new();
                     let mut view = StackView::<Sync, Static>::new(d);
                     let frame_idx = view.new_frame(capacity)?;
-                    let mut frame = StaticFrame::with_capacity(frame_idx, capacity, view);
+                    let mut frame = StaticFrame::with_capacity(frame_idx, capacity, view, site);
                     func(global, &mut frame)
                 })
                 .unwrap_or_else(|| std::hint::unreachable_unchecked()) // The stack is guaranteed to be initialized
+        }));
+
+        match res {
+            Ok(res) => res,
+            Err(payload) => unsafe { (&mut *this).throw_panic(payload) },
         }
     }
 
@@ -742,26 +993,40 @@ impl CCall {
     /// or an error if the new frame can't be created because the stack is too small. The number
     /// of required slots on the stack is 2.
     ///
+    /// If the closure panics the panic is caught and rethrown as a Julia `ErrorException`, the
+    /// stack is freed before the exception is thrown.
+    ///
     /// Every output and value you create inside the closure using the [`DynamicFrame`], either
     /// directly or through calling a [`Value`], will occupy a single slot on the GC stack.
     ///
     /// [`DynamicFrame`]: ../frame/struct.DynamicFrame.html
     /// [`Value`]: ../value/struct.Value.html
+    #[cfg_attr(feature = "gc-diagnostics", track_caller)]
     pub fn dynamic_frame<'base, 'julia: 'base, T, F>(&'julia mut self, func: F) -> JlrsResult<T>
     where
         F: FnOnce(Global<'base>, &mut DynamicFrame<'base, Sync>) -> JlrsResult<T>,
     {
-        unsafe {
-            self.ensure_init_stack()
+        // The closure must borrow self for 'julia, the pointer is only used again after it has
+        // returned.
+        let this = self as *mut Self;
+        let site = FrameSite::caller();
+        let res = panic::catch_unwind(AssertUnwindSafe(move || unsafe {
+            (&mut *this)
+                .ensure_init_stack()
                 .map(|s| {
-                    let d = s.as_mut();
+                    let d = s;
                     let global = Global::new();
                     let mut view = StackView::<Sync, Dynamic>::new(d);
                     let frame_idx = view.new_frame()?;
-                    let mut frame = DynamicFrame::new(frame_idx, view);
+                    let mut frame = DynamicFrame::new(frame_idx, view, site);
                     func(global, &mut frame)
                 })
                 .unwrap_or_else(|| std::hint::unreachable_unchecked()) // The stack is guaranteed to be initialized
+        }));
+
+        match res {
+            Ok(res) => res,
+            Err(payload) => unsafe { (&mut *this).throw_panic(payload) },
         }
     }
 
@@ -769,23 +1034,117 @@ impl CCall {
     /// can only be used to (mutably) borrow array data. Unlike the other frame-creating methods,
     /// no `Global` is provided to the closure.
     ///
+    /// If the closure panics the panic is caught and rethrown as a Julia `ErrorException`.
+    ///
     /// [`NullFrame`]: ../frame/struct.NullFrame.html
     /// [`Global`]: ../global/struct.Global.html
     pub fn null_frame<'base, 'julia: 'base, T, F>(&'julia mut self, func: F) -> JlrsResult<T>
     where
         F: FnOnce(&mut NullFrame<'base>) -> JlrsResult<T>,
     {
-        unsafe {
-            let mut frame = NullFrame::new(self);
+        let this = self as *mut Self;
+        let res = panic::catch_unwind(AssertUnwindSafe(move || unsafe {
+            let mut frame = NullFrame::new(&mut *this);
             func(&mut frame)
+        }));
+
+        match res {
+            Ok(res) => res,
+            Err(payload) => unsafe { (&mut *this).throw_panic(payload) },
         }
     }
 
+    /// Throw a Julia exception and return to the caller of the function called with `ccall`. The
+    /// exception is created by the given closure which is called with a new [`DynamicFrame`]. If
+    /// the closure returns an error or panics, an `ErrorException` with the error message is
+    /// thrown instead.
+    ///
+    /// Julia uses `longjmp` to throw exceptions which jumps over every Rust frame between this
+    /// call and the function called with `ccall`. The frame and the stack are freed before the
+    /// exception is thrown, but none of the other values that own resources in these Rust frames
+    /// are dropped. You should make sure everything has been dropped before calling this method.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// # use jlrs::prelude::*;
+    /// #[no_mangle]
+    /// pub unsafe extern "C" fn checked_sqrt(x: f64) -> f64 {
+    ///     if x < 0.0 {
+    ///         let mut ccall = CCall::new(4);
+    ///         ccall.throw_exception(|global, frame| {
+    ///             let msg = Value::new(frame, "x must be non-negative")?;
+    ///             let exc = Module::core(global)
+    ///                 .function("ArgumentError")?
+    ///                 .call1(frame, msg)?
+    ///                 .unwrap_or_else(|e| e);
+    ///
+    ///             Ok(exc)
+    ///         })
+    ///     }
+    ///
+    ///     x.sqrt()
+    /// }
+    /// ```
+    ///
+    /// [`DynamicFrame`]: ../frame/struct.DynamicFrame.html
+    pub unsafe fn throw_exception<'base, 'julia: 'base, 'data, F>(&'julia mut self, func: F) -> !
+    where
+        F: FnOnce(Global<'base>, &mut DynamicFrame<'base, Sync>) -> JlrsResult<Value<'base, 'data>>,
+    {
+        let this = self as *mut Self;
+        let res = (&mut *this).dynamic_frame(|global, frame| Ok(func(global, frame)?.ptr()));
+
+        match res {
+            Ok(exception) => {
+                // Nothing is allocated after the frame has been popped, so the exception doesn't
+                // need to be rooted until it's thrown.
+                (&mut *this).stack = None;
+                jl_throw(exception);
+                unreachable!()
+            }
+            Err(e) => {
+                let msg = e.to_string();
+                std::mem::drop(e);
+                (&mut *this).throw_error(msg)
+            }
+        }
+    }
+
+    // Rethrow a panic as an `ErrorException`.
+    unsafe fn throw_panic(&mut self, payload: Box<dyn Any + Send>) -> ! {
+        let msg = format!("A Rust panic occurred: {}", panic_message(payload));
+        self.throw_error(msg)
+    }
+
+    // Throw an `ErrorException`. Julia jumps over all Rust frames up to the function called with
+    // `ccall`, so everything that owns resources must be dropped before the exception is thrown.
+    // The message is copied to a buffer on the stack for the same reason.
+    pub(crate) unsafe fn throw_error(&mut self, message: String) -> ! {
+        self.stack = None;
+
+        let mut msg = [0u8; 1024];
+        {
+            let bytes = message.as_bytes();
+            let len = bytes.len().min(msg.len() - 1);
+            msg[..len].copy_from_slice(&bytes[..len]);
+            // Julia expects a NUL-terminated string, interior NULs would truncate it anyway.
+            msg[..len]
+                .iter_mut()
+                .filter(|b| **b == 0)
+                .for_each(|b| *b = b' ');
+        }
+        std::mem::drop(message);
+
+        jl_error(msg.as_ptr().cast());
+        unreachable!()
+    }
+
     #[inline(always)]
     fn ensure_init_stack(&mut self) -> Option<&mut RawStack> {
         if self.stack.is_none() {
             unsafe {
-                self.stack = Some(RawStack::new(self.stack_size));
+                self.stack = Some(RawStack::new(self.stack_size, self.max_stack_size, "ccall"));
             }
         }
 
@@ -793,17 +1152,55 @@ impl CCall {
     }
 }
 
+// Closures added with Value::add_rust_finalizer, they're removed from this map when the finalizer
+// is called.
+static RUST_FINALIZERS: Mutex<BTreeMap<usize, Box<dyn FnOnce() + Send>>> =
+    Mutex::new(BTreeMap::new());
+static NEXT_FINALIZER_ID: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn register_rust_finalizer(func: Box<dyn FnOnce() + Send>) -> usize {
+    let id = NEXT_FINALIZER_ID.fetch_add(1, Ordering::Relaxed);
+    RUST_FINALIZERS
+        .lock()
+        .expect("Rust finalizers are poisoned")
+        .insert(id, func);
+    id
+}
+
+pub(crate) fn unregister_rust_finalizer(id: usize) -> Option<Box<dyn FnOnce() + Send>> {
+    RUST_FINALIZERS
+        .lock()
+        .expect("Rust finalizers are poisoned")
+        .remove(&id)
+}
+
+unsafe extern "C" fn runfinalizer(id: usize) {
+    // The closure is removed before it's called, so it can add other finalizers.
+    if let Some(func) = unregister_rust_finalizer(id) {
+        panic::catch_unwind(AssertUnwindSafe(func)).ok();
+    }
+}
+
 unsafe extern "C" fn droparray(a: Array) {
     // The data of a moved array is allocated by Rust, this function is called by
-    // a finalizer in order to ensure it's also freed by Rust.
+    // a finalizer in order to ensure it's also freed by Rust. Julia doesn't store the alignment
+    // of the data, but it matches the alignment of the element type.
+    let align = a
+        .element_type()
+        .cast::<DataType>()
+        .map(|ty| ty.align() as usize)
+        .unwrap_or(1);
+
     let arr_ref = &mut *a.ptr();
 
     if arr_ref.flags.how() != 2 {
         return;
     }
 
-    let data_ptr = arr_ref.data.cast::<MaybeUninit<u8>>();
+    let data_ptr = arr_ref.data.cast::<u8>();
     arr_ref.data = null_mut();
-    let n_els = arr_ref.elsize as usize * arr_ref.length;
-    Vec::from_raw_parts(data_ptr, n_els, n_els);
+    let n_bytes = arr_ref.elsize as usize * arr_ref.length;
+    if n_bytes > 0 {
+        dealloc(data_ptr, Layout::from_size_align_unchecked(n_bytes, align));
+    }
 }
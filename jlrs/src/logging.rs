@@ -0,0 +1,299 @@
+//! Forward records logged with Julia's `Logging` to Rust.
+//!
+//! Julia code reports through the macros `@debug`, `@info`, `@warn` and `@error`, these records
+//! are handled by the current logger. With [`Julia::set_logger`] and [`AsyncJulia::set_logger`]
+//! you can install a [`Logger`] as Julia's global logger. Every record that's logged at or above
+//! its minimum level is converted to a [`LogRecord`] and passed to the logger's callback.
+//!
+//! If the `log` feature is enabled, [`Logger::log`] creates a logger that forwards all records
+//! to the logger of the `log` crate. Similarly, if the `tracing` feature is enabled
+//! [`Logger::tracing`] creates a logger that emits every record as a `tracing` event.
+//!
+//! The Julia logger is defined in `Main.Jlrs`, it's a subtype of `Base.CoreLogging.AbstractLogger`
+//! and it's only the global logger; loggers that have been installed with `with_logger` take
+//! precedence. The logger is called from the thread that logs the record, which is why the
+//! callback must be both `Send` and `Sync`.
+//!
+//! [`Julia::set_logger`]: ../struct.Julia.html#method.set_logger
+//! [`AsyncJulia::set_logger`]: ../multitask/struct.AsyncJulia.html#method.set_logger
+//! [`Logger`]: struct.Logger.html
+//! [`Logger::log`]: struct.Logger.html#method.log
+//! [`Logger::tracing`]: struct.Logger.html#method.tracing
+//! [`LogRecord`]: struct.LogRecord.html
+
+use crate::error::{JlrsError, JlrsResult};
+use crate::global::Global;
+use crate::value::array::Array;
+use crate::value::module::Module;
+use crate::value::Value;
+use jl_sys::{jl_box_int32, jl_box_voidpointer, jl_string_data, jl_string_len, jl_value_t};
+use std::ffi::c_void;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+/// The severity of a log record. Julia represents log levels as integers, custom levels are
+/// mapped to the closest standard level below them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    fn from_raw(level: i32) -> Self {
+        if level < 0 {
+            LogLevel::Debug
+        } else if level < 1000 {
+            LogLevel::Info
+        } else if level < 2000 {
+            LogLevel::Warn
+        } else {
+            LogLevel::Error
+        }
+    }
+
+    fn to_raw(self) -> i32 {
+        match self {
+            LogLevel::Debug => -1000,
+            LogLevel::Info => 0,
+            LogLevel::Warn => 1000,
+            LogLevel::Error => 2000,
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            LogLevel::Debug => "Debug",
+            LogLevel::Info => "Info",
+            LogLevel::Warn => "Warn",
+            LogLevel::Error => "Error",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// A record that has been logged by Julia. The message and the values of the key-value pairs
+/// are converted to strings with `Base.string`. If the record has no associated file `file` is
+/// empty, if it has no associated line `line` is 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogRecord {
+    pub level: LogLevel,
+    pub message: String,
+    pub module: String,
+    pub file: String,
+    pub line: isize,
+    pub kwargs: Vec<(String, String)>,
+}
+
+/// A logger that can be installed as Julia's global logger. Records below the minimum level are
+/// discarded by Julia, all others are passed to the callback.
+pub struct Logger {
+    min_level: LogLevel,
+    callback: Box<dyn Fn(&LogRecord) + Send + Sync>,
+}
+
+impl Logger {
+    /// Create a new logger that calls `callback` with every record that's logged at or above
+    /// `min_level`.
+    pub fn new<F>(min_level: LogLevel, callback: F) -> Self
+    where
+        F: Fn(&LogRecord) + Send + Sync + 'static,
+    {
+        Logger {
+            min_level,
+            callback: Box::new(callback),
+        }
+    }
+
+    /// Create a new logger that forwards every record that's logged at or above `min_level` to
+    /// the logger of the `log` crate. The module is used as the target, the key-value pairs are
+    /// appended to the message.
+    #[cfg(feature = "log")]
+    pub fn log(min_level: LogLevel) -> Self {
+        Logger::new(min_level, |record| {
+            let level = match record.level {
+                LogLevel::Debug => log::Level::Debug,
+                LogLevel::Info => log::Level::Info,
+                LogLevel::Warn => log::Level::Warn,
+                LogLevel::Error => log::Level::Error,
+            };
+
+            if level > log::max_level() {
+                return;
+            }
+
+            let file = if record.file.is_empty() {
+                None
+            } else {
+                Some(record.file.as_str())
+            };
+
+            let line = if record.line > 0 {
+                Some(record.line as u32)
+            } else {
+                None
+            };
+
+            log::logger().log(
+                &log::Record::builder()
+                    .level(level)
+                    .target(&record.module)
+                    .module_path(Some(&record.module))
+                    .file(file)
+                    .line(line)
+                    .args(format_args!("{}", DisplayRecord(record)))
+                    .build(),
+            );
+        })
+    }
+
+    /// Create a new logger that emits every record that's logged at or above `min_level` as a
+    /// `tracing` event with the target `julia`. The module, file and line are recorded as the
+    /// fields `module`, `file` and `line`, the key-value pairs are appended to the message.
+    #[cfg(feature = "tracing")]
+    pub fn tracing(min_level: LogLevel) -> Self {
+        Logger::new(min_level, |record| {
+            let message = DisplayRecord(record);
+            let module = record.module.as_str();
+            let file = record.file.as_str();
+            let line = record.line;
+
+            match record.level {
+                LogLevel::Debug => {
+                    tracing::debug!(target: "julia", module, file, line, "{}", message)
+                }
+                LogLevel::Info => {
+                    tracing::info!(target: "julia", module, file, line, "{}", message)
+                }
+                LogLevel::Warn => {
+                    tracing::warn!(target: "julia", module, file, line, "{}", message)
+                }
+                LogLevel::Error => {
+                    tracing::error!(target: "julia", module, file, line, "{}", message)
+                }
+            }
+        })
+    }
+
+    /// Returns the minimum level of this logger.
+    pub fn min_level(&self) -> LogLevel {
+        self.min_level
+    }
+}
+
+// Displays the message of a record followed by its key-value pairs.
+#[cfg(any(feature = "log", feature = "tracing"))]
+struct DisplayRecord<'a>(&'a LogRecord);
+
+#[cfg(any(feature = "log", feature = "tracing"))]
+impl<'a> fmt::Display for DisplayRecord<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0.message)?;
+        for (key, value) in self.0.kwargs.iter() {
+            write!(f, " {}={}", key, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+// The logger that's currently installed. It must stay alive until Julia no longer uses it.
+#[derive(Default)]
+pub(crate) struct InstalledLogger(Option<Box<Logger>>);
+
+impl InstalledLogger {
+    // Install `logger` as Julia's global logger, or restore the previous logger if `logger` is
+    // `None`.
+    pub(crate) unsafe fn set(&mut self, logger: Option<Logger>) -> JlrsResult<()> {
+        let global = Global::new();
+
+        if let Some(old) = self.0.take() {
+            if let Err(e) = call_jlrs(global, "uninstalllogger", &old, None) {
+                // Julia might still use the old logger, so it must not be dropped.
+                Box::leak(old);
+                return Err(e);
+            }
+        }
+
+        if let Some(logger) = logger {
+            let logger = Box::new(logger);
+            call_jlrs(global, "installlogger", &logger, Some(logger.min_level))?;
+            self.0 = Some(logger);
+        }
+
+        Ok(())
+    }
+}
+
+unsafe fn call_jlrs(
+    global: Global,
+    func: &str,
+    logger: &Logger,
+    min_level: Option<LogLevel>,
+) -> JlrsResult<()> {
+    let logger_ptr = Value::wrap(jl_box_voidpointer(logger as *const Logger as *mut c_void));
+    let func_value = Module::main(global).submodule("Jlrs")?.function(func)?;
+
+    // The arguments are protected by jl_call, the result is not used.
+    let res = match min_level {
+        Some(level) => func_value.call2_unprotected(
+            global,
+            logger_ptr,
+            Value::wrap(jl_box_int32(level.to_raw())),
+        ),
+        None => func_value.call1_unprotected(global, logger_ptr),
+    };
+
+    res.map_err(|e| JlrsError::Other(format!("{} threw {}", func, e.type_name()).into()))?;
+    Ok(())
+}
+
+// Julia strings aren't guaranteed to be valid UTF-8, invalid sequences are replaced with `U+FFFD`.
+unsafe fn read_string(value: *mut jl_value_t) -> String {
+    let len = jl_string_len(value);
+    let bytes = std::slice::from_raw_parts(jl_string_data(value), len);
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+unsafe fn read_strings(array: Array) -> Vec<String> {
+    let arr = &*array.ptr();
+    let data = arr.data as *const *mut jl_value_t;
+
+    (0..arr.length).map(|i| read_string(*data.add(i))).collect()
+}
+
+// This function is set as a constant in `Main.Jlrs` and called using `ccall` to pass a record
+// to the logger that's currently installed. All strings and arrays are rooted by the caller.
+pub(crate) unsafe extern "C" fn log_rust(
+    logger: *mut c_void,
+    level: i32,
+    message: Value<'static, 'static>,
+    module: Value<'static, 'static>,
+    file: Value<'static, 'static>,
+    line: isize,
+    keys: Array<'static, 'static>,
+    values: Array<'static, 'static>,
+) {
+    let logger = &*(logger as *const Logger);
+    let kwargs = read_strings(keys)
+        .into_iter()
+        .zip(read_strings(values))
+        .collect();
+
+    let record = LogRecord {
+        level: LogLevel::from_raw(level),
+        message: read_string(message.ptr()),
+        module: read_string(module.ptr()),
+        file: read_string(file.ptr()),
+        line,
+        kwargs,
+    };
+
+    // A panic must not unwind into Julia, the record is dropped if the callback panics.
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| (logger.callback)(&record)));
+}
@@ -5,33 +5,41 @@
 //! offered by jlrs combines this feature with Rust's async/.await syntax.
 //!
 //! In order to use the async runtime, Julia must be started with more than one thread by setting
-//! the `JULIA_NUM_THREADS` environment variable. In order to create tasks that can be executed
-//! you must implement the [`JuliaTask`] trait.
+//! the `JULIA_NUM_THREADS` environment variable or with [`JuliaBuilder::n_threads`]. In order to
+//! create tasks that can be executed you must implement the [`JuliaTask`] trait.
 //!
 //! [`JuliaTask`]: ../traits/multitask/trait.JuliaTask.html
+//! [`JuliaBuilder::n_threads`]: ../builder/struct.JuliaBuilder.html#method.n_threads
 
+use crate::builder::{self, IgnoredOption, JuliaBuilder};
 use crate::error::other_err;
 use crate::error::{JlrsError, JlrsResult};
 use crate::frame::AsyncFrame;
 use crate::global::Global;
+use crate::interrupt::{self, InterruptHandle};
+use crate::logging::{InstalledLogger, Logger};
 use crate::mode::Async;
+use crate::persistent;
 use crate::stack::multitask::{MultitaskStack, TaskStack};
-use crate::stack::{Dynamic, StackView};
+use crate::stack::{Dynamic, RawStack, StackView};
+use crate::stdio::{Redirects, Stream};
 use crate::traits::multitask::{JuliaTask, ReturnChannel};
+use crate::traits::IntoJlrsResult;
 use crate::value::module::Module;
 use crate::value::Value;
-use crate::{INIT, JLRS_JL};
+use crate::JLRS_JL;
 use async_std::channel::{
     bounded, Receiver as AsyncStdReceiver, RecvError, Sender as AsyncStdSender, TrySendError,
 };
 use async_std::future::timeout;
 use async_std::sync::{Condvar as AsyncStdCondvar, Mutex as AsyncStdMutex};
 use async_std::task::{self, JoinHandle as AsyncStdHandle};
-use jl_sys::{jl_atexit_hook, jl_gc_safepoint, jl_init_with_image__threading, jl_is_initialized};
+use futures::FutureExt;
+use jl_sys::jl_atexit_hook;
 use std::ffi::{c_void, CString};
-use std::io::{Error as IOError, ErrorKind};
+use std::io::Write;
+use std::panic::AssertUnwindSafe;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::Ordering;
 use std::sync::{Arc, Condvar, Mutex};
 use std::thread::{self, JoinHandle as ThreadHandle};
 use std::time::Duration;
@@ -51,8 +59,9 @@ use std::time::Duration;
 ///  - `n_threads`: the number of threads that can be used to run tasks at the same time, it must
 ///    be less than the number of threads set with the `JULIA_NUM_THREADS` environment variable
 ///    (which defaults to 1).
-///  - `stack_size`: the size of a stack that is created for each of the tasks threads and the
-///    main thread (so `n_thread + 1` stacks with `stack_size` slots are created).
+///  - `stack_size`: the initial size of a stack that is created for each of the tasks threads and
+///    the main thread (so `n_thread + 1` stacks with `stack_size` slots are created). These
+///    stacks grow on demand.
 ///  - `process_events_ms`: to ensure the garbage collector can run and tasks that have yielded in
 ///    Julia are rescheduled, events must be processed periodically when at least one task is
 ///    running.
@@ -86,13 +95,8 @@ where
         stack_size: usize,
         process_events_ms: u64,
     ) -> JlrsResult<(Self, ThreadHandle<JlrsResult<()>>)> {
-        let (sender, receiver) = bounded(channel_capacity);
-        let julia = AsyncJulia { sender };
-        let handle =
-            thread::spawn(move || run_async(n_threads, stack_size, process_events_ms, receiver));
-        julia.try_set_wake_fn().map_err(other_err)?;
-
-        Ok((julia, handle))
+        let builder = JuliaBuilder::new().stack_size(stack_size);
+        Self::init_with_builder(builder, channel_capacity, n_threads, process_events_ms)
     }
 
     /// Initialize Julia as a blocking task, this function can only be called once. If Julia was
@@ -108,14 +112,8 @@ where
         stack_size: usize,
         process_events_ms: u64,
     ) -> JlrsResult<(Self, AsyncStdHandle<JlrsResult<()>>)> {
-        let (sender, receiver) = bounded(channel_capacity);
-        let julia = AsyncJulia { sender };
-        let handle = task::spawn_blocking(move || {
-            run_async(n_threads, stack_size, process_events_ms, receiver)
-        });
-        julia.set_wake_fn().await?;
-
-        Ok((julia, handle))
+        let builder = JuliaBuilder::new().stack_size(stack_size);
+        Self::init_with_builder_async(builder, channel_capacity, n_threads, process_events_ms).await
     }
 
     /// This function is similar to [`AsyncJulia::init`] except that it loads a custom system
@@ -147,21 +145,10 @@ where
         P: AsRef<Path> + Send + 'static,
         Q: AsRef<Path> + Send + 'static,
     {
-        let (sender, receiver) = bounded(channel_capacity);
-        let julia = AsyncJulia { sender };
-        let handle = thread::spawn(move || {
-            run_async_with_image(
-                n_threads,
-                stack_size,
-                process_events_ms,
-                receiver,
-                julia_bindir,
-                image_path,
-            )
-        });
-        julia.try_set_wake_fn().map_err(other_err)?;
-
-        Ok((julia, handle))
+        let builder = JuliaBuilder::new()
+            .stack_size(stack_size)
+            .image(julia_bindir, image_path);
+        Self::init_with_builder(builder, channel_capacity, n_threads, process_events_ms)
     }
 
     /// This function is similar to [`AsyncJulia::init_async`] except that it loads a custom
@@ -193,17 +180,59 @@ where
         P: AsRef<Path> + Send + 'static,
         Q: AsRef<Path> + Send + 'static,
     {
+        let builder = JuliaBuilder::new()
+            .stack_size(stack_size)
+            .image(julia_bindir, image_path);
+        Self::init_with_builder_async(builder, channel_capacity, n_threads, process_events_ms).await
+    }
+
+    /// This function is similar to [`AsyncJulia::init`] except that Julia is configured and
+    /// initialized with a [`JuliaBuilder`]. The stack size and maximum stack size of the builder
+    /// are used for each of the stacks, and `n_threads` must be less than the number of threads set with
+    /// [`JuliaBuilder::n_threads`].
+    ///
+    /// This function is unsafe because this crate provides you with a way to execute arbitrary
+    /// Julia code which can't be checked for correctness.
+    ///
+    /// [`AsyncJulia::init`]: struct.AsyncJulia.html#method.init
+    /// [`JuliaBuilder`]: ../builder/struct.JuliaBuilder.html
+    /// [`JuliaBuilder::n_threads`]: ../builder/struct.JuliaBuilder.html#method.n_threads
+    pub unsafe fn init_with_builder(
+        builder: JuliaBuilder,
+        channel_capacity: usize,
+        n_threads: usize,
+        process_events_ms: u64,
+    ) -> JlrsResult<(Self, ThreadHandle<JlrsResult<()>>)> {
+        let (sender, receiver) = bounded(channel_capacity);
+        let julia = AsyncJulia { sender };
+        let handle =
+            thread::spawn(move || run_async(builder, n_threads, process_events_ms, receiver));
+        julia.try_set_wake_fn().map_err(other_err)?;
+
+        Ok((julia, handle))
+    }
+
+    /// This function is similar to [`AsyncJulia::init_async`] except that Julia is configured
+    /// and initialized with a [`JuliaBuilder`]. The stack size and maximum stack size of the
+    /// builder are used for each of the stacks, and `n_threads` must be less than the number of threads set with
+    /// [`JuliaBuilder::n_threads`].
+    ///
+    /// This function is unsafe because this crate provides you with a way to execute arbitrary
+    /// Julia code which can't be checked for correctness.
+    ///
+    /// [`AsyncJulia::init_async`]: struct.AsyncJulia.html#method.init_async
+    /// [`JuliaBuilder`]: ../builder/struct.JuliaBuilder.html
+    /// [`JuliaBuilder::n_threads`]: ../builder/struct.JuliaBuilder.html#method.n_threads
+    pub async unsafe fn init_with_builder_async(
+        builder: JuliaBuilder,
+        channel_capacity: usize,
+        n_threads: usize,
+        process_events_ms: u64,
+    ) -> JlrsResult<(Self, AsyncStdHandle<JlrsResult<()>>)> {
         let (sender, receiver) = bounded(channel_capacity);
         let julia = AsyncJulia { sender };
         let handle = task::spawn_blocking(move || {
-            run_async_with_image(
-                n_threads,
-                stack_size,
-                process_events_ms,
-                receiver,
-                julia_bindir,
-                image_path,
-            )
+            run_async(builder, n_threads, process_events_ms, receiver)
         });
         julia.set_wake_fn().await?;
 
@@ -277,10 +306,10 @@ where
                 completed.clone(),
             ))
             .map_err(|e| match e {
-                TrySendError::Full(Message::Include(t, _)) => {
+                TrySendError::Full(Message::TryInclude(t, _)) => {
                     Box::new(other_err(TrySendError::Full(t)))
                 }
-                TrySendError::Closed(Message::Include(t, _)) => {
+                TrySendError::Closed(Message::TryInclude(t, _)) => {
                     Box::new(other_err(TrySendError::Closed(t)))
                 }
                 _ => unreachable!(),
@@ -295,6 +324,193 @@ where
             })
     }
 
+    /// Call `include_string` in the `Main` module in Julia, which evaluates `code` as if it were
+    /// the contents of a file named `name`. The file name and line numbers are available in
+    /// backtraces. This method waits until the code has been evaluated. It returns an error if
+    /// the call to `include_string` throws an exception.
+    pub async fn include_string<C: Into<String>, N: Into<String>>(
+        &self,
+        code: C,
+        name: N,
+    ) -> JlrsResult<()> {
+        let completed = Arc::new((AsyncStdMutex::new(Status::Pending), AsyncStdCondvar::new()));
+        self.sender
+            .send(Message::IncludeString(
+                code.into(),
+                name.into(),
+                completed.clone(),
+            ))
+            .await
+            .expect("Channel was closed");
+
+        let (lock, cvar) = &*completed;
+        let mut completed = lock.lock().await;
+        while (&*completed).is_pending() {
+            completed = cvar.wait(completed).await;
+        }
+
+        (&mut *completed).as_jlrs_result()
+    }
+
+    /// Call `include_string` in the `Main` module in Julia, which evaluates `code` as if it were
+    /// the contents of a file named `name`. This method waits until the code has been evaluated.
+    /// It returns an error if the channel is full or the call to `include_string` throws an
+    /// exception.
+    pub fn try_include_string<C: Into<String>, N: Into<String>>(
+        &self,
+        code: C,
+        name: N,
+    ) -> JlrsResult<()> {
+        let completed = Arc::new((Mutex::new(Status::Pending), Condvar::new()));
+        self.sender
+            .try_send(Message::TryIncludeString(
+                code.into(),
+                name.into(),
+                completed.clone(),
+            ))
+            .map_err(|e| match e {
+                TrySendError::Full(Message::TryIncludeString(_, name, _)) => {
+                    Box::new(other_err(TrySendError::Full(name)))
+                }
+                TrySendError::Closed(Message::TryIncludeString(_, name, _)) => {
+                    Box::new(other_err(TrySendError::Closed(name)))
+                }
+                _ => unreachable!(),
+            })
+            .and_then(|_| {
+                let (lock, cvar) = &*completed;
+                let mut completed = lock.lock().unwrap();
+                while (&*completed).is_pending() {
+                    completed = cvar.wait(completed).unwrap();
+                }
+                (&mut *completed).as_jlrs_result()
+            })
+    }
+
+    /// Redirect Julia's `stdout` or `stderr` to `writer`, or restore the original stream if
+    /// `writer` is `None`. This method waits until the stream has been redirected. The writer is
+    /// used until it's replaced or the runtime shuts down. See the [`stdio`] module for more
+    /// information.
+    ///
+    /// [`stdio`]: ../stdio/index.html
+    pub async fn redirect_output(
+        &self,
+        stream: Stream,
+        writer: Option<Box<dyn Write + Send>>,
+    ) -> JlrsResult<()> {
+        let completed = Arc::new((AsyncStdMutex::new(Status::Pending), AsyncStdCondvar::new()));
+        self.sender
+            .send(Message::RedirectOutput(stream, writer, completed.clone()))
+            .await
+            .expect("Channel was closed");
+
+        let (lock, cvar) = &*completed;
+        let mut completed = lock.lock().await;
+        while (&*completed).is_pending() {
+            completed = cvar.wait(completed).await;
+        }
+
+        (&mut *completed).as_jlrs_result()
+    }
+
+    /// Redirect Julia's `stdout` or `stderr` to `writer`, or restore the original stream if
+    /// `writer` is `None`. This method waits until the stream has been redirected. It returns an
+    /// error if the channel is full.
+    pub fn try_redirect_output(
+        &self,
+        stream: Stream,
+        writer: Option<Box<dyn Write + Send>>,
+    ) -> JlrsResult<()> {
+        let completed = Arc::new((Mutex::new(Status::Pending), Condvar::new()));
+        self.sender
+            .try_send(Message::TryRedirectOutput(
+                stream,
+                writer,
+                completed.clone(),
+            ))
+            .map_err(|e| match e {
+                TrySendError::Full(Message::TryRedirectOutput(stream, _, _)) => {
+                    Box::new(other_err(TrySendError::Full(stream)))
+                }
+                TrySendError::Closed(Message::TryRedirectOutput(stream, _, _)) => {
+                    Box::new(other_err(TrySendError::Closed(stream)))
+                }
+                _ => unreachable!(),
+            })
+            .and_then(|_| {
+                let (lock, cvar) = &*completed;
+                let mut completed = lock.lock().unwrap();
+                while (&*completed).is_pending() {
+                    completed = cvar.wait(completed).unwrap();
+                }
+                (&mut *completed).as_jlrs_result()
+            })
+    }
+
+    /// Install `logger` as Julia's global logger, or restore the previous global logger if
+    /// `logger` is `None`. This method waits until the logger has been installed. The logger is
+    /// used until it's replaced or the runtime shuts down. See the [`logging`] module for more
+    /// information.
+    ///
+    /// [`logging`]: ../logging/index.html
+    pub async fn set_logger(&self, logger: Option<Logger>) -> JlrsResult<()> {
+        let completed = Arc::new((AsyncStdMutex::new(Status::Pending), AsyncStdCondvar::new()));
+        self.sender
+            .send(Message::SetLogger(logger, completed.clone()))
+            .await
+            .expect("Channel was closed");
+
+        let (lock, cvar) = &*completed;
+        let mut completed = lock.lock().await;
+        while (&*completed).is_pending() {
+            completed = cvar.wait(completed).await;
+        }
+
+        (&mut *completed).as_jlrs_result()
+    }
+
+    /// Install `logger` as Julia's global logger, or restore the previous global logger if
+    /// `logger` is `None`. This method waits until the logger has been installed. It returns an
+    /// error if the channel is full.
+    pub fn try_set_logger(&self, logger: Option<Logger>) -> JlrsResult<()> {
+        let completed = Arc::new((Mutex::new(Status::Pending), Condvar::new()));
+        self.sender
+            .try_send(Message::TrySetLogger(logger, completed.clone()))
+            .map_err(|e| match e {
+                TrySendError::Full(Message::TrySetLogger(_, _)) => {
+                    Box::new(other_err(TrySendError::Full(())))
+                }
+                TrySendError::Closed(Message::TrySetLogger(_, _)) => {
+                    Box::new(other_err(TrySendError::Closed(())))
+                }
+                _ => unreachable!(),
+            })
+            .and_then(|_| {
+                let (lock, cvar) = &*completed;
+                let mut completed = lock.lock().unwrap();
+                while (&*completed).is_pending() {
+                    completed = cvar.wait(completed).unwrap();
+                }
+                (&mut *completed).as_jlrs_result()
+            })
+    }
+
+    /// Returns a handle that can be used to interrupt the Julia code that's currently running on
+    /// the thread of the async runtime. See the [`interrupt`] module for more information.
+    ///
+    /// [`interrupt`]: ../interrupt/index.html
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        InterruptHandle::new()
+    }
+
+    /// Returns the options of the [`JuliaBuilder`] that have been ignored because they couldn't
+    /// be applied after Julia has been initialized.
+    ///
+    /// [`JuliaBuilder`]: ../builder/struct.JuliaBuilder.html
+    pub fn ignored_options(&self) -> Vec<IgnoredOption> {
+        builder::ignored_options()
+    }
+
     /// Returns the capacity of the channel.
     pub fn capacity(&self) -> usize {
         self.sender.capacity().unwrap()
@@ -387,9 +603,30 @@ enum Message<T, R> {
     ),
     Include(PathBuf, Arc<(AsyncStdMutex<Status>, AsyncStdCondvar)>),
     TryInclude(PathBuf, Arc<(Mutex<Status>, Condvar)>),
+    IncludeString(
+        String,
+        String,
+        Arc<(AsyncStdMutex<Status>, AsyncStdCondvar)>,
+    ),
+    TryIncludeString(String, String, Arc<(Mutex<Status>, Condvar)>),
     Complete(Wrapper, AsyncStdSender<Message<T, R>>),
     SetWakeFn(Arc<(AsyncStdMutex<Status>, AsyncStdCondvar)>),
     TrySetWakeFn(Arc<(Mutex<Status>, Condvar)>),
+    RedirectOutput(
+        Stream,
+        Option<Box<dyn Write + Send>>,
+        Arc<(AsyncStdMutex<Status>, AsyncStdCondvar)>,
+    ),
+    TryRedirectOutput(
+        Stream,
+        Option<Box<dyn Write + Send>>,
+        Arc<(Mutex<Status>, Condvar)>,
+    ),
+    SetLogger(
+        Option<Logger>,
+        Arc<(AsyncStdMutex<Status>, AsyncStdCondvar)>,
+    ),
+    TrySetLogger(Option<Logger>, Arc<(Mutex<Status>, Condvar)>),
 }
 
 struct Wrapper(usize, TaskStack);
@@ -413,12 +650,12 @@ where
             match tv.new_frame() {
                 Ok(frame_idx) => {
                     let global = Global::new();
-                    let mut frame = AsyncFrame {
-                        idx: frame_idx,
-                        memory: tv,
-                        len: 0,
-                    };
-                    let res = jl_task.run(global, &mut frame).await;
+                    let mut frame = AsyncFrame::new(frame_idx, tv);
+                    // A panic must not take down the runtime thread.
+                    let res = AssertUnwindSafe(jl_task.run(global, &mut frame))
+                        .catch_unwind()
+                        .await
+                        .unwrap_or_else(|payload| Err(JlrsError::panic(payload))?);
 
                     if let Some(sender) = jl_task.return_channel() {
                         sender.send(res).await;
@@ -441,8 +678,8 @@ where
 }
 
 fn run_async<T, R>(
+    builder: JuliaBuilder,
     n_threads: usize,
-    stack_size: usize,
     process_events_ms: u64,
     receiver: AsyncStdReceiver<Message<T, R>>,
 ) -> JlrsResult<()>
@@ -452,17 +689,21 @@ where
 {
     task::block_on(async {
         let mut mt_stack: MultitaskStack<T, R> = unsafe {
-            if jl_is_initialized() != 0 || INIT.swap(true, Ordering::SeqCst) {
-                return Err(JlrsError::AlreadyInitialized.into());
-            }
+            builder.init_runtime()?;
 
-            jl_sys::jl_init();
             let jlrs_jl = CString::new(JLRS_JL).expect("Invalid Jlrs module");
             jl_sys::jl_eval_string(jlrs_jl.as_ptr());
+            interrupt::init_interrupt(Global::new())?;
+            builder.configure_runtime();
+            persistent::set_julia_thread(true);
 
-            MultitaskStack::new(n_threads, stack_size)
+            MultitaskStack::new(n_threads, builder.stack_size, builder.max_stack_size)
         };
 
+        // The writers of redirected streams and the logger must live until Julia has shut down.
+        let mut redirects = Redirects::default();
+        let mut logger = InstalledLogger::default();
+
         loop {
             match timeout(Duration::from_millis(process_events_ms), receiver.recv()).await {
                 Err(_) => unsafe {
@@ -493,130 +734,89 @@ where
                     }
                 }
                 Ok(Ok(Message::Include(path, completed))) => {
-                    include(&mut mt_stack.raw, path, completed).await
+                    include(
+                        &mut mt_stack.raw,
+                        |stack| call_include(stack, path),
+                        completed,
+                    )
+                    .await
                 }
-                Ok(Ok(Message::TryInclude(path, completed))) => {
-                    try_include(&mut mt_stack.raw, path, completed)
+                Ok(Ok(Message::TryInclude(path, completed))) => try_include(
+                    &mut mt_stack.raw,
+                    |stack| call_include(stack, path),
+                    completed,
+                ),
+                Ok(Ok(Message::IncludeString(code, name, completed))) => {
+                    include(
+                        &mut mt_stack.raw,
+                        |stack| call_include_string(stack, code, name),
+                        completed,
+                    )
+                    .await
                 }
+                Ok(Ok(Message::TryIncludeString(code, name, completed))) => try_include(
+                    &mut mt_stack.raw,
+                    |stack| call_include_string(stack, code, name),
+                    completed,
+                ),
                 Ok(Ok(Message::SetWakeFn(completed))) => {
                     set_wake_fn(&mut mt_stack.raw, completed).await
                 }
                 Ok(Ok(Message::TrySetWakeFn(completed))) => {
                     try_set_wake_fn(&mut mt_stack.raw, completed)
                 }
-                Ok(Err(RecvError)) => break,
-            }
-        }
-
-        // Wait for tasks to finish
-        for running in mt_stack.running.iter_mut() {
-            if let Some(handle) = running.take() {
-                handle.await;
-            }
-        }
-
-        unsafe {
-            jl_atexit_hook(0);
-        }
-
-        Ok(())
-    })
-}
-
-fn run_async_with_image<T, R, P, Q>(
-    n_threads: usize,
-    stack_size: usize,
-    process_events_ms: u64,
-    receiver: AsyncStdReceiver<Message<T, R>>,
-    julia_bindir: P,
-    image_path: Q,
-) -> JlrsResult<()>
-where
-    T: Send + Sync + 'static,
-    R: ReturnChannel<T = T> + 'static,
-    P: AsRef<Path>,
-    Q: AsRef<Path>,
-{
-    task::block_on(async {
-        let mut mt_stack: MultitaskStack<T, R> = unsafe {
-            if jl_is_initialized() != 0 || INIT.swap(true, Ordering::SeqCst) {
-                return Err(JlrsError::AlreadyInitialized.into());
-            }
-
-            let julia_bindir_str = julia_bindir.as_ref().to_string_lossy().to_string();
-            let image_path_str = image_path.as_ref().to_string_lossy().to_string();
-
-            if !julia_bindir.as_ref().exists() {
-                let io_err = IOError::new(ErrorKind::NotFound, julia_bindir_str);
-                return Err(other_err(io_err))?;
-            }
-
-            if !image_path.as_ref().exists() {
-                let io_err = IOError::new(ErrorKind::NotFound, image_path_str);
-                return Err(other_err(io_err))?;
-            }
-
-            let bindir = std::ffi::CString::new(julia_bindir_str).unwrap();
-            let im_rel_path = std::ffi::CString::new(image_path_str).unwrap();
-
-            jl_init_with_image__threading(bindir.as_ptr(), im_rel_path.as_ptr());
-
-            let jlrs_jl = CString::new(JLRS_JL).expect("Invalid Jlrs module");
-            jl_sys::jl_eval_string(jlrs_jl.as_ptr());
-            MultitaskStack::new(n_threads, stack_size)
-        };
-
-        loop {
-            match timeout(Duration::from_millis(process_events_ms), receiver.recv()).await {
-                Err(_) => unsafe {
-                    // periodically insert a safepoint so the GC can run when nothing is happening on
-                    // the main thread but tasks are active
-                    if mt_stack.n > 0 {
-                        jl_gc_safepoint();
-                    }
-                },
-                Ok(Ok(Message::Task(jl_task, sender))) => {
-                    if let Some((task_idx, task_stack)) = mt_stack.acquire_task_frame() {
-                        mt_stack.n += 1;
-                        mt_stack.running[task_idx] =
-                            Some(run_task(jl_task, task_idx, task_stack, sender));
-                    } else {
-                        mt_stack.add_pending(jl_task);
-                    }
-                }
-                Ok(Ok(Message::Complete(Wrapper(task_idx, task_stack), sender))) => {
-                    if let Some(jl_task) = mt_stack.pop_pending() {
-                        mt_stack.running[task_idx] =
-                            Some(run_task(jl_task, task_idx, task_stack, sender));
-                    } else {
-                        mt_stack.n -= 1;
-                        mt_stack.running[task_idx] = None;
-                        mt_stack.return_task_frame(task_idx, task_stack);
-                    }
-                }
-                Ok(Ok(Message::Include(path, completed))) => {
-                    include(&mut mt_stack.raw, path, completed).await
+                Ok(Ok(Message::RedirectOutput(stream, writer, completed))) => {
+                    let res = unsafe { redirects.set(stream, writer) };
+                    let (lock, condvar) = &*completed;
+                    let mut completed = lock.lock().await;
+                    *completed = match res {
+                        Ok(_) => Status::Ok,
+                        Err(e) => Status::Err(Some(e)),
+                    };
+                    condvar.notify_one();
                 }
-                Ok(Ok(Message::TryInclude(path, completed))) => {
-                    try_include(&mut mt_stack.raw, path, completed)
+                Ok(Ok(Message::TryRedirectOutput(stream, writer, completed))) => {
+                    let res = unsafe { redirects.set(stream, writer) };
+                    let (lock, condvar) = &*completed;
+                    let mut completed = lock.lock().expect("Cannot lock");
+                    *completed = match res {
+                        Ok(_) => Status::Ok,
+                        Err(e) => Status::Err(Some(e)),
+                    };
+                    condvar.notify_one();
                 }
-                Ok(Ok(Message::SetWakeFn(completed))) => {
-                    set_wake_fn(&mut mt_stack.raw, completed).await
+                Ok(Ok(Message::SetLogger(new_logger, completed))) => {
+                    let res = unsafe { logger.set(new_logger) };
+                    let (lock, condvar) = &*completed;
+                    let mut completed = lock.lock().await;
+                    *completed = match res {
+                        Ok(_) => Status::Ok,
+                        Err(e) => Status::Err(Some(e)),
+                    };
+                    condvar.notify_one();
                 }
-                Ok(Ok(Message::TrySetWakeFn(completed))) => {
-                    try_set_wake_fn(&mut mt_stack.raw, completed)
+                Ok(Ok(Message::TrySetLogger(new_logger, completed))) => {
+                    let res = unsafe { logger.set(new_logger) };
+                    let (lock, condvar) = &*completed;
+                    let mut completed = lock.lock().expect("Cannot lock");
+                    *completed = match res {
+                        Ok(_) => Status::Ok,
+                        Err(e) => Status::Err(Some(e)),
+                    };
+                    condvar.notify_one();
                 }
                 Ok(Err(RecvError)) => break,
             }
         }
 
         // Wait for tasks to finish
-        for pending in mt_stack.running.iter_mut() {
-            if let Some(handle) = pending.take() {
+        for running in mt_stack.running.iter_mut() {
+            if let Some(handle) = running.take() {
                 handle.await;
             }
         }
 
+        persistent::set_julia_thread(false);
         unsafe {
             jl_atexit_hook(0);
         }
@@ -625,17 +825,13 @@ where
     })
 }
 
-fn call_set_wake_fn(stack: &mut [*mut c_void]) -> JlrsResult<()> {
+fn call_set_wake_fn(stack: &mut RawStack) -> JlrsResult<()> {
     unsafe {
         let global = Global::new();
         let mut view = StackView::<Async, Dynamic>::new(stack);
         let idx = view.new_frame()?;
 
-        let mut frame = AsyncFrame {
-            idx,
-            len: 0,
-            memory: view,
-        };
+        let mut frame = AsyncFrame::new(idx, view);
 
         let waker = Value::new(&mut frame, crate::julia_future::wake_task as *mut c_void)?;
         Module::main(global)
@@ -643,11 +839,7 @@ fn call_set_wake_fn(stack: &mut [*mut c_void]) -> JlrsResult<()> {
             .global("wakerust")?
             .set_nth_field(0, waker)?;
 
-        let dropper = Value::new(&mut frame, crate::droparray as *mut c_void)?;
-        Module::main(global)
-            .submodule("Jlrs")?
-            .global("droparray")?
-            .set_nth_field(0, dropper)?;
+        crate::set_jlrs_pointers(&mut frame)?;
     }
 
     Ok(())
@@ -696,17 +888,13 @@ fn try_set_wake_fn(stacks: &mut [Option<TaskStack>], completed: Arc<(Mutex<Statu
     }
 }
 
-fn call_include(stack: &mut [*mut c_void], path: PathBuf) -> JlrsResult<()> {
+fn call_include(stack: &mut RawStack, path: PathBuf) -> JlrsResult<()> {
     unsafe {
         let global = Global::new();
         let mut view = StackView::<Async, Dynamic>::new(stack);
         let idx = view.new_frame()?;
 
-        let mut frame = AsyncFrame {
-            idx,
-            len: 0,
-            memory: view,
-        };
+        let mut frame = AsyncFrame::new(idx, view);
 
         match path.to_str() {
             Some(path) => {
@@ -714,9 +902,7 @@ fn call_include(stack: &mut [*mut c_void], path: PathBuf) -> JlrsResult<()> {
                 Module::main(global)
                     .function("include")?
                     .call1(&mut frame, path)?
-                    .map_err(|_e| {
-                        crate::error::exception::<Value>("Include error".into()).unwrap_err()
-                    })?;
+                    .into_jlrs_result(&mut frame)?;
             }
             None => {}
         }
@@ -725,15 +911,36 @@ fn call_include(stack: &mut [*mut c_void], path: PathBuf) -> JlrsResult<()> {
     }
 }
 
-async fn include(
+fn call_include_string(stack: &mut RawStack, code: String, name: String) -> JlrsResult<()> {
+    unsafe {
+        let global = Global::new();
+        let mut view = StackView::<Async, Dynamic>::new(stack);
+        let idx = view.new_frame()?;
+
+        let mut frame = AsyncFrame::new(idx, view);
+
+        let code = Value::new(&mut frame, code)?;
+        let name = Value::new(&mut frame, name)?;
+        Module::base(global)
+            .function("include_string")?
+            .call3(&mut frame, Module::main(global).as_value(), code, name)?
+            .into_jlrs_result(&mut frame)?;
+
+        Ok(())
+    }
+}
+
+async fn include<F>(
     stacks: &mut [Option<TaskStack>],
-    path: PathBuf,
+    func: F,
     completed: Arc<(AsyncStdMutex<Status>, AsyncStdCondvar)>,
-) {
+) where
+    F: FnOnce(&mut RawStack) -> JlrsResult<()>,
+{
     let idx = stacks.len() - 1;
     let include_result = {
         let mut stack = stacks[idx].take().expect("GC stack is corrupted.");
-        let res = call_include(&mut stack.raw, path);
+        let res = func(&mut stack.raw);
         stacks[idx] = Some(stack);
         res
     };
@@ -751,15 +958,17 @@ async fn include(
     }
 }
 
-fn try_include(
+fn try_include<F>(
     stacks: &mut [Option<TaskStack>],
-    path: PathBuf,
+    func: F,
     completed: Arc<(Mutex<Status>, Condvar)>,
-) {
+) where
+    F: FnOnce(&mut RawStack) -> JlrsResult<()>,
+{
     let idx = stacks.len() - 1;
     let include_result = {
         let mut stack = stacks[idx].take().expect("GC stack is corrupted.");
-        let res = call_include(&mut stack.raw, path);
+        let res = func(&mut stack.raw);
         stacks[idx] = Some(stack);
         res
     };
@@ -0,0 +1,192 @@
+//! Root values independently of frames.
+//!
+//! Every [`Value`] is protected from garbage collection by the frame it was created in, after
+//! that frame has been dropped the value can no longer be used. This means that values you want
+//! to reuse, like a function you've looked up, a model you've loaded or a large array, have to be
+//! created again every time a new frame is used. A [`Persistent`] protects a value until it's
+//! dropped by storing it in a `Dict` in the `Jlrs` module under a unique id, which lets you cache values between
+//! calls to [`Julia::frame`] or share them between tasks in the async runtime.
+//!
+//! The rooted value can be borrowed back into any frame with [`Persistent::value`], or cast to
+//! another type like a [`DataType`] or [`Module`] with [`Persistent::cast`]. A `Persistent` can
+//! be sent to and shared with other threads. If it's dropped in a thread that can't call into
+//! Julia, the value is released the next time a `Persistent` is created or dropped in a thread
+//! that can.
+//!
+//! Example:
+//!
+//! ```no_run
+//! # use jlrs::prelude::*;
+//! # use jlrs::persistent::Persistent;
+//! # fn main() {
+//! # let mut julia = unsafe { Julia::init(16).unwrap() };
+//! let cached = julia.frame(1, |_global, frame| {
+//!     let v = Value::eval_string(frame, "rand(3)")?.unwrap();
+//!     Persistent::new(frame, v)
+//! }).unwrap();
+//!
+//! julia.frame(0, |_global, frame| {
+//!     let array = cached.cast::<Array, _>(frame)?;
+//!     assert_eq!(array.dimensions().n_elements(0), 3);
+//!     Ok(())
+//! }).unwrap();
+//! # }
+//! ```
+//!
+//! [`Value`]: ../value/struct.Value.html
+//! [`Persistent`]: struct.Persistent.html
+//! [`Persistent::value`]: struct.Persistent.html#method.value
+//! [`Persistent::cast`]: struct.Persistent.html#method.cast
+//! [`Julia::frame`]: ../struct.Julia.html#method.frame
+//! [`DataType`]: ../value/datatype/struct.DataType.html
+//! [`Module`]: ../value/module/struct.Module.html
+
+use crate::error::{JlrsError, JlrsResult};
+use crate::global::Global;
+use crate::traits::{Cast, Frame};
+use crate::value::module::Module;
+use crate::value::Value;
+use jl_sys::{jl_box_uint64, jl_value_t};
+use std::cell::Cell;
+use std::ptr::null_mut;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+thread_local! {
+    // Set on threads that can call into Julia.
+    static JULIA_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
+// Values that have been released by a thread that can't call into Julia.
+static PENDING: AtomicPtr<PendingRelease> = AtomicPtr::new(null_mut());
+
+// Every value is rooted under its own id, so distinct values that are egal don't share a root.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+struct PendingRelease {
+    id: usize,
+    next: *mut PendingRelease,
+}
+
+/// A value that is protected from garbage collection until this handle is dropped. See the
+/// [`persistent`] module for more information.
+///
+/// [`persistent`]: index.html
+pub struct Persistent {
+    value: *mut jl_value_t,
+    id: usize,
+}
+
+// The value is only accessed through a frame, which can only exist in a thread that can call into
+// Julia.
+unsafe impl Send for Persistent {}
+unsafe impl Sync for Persistent {}
+
+impl Persistent {
+    /// Protect `value` from garbage collection until the returned `Persistent` is dropped. Values
+    /// that borrow data from Rust can't be rooted this way. The same value can be rooted multiple
+    /// times, it remains rooted until all of them have been dropped.
+    pub fn new<'frame, F>(frame: &mut F, value: Value<'_, 'static>) -> JlrsResult<Self>
+    where
+        F: Frame<'frame>,
+    {
+        unsafe {
+            let global = frame.global();
+            release_pending(global);
+            let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+            let boxed_id = jl_box_uint64(id as u64);
+            call_jlrs(global, "root", &mut [Value::wrap(boxed_id), value])?;
+            Ok(Persistent {
+                value: value.ptr(),
+                id,
+            })
+        }
+    }
+
+    /// Borrow the rooted value. The value can be used until this `Persistent` is dropped.
+    pub fn value<'frame, 'root, F>(&'root self, _frame: &F) -> Value<'root, 'static>
+    where
+        F: Frame<'frame>,
+    {
+        unsafe { Value::wrap(self.value) }
+    }
+
+    /// Borrow the rooted value and cast it to `T`, eg a [`DataType`] or [`Array`]. Returns an
+    /// error if the value can't be cast to `T`.
+    ///
+    /// [`DataType`]: ../value/datatype/struct.DataType.html
+    /// [`Array`]: ../value/array/struct.Array.html
+    pub fn cast<'frame, 'root, T, F>(&'root self, frame: &F) -> JlrsResult<T::Output>
+    where
+        T: Cast<'root, 'static>,
+        F: Frame<'frame>,
+    {
+        self.value(frame).cast::<T>()
+    }
+}
+
+impl Drop for Persistent {
+    fn drop(&mut self) {
+        unsafe {
+            if is_julia_thread() {
+                let global = Global::new();
+                release_pending(global);
+                unroot(global, self.id).ok();
+            } else {
+                let pending = Box::new(PendingRelease {
+                    id: self.id,
+                    next: null_mut(),
+                });
+                let pending = Box::into_raw(pending);
+
+                let mut head = PENDING.load(Ordering::Acquire);
+                loop {
+                    (*pending).next = head;
+                    match PENDING.compare_exchange_weak(
+                        head,
+                        pending,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    ) {
+                        Ok(_) => break,
+                        Err(current) => head = current,
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Mark the current thread as one that can or can't call into Julia.
+pub(crate) fn set_julia_thread(is_julia_thread: bool) {
+    JULIA_THREAD.with(|t| t.set(is_julia_thread));
+}
+
+pub(crate) fn is_julia_thread() -> bool {
+    JULIA_THREAD.with(|t| t.get())
+}
+
+unsafe fn release_pending(global: Global) {
+    let mut pending = PENDING.swap(null_mut(), Ordering::AcqRel);
+    while !pending.is_null() {
+        let release = Box::from_raw(pending);
+        unroot(global, release.id).ok();
+        pending = release.next;
+    }
+}
+
+unsafe fn unroot(global: Global, id: usize) -> JlrsResult<()> {
+    // A boxed UInt64 is only used as an argument, so it doesn't need to be rooted.
+    let boxed_id = jl_box_uint64(id as u64);
+    call_jlrs(global, "unroot", &mut [Value::wrap(boxed_id)])
+}
+
+unsafe fn call_jlrs(global: Global, func: &str, args: &mut [Value]) -> JlrsResult<()> {
+    // The arguments are protected by jl_call, the result is not used.
+    Module::main(global)
+        .submodule("Jlrs")?
+        .function(func)?
+        .call_unprotected::<_, ()>(global, args)
+        .map_err(|e| JlrsError::Other(format!("{} threw {}", func, e.type_name()).into()))?;
+
+    Ok(())
+}
@@ -1,9 +1,10 @@
 //! Reexports structs and traits you're likely to need.
 
-pub use crate::error::{JlrsError, JlrsResult};
+pub use crate::builder::JuliaBuilder;
+pub use crate::error::{Backtrace, ExceptionKind, JlrsError, JlrsResult, JuliaException};
 pub use crate::frame::{DynamicFrame, NullFrame, StaticFrame};
 pub use crate::global::Global;
-pub use crate::traits::{Frame, ValidLayout};
+pub use crate::traits::{Frame, IntoJlrsResult, ValidLayout};
 pub use crate::value::array::{
     Array, ArrayData, CopiedArray, InlineArrayDataMut, TypedArray, ValueArrayDataMut,
 };
@@ -16,7 +17,7 @@ pub use crate::value::{Value, Values};
 pub use crate::{named_tuple, CCall, Julia};
 
 #[cfg(feature = "jlrs-derive")]
-pub use jlrs_derive::{IntoJulia, JuliaStruct};
+pub use jlrs_derive::{include_julia_dir, IntoJulia, JuliaStruct};
 
 #[cfg(all(feature = "async", target_os = "linux"))]
 pub use crate::frame::AsyncFrame;
@@ -0,0 +1,97 @@
+//! Activate and instantiate Julia projects.
+//!
+//! Julia loads packages from the environments in its load path, the active project is usually
+//! the first of these environments. The active project can be set before Julia is initialized
+//! with [`JuliaBuilder::project`], the load path and depot path with [`JuliaBuilder::load_path`]
+//! and [`JuliaBuilder::depot_path`]. After Julia has been initialized, another project can be
+//! activated with [`activate_project`] or [`Julia::activate_project`].
+//!
+//! Applications that are deployed without network access can use [`instantiate_offline`] or
+//! [`Julia::instantiate_offline`] to instantiate the active project with the packages that are
+//! already available in the depot. If a dependency of the project isn't installed, an error that
+//! contains the names of all missing packages is returned.
+//!
+//! [`JuliaBuilder::project`]: ../builder/struct.JuliaBuilder.html#method.project
+//! [`JuliaBuilder::load_path`]: ../builder/struct.JuliaBuilder.html#method.load_path
+//! [`JuliaBuilder::depot_path`]: ../builder/struct.JuliaBuilder.html#method.depot_path
+//! [`Julia::activate_project`]: ../struct.Julia.html#method.activate_project
+//! [`Julia::instantiate_offline`]: ../struct.Julia.html#method.instantiate_offline
+//! [`activate_project`]: fn.activate_project.html
+//! [`instantiate_offline`]: fn.instantiate_offline.html
+
+use crate::error::{JlrsError, JlrsResult};
+use crate::traits::{Frame, IntoJlrsResult};
+use crate::value::array::Array;
+use crate::value::module::Module;
+use crate::value::Value;
+use std::path::Path;
+
+/// Activate the project at `path`, which must be either a directory that contains a
+/// `Project.toml` or `JuliaProject.toml`, or the path to a project file. A relative path is
+/// resolved against the current working directory. This is equivalent to `Pkg.activate(path)`,
+/// but doesn't require loading `Pkg`. Returns `JlrsError::ProjectNotFound` if `path` doesn't
+/// exist or doesn't contain a project file. A new dynamic frame is created which needs two slots
+/// on the GC stack.
+pub fn activate_project<'frame, F, P>(frame: &mut F, path: P) -> JlrsResult<()>
+where
+    F: Frame<'frame>,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    if !path.exists() {
+        Err(JlrsError::ProjectNotFound(path.to_string_lossy().into()))?;
+    }
+
+    frame.dynamic_frame(|frame| {
+        let global = frame.global();
+        let path_jl_str = Value::new(frame, path.to_string_lossy())?;
+        let activated = Module::main(global)
+            .submodule("Jlrs")?
+            .function("activateproject")?
+            .call1(frame, path_jl_str)?
+            .into_jlrs_result(frame)?
+            .cast::<bool>()?;
+
+        if !activated {
+            Err(JlrsError::ProjectNotFound(path.to_string_lossy().into()))?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Instantiate the active project in offline mode, only packages that are already available in
+/// the depot are used. If dependencies of the project aren't installed, the project isn't
+/// instantiated and `JlrsError::MissingPackages` with the names of these dependencies is
+/// returned. If `Pkg.instantiate` throws an exception, it's returned as a
+/// `JlrsError::Exception`.
+///
+/// This function loads `Pkg` if it hasn't been loaded yet. A new dynamic frame is created which
+/// needs three slots on the GC stack.
+pub fn instantiate_offline<'frame, F>(frame: &mut F) -> JlrsResult<()>
+where
+    F: Frame<'frame>,
+{
+    frame.dynamic_frame(|frame| {
+        let global = frame.global();
+        let missing = Module::main(global)
+            .submodule("Jlrs")?
+            .function("instantiateoffline")?
+            .call0(frame)?
+            .into_jlrs_result(frame)?
+            .cast::<Array>()?;
+
+        // Safety: the names are only read while the array is rooted.
+        let missing = unsafe { missing.value_data(frame)? }
+            .as_slice()
+            .iter()
+            .map(|name| name.cast::<String>())
+            .collect::<JlrsResult<Vec<_>>>()?;
+
+        if !missing.is_empty() {
+            Err(JlrsError::MissingPackages(missing))?;
+        }
+
+        Ok(())
+    })
+}
@@ -5,8 +5,9 @@
 // that should not be freed.
 //
 // Rust doesn't really like dynamically sized types, and as far as I'm aware something like alloca
-// is unavailable. As a workaround jlrs creates a boxed array to contain these frames. A new frame
-// is pushed when a frame is created, and is popped when the frame is dropped.
+// is unavailable. As a workaround jlrs allocates its own stack to contain these frames, which
+// grows when it runs out of space. A new frame is pushed when a frame is created, and is popped
+// when the frame is dropped.
 //
 // Compared to the possibilities of the macros, jlrs is a bit more flexible. For example, the
 // DynamicFrame dynamically grows its associated GC frame which is not possible in the C API and
@@ -17,6 +18,8 @@
 // from existing in Rust. Similarly, thanks to lifetimes we can enforce that a value can't live
 // longer than its frame while C can offer no such guarantees.
 
+#[cfg(feature = "gc-diagnostics")]
+use crate::diagnostics::{self, StackCounters};
 use crate::error::{AllocError, JlrsResult};
 use crate::frame::{FrameIdx, Output};
 use crate::mode::Mode;
@@ -24,49 +27,179 @@ use crate::value::{Value, Values};
 use std::ffi::c_void;
 use std::marker::PhantomData;
 use std::ptr::null_mut;
+#[cfg(feature = "gc-diagnostics")]
+use std::sync::Arc;
 
 pub(crate) enum Static {}
 pub(crate) enum Dynamic {}
 
-pub(crate) struct RawStack(Box<[*mut c_void]>);
+// The GC stack is made up of one or more segments. Frames are pushed to the current segment, if
+// it doesn't have enough free slots the next segment is used, which is allocated if it doesn't
+// exist yet. Segments are never moved or freed while they're in use, because the GC and the
+// frames below the current one contain pointers into them. A frame never spans multiple
+// segments, if a dynamic frame runs out of space in its segment it's extended by pushing a
+// frame to the next segment which is popped together with the original frame.
+//
+// The first slot of every segment contains the number of slots of that segment that are in use.
+pub(crate) struct RawStack {
+    segments: Vec<Box<[*mut c_void]>>,
+    current: usize,
+    max_size: Option<usize>,
+    #[cfg(feature = "gc-diagnostics")]
+    usage: Arc<StackCounters>,
+}
 
 impl RawStack {
-    pub(crate) unsafe fn new(stack_size: usize) -> Self {
-        debug_assert!(stack_size > 0);
-        let mut raw = vec![null_mut(); stack_size];
-        raw[0] = 1 as _;
-        let boxed = raw.into_boxed_slice();
-        RawStack(boxed)
+    // The name is used to identify the stack when the `gc-diagnostics` feature is enabled.
+    #[cfg_attr(not(feature = "gc-diagnostics"), allow(unused_variables))]
+    pub(crate) unsafe fn new(stack_size: usize, max_size: Option<usize>, name: &str) -> Self {
+        let stack = RawStack {
+            segments: vec![Self::new_segment(stack_size.max(1))],
+            current: 0,
+            max_size,
+            #[cfg(feature = "gc-diagnostics")]
+            usage: diagnostics::register_stack(name),
+        };
+
+        stack.record_usage();
+        stack
     }
 
-    pub(crate) fn as_mut<'original: 'scope, 'scope>(
-        &'original mut self,
-    ) -> &'scope mut [*mut c_void] {
-        &mut self.0
+    fn new_segment(len: usize) -> Box<[*mut c_void]> {
+        let mut raw = vec![null_mut(); len];
+        raw[0] = 1 as _;
+        raw.into_boxed_slice()
     }
 
+    // The total number of slots of all segments.
     pub(crate) fn size(&self) -> usize {
-        self.0.len()
+        self.segments.iter().map(|s| s.len()).sum()
+    }
+
+    pub(crate) fn max_size(&self) -> Option<usize> {
+        self.max_size
+    }
+
+    pub(crate) fn base(&mut self) -> &mut [*mut c_void] {
+        &mut self.segments[0]
+    }
+
+    pub(crate) fn print_memory(&self) {
+        println!("{:?}", &self.segments);
+    }
+
+    #[inline(always)]
+    fn record_usage(&self) {
+        #[cfg(feature = "gc-diagnostics")]
+        {
+            let used = self.segments[..=self.current]
+                .iter()
+                .map(|s| s[0] as usize - 1)
+                .sum();
+            self.usage.update(self.size(), used);
+        }
+    }
+
+    fn free(&self, segment: usize) -> usize {
+        let segment = &self.segments[segment];
+        segment.len() - segment[0] as usize
+    }
+
+    // Returns the index of a segment that has at least `n` free slots. If the current segment is
+    // too small the next one is used, it's allocated or replaced with a larger one if necessary.
+    fn reserve(&mut self, n: usize) -> Result<usize, AllocError> {
+        if self.free(self.current) >= n {
+            return Ok(self.current);
+        }
+
+        let next = self.current + 1;
+        if next < self.segments.len() && self.segments[next].len() > n {
+            self.current = next;
+            return Ok(next);
+        }
+
+        // The segments after the current one aren't in use.
+        self.segments.truncate(next);
+        let mut len = (self.segments[self.current].len() * 2).max(n + 1);
+        if let Some(max_size) = self.max_size {
+            let available = max_size.saturating_sub(self.size());
+            if available <= n {
+                return Err(AllocError::StackOverflow(n, max_size));
+            }
+
+            len = len.min(available);
+        }
+
+        self.segments.push(Self::new_segment(len));
+        self.current = next;
+        Ok(next)
+    }
+
+    unsafe fn push_frame<M: Mode>(
+        &mut self,
+        capacity: usize,
+        reserve: usize,
+    ) -> Result<FrameIdx, AllocError> {
+        let segment = self.reserve(capacity + reserve + 2)?;
+        let base = self.segments[0].as_mut_ptr();
+        let stack = &mut self.segments[segment];
+        let size = stack[0] as usize;
+
+        stack[size] = (capacity << 1) as _;
+        for i in 0..capacity {
+            stack[size + 2 + i] = null_mut();
+        }
+
+        M::push_frame(base, stack[size..].as_mut_ptr());
+        stack[0] = (size + capacity + 2) as _;
+        self.record_usage();
+
+        Ok(FrameIdx::new(segment, size + 2))
+    }
+
+    // Pops the frame and everything that has been pushed after it, including the extensions of a
+    // dynamic frame.
+    unsafe fn pop_frame<M: Mode>(&mut self, idx: FrameIdx) {
+        let base = self.segments[0].as_mut_ptr();
+        M::pop_frame(
+            base,
+            self.segments[idx.segment][idx.offset - 2..].as_mut_ptr(),
+        );
+
+        for segment in self.segments[idx.segment + 1..=self.current].iter_mut() {
+            segment[0] = 1 as _;
+        }
+
+        self.segments[idx.segment][0] = (idx.offset - 2) as _;
+        self.current = idx.segment;
+    }
+
+    // Adds a slot to the frame at `idx`, which must be the last frame of its segment and have a
+    // free slot available. Returns the offset of the new slot.
+    unsafe fn grow_frame(&mut self, idx: FrameIdx, value: *mut c_void) -> usize {
+        let stack = &mut self.segments[idx.segment];
+        let size = stack[0] as usize;
+        stack[size] = value;
+        stack[idx.offset - 2] = (stack[idx.offset - 2] as usize + 2) as _;
+        stack[0] = (size + 1) as _;
+        self.record_usage();
+        size
     }
 }
 
 pub(crate) struct StackView<'stack, U: Mode, V> {
-    stack: &'stack mut [*mut c_void],
+    stack: &'stack mut RawStack,
     _u: PhantomData<U>,
     _v: PhantomData<V>,
 }
 
 impl<'stack, M: Mode, V> StackView<'stack, M, V> {
-    pub(crate) fn size(&self) -> usize {
-        self.stack[0] as _
-    }
-
     pub(crate) fn print_memory(&self) {
-        println!("{:?}", &self.stack);
+        self.stack.print_memory()
     }
 
     pub(crate) unsafe fn pop_frame(&mut self, idx: FrameIdx) {
-        M::pop_frame(&mut self.stack, idx)
+        self.stack.pop_frame::<M>(idx)
     }
 
     pub(crate) unsafe fn nest_static<'nested>(&'nested mut self) -> StackView<'nested, M, Static> {
@@ -93,16 +226,25 @@ impl<'stack, M: Mode, V> StackView<'stack, M, V> {
         offset: usize,
         n: usize,
     ) -> Values<'output> {
-        let ptr = self.stack[idx.0 + offset..].as_mut_ptr();
+        let ptr = self.stack.segments[idx.segment][idx.offset + offset..].as_mut_ptr();
         Values::wrap(ptr.cast(), n)
     }
+
+    pub(crate) unsafe fn protect_output<'output>(
+        &mut self,
+        output: Output,
+        value: *mut c_void,
+    ) -> Value<'output, 'static> {
+        self.stack.segments[output.segment][output.offset] = value.cast::<_>();
+        Value::wrap(value.cast::<_>())
+    }
 }
 
 impl<'stack, M> StackView<'stack, M, Dynamic>
 where
     M: Mode,
 {
-    pub(crate) unsafe fn new(stack: &'stack mut [*mut c_void]) -> Self {
+    pub(crate) unsafe fn new(stack: &'stack mut RawStack) -> Self {
         StackView {
             stack,
             _u: PhantomData,
@@ -111,64 +253,56 @@ where
     }
 
     pub(crate) unsafe fn new_frame(&mut self) -> JlrsResult<FrameIdx> {
-        if self.size() + 2 >= self.stack.len() {
-            return Err(Box::new(
-                AllocError::StackOverflow(2, self.stack.len()).into(),
-            ));
+        self.stack
+            .push_frame::<M>(0, 0)
+            .map_err(|e| Box::new(e.into()))
+    }
+
+    // Ensure the frame can grow by `n` slots without being extended, `tail` is the last frame that
+    // has been pushed by a dynamic frame. If there's not enough space left in its segment, a new
+    // frame is pushed to a segment that does have enough space and `tail` is updated.
+    pub(crate) unsafe fn reserve(
+        &mut self,
+        tail: &mut FrameIdx,
+        n: usize,
+    ) -> Result<(), AllocError> {
+        if self.stack.free(tail.segment) < n {
+            *tail = self.stack.push_frame::<M>(0, n)?;
         }
 
-        let size = self.size();
-        Ok(M::new_dynamic_frame(&mut self.stack, size))
+        Ok(())
+    }
+
+    // The offset of the next slot relative to `tail`.
+    pub(crate) fn next_offset(&self, tail: FrameIdx) -> usize {
+        self.stack.segments[tail.segment][0] as usize - tail.offset
     }
 
     pub(crate) unsafe fn new_output<'output>(
         &mut self,
-        idx: FrameIdx,
+        tail: &mut FrameIdx,
     ) -> JlrsResult<Output<'output>> {
-        if self.size() >= self.stack.len() {
-            return Err(Box::new(
-                AllocError::StackOverflow(1, self.stack.len()).into(),
-            ));
-        }
-
-        let sz = self.size();
-        self.stack[sz] = null_mut();
-        self.stack[idx.0 - 2] = (self.stack[idx.0 - 2] as usize + 2) as _;
-        self.stack[0] = (self.size() + 1) as _;
-        Ok(Output::new(sz))
+        self.reserve(tail, 1).map_err(|e| Box::new(e.into()))?;
+        let offset = self.stack.grow_frame(*tail, null_mut());
+        Ok(Output::new(tail.segment, offset))
     }
 
     pub(crate) unsafe fn protect<'output>(
         &mut self,
-        idx: FrameIdx,
+        tail: &mut FrameIdx,
         value: *mut c_void,
     ) -> Result<Value<'output, 'static>, AllocError> {
-        if self.size() == self.stack.len() {
-            return Err(AllocError::StackOverflow(1, self.stack.len()));
-        }
-
-        self.stack[self.size()] = value.cast::<_>();
-        self.stack[idx.0 - 2] = (self.stack[idx.0 - 2] as usize + 2) as _;
-        self.stack[0] = (self.size() + 1) as _;
-
+        self.reserve(tail, 1)?;
+        self.stack.grow_frame(*tail, value.cast::<_>());
         Ok(Value::wrap(value.cast::<_>()))
     }
-
-    pub(crate) unsafe fn protect_output<'output>(
-        &mut self,
-        output: Output,
-        value: *mut c_void,
-    ) -> Value<'output, 'static> {
-        self.stack[output.offset] = value.cast::<_>();
-        Value::wrap(value.cast::<_>())
-    }
 }
 
 impl<'stack, M> StackView<'stack, M, Static>
 where
     M: Mode,
 {
-    pub(crate) unsafe fn new(stack: &'stack mut [*mut c_void]) -> Self {
+    pub(crate) unsafe fn new(stack: &'stack mut RawStack) -> Self {
         StackView {
             stack,
             _u: PhantomData,
@@ -177,14 +311,9 @@ where
     }
 
     pub(crate) unsafe fn new_frame(&mut self, capacity: usize) -> JlrsResult<FrameIdx> {
-        let size = self.size();
-        if size + capacity + 2 >= self.stack.len() {
-            return Err(Box::new(
-                AllocError::StackOverflow(capacity + 2, self.stack.len()).into(),
-            ));
-        }
-
-        Ok(M::new_frame(&mut self.stack, size, capacity))
+        self.stack
+            .push_frame::<M>(capacity, 0)
+            .map_err(|e| Box::new(e.into()))
     }
 
     pub(crate) unsafe fn new_output<'output>(
@@ -192,7 +321,7 @@ where
         idx: FrameIdx,
         offset: usize,
     ) -> Output<'output> {
-        Output::new(idx.0 + offset)
+        Output::new(idx.segment, idx.offset + offset)
     }
 
     pub(crate) unsafe fn protect<'output>(
@@ -201,20 +330,19 @@ where
         offset: usize,
         value: *mut c_void,
     ) -> Value<'output, 'static> {
-        self.stack[idx.0 + offset] = value;
+        self.stack.segments[idx.segment][idx.offset + offset] = value;
         Value::wrap(value.cast())
     }
 }
 
 #[cfg(all(feature = "async", target_os = "linux"))]
 pub(crate) mod multitask {
+    use super::RawStack;
     use crate::error::{AllocError, JlrsError, JlrsResult};
     use crate::traits::multitask::JuliaTask;
     use async_std::task::JoinHandle;
     use jl_sys::jl_get_ptls_states;
     use std::collections::VecDeque;
-    use std::ffi::c_void;
-    use std::ptr::null_mut;
 
     struct Node<T> {
         value: T,
@@ -264,38 +392,41 @@ pub(crate) mod multitask {
     }
 
     pub(crate) struct TaskStack {
-        pub(crate) raw: Box<[*mut c_void]>,
+        pub(crate) raw: RawStack,
     }
 
     impl TaskStack {
-        pub(crate) unsafe fn new(stack_size: usize) -> Self {
-            let v = vec![null_mut(); stack_size];
-
+        pub(crate) unsafe fn new(
+            stack_size: usize,
+            max_stack_size: Option<usize>,
+            name: &str,
+        ) -> Self {
             Self {
-                raw: v.into_boxed_slice(),
+                raw: RawStack::new(stack_size, max_stack_size, name),
             }
         }
 
         pub(crate) unsafe fn init(&mut self) -> JlrsResult<()> {
-            if self.raw.len() < 3 {
+            let base = self.raw.base();
+            if base.len() < 3 {
                 Err(JlrsError::AllocError(AllocError::StackOverflow(
                     3,
-                    self.raw.len(),
+                    base.len(),
                 )))?;
             }
 
             let rtls = &mut *jl_get_ptls_states();
 
-            self.raw[0] = 3 as _;
-            self.raw[2] = rtls.pgcstack as _;
+            base[0] = 3 as _;
+            base[2] = rtls.pgcstack as _;
 
-            rtls.pgcstack = self.raw[1..].as_mut_ptr().cast();
+            rtls.pgcstack = base[1..].as_mut_ptr().cast();
             Ok(())
         }
 
         #[allow(dead_code)]
         pub fn print_memory(&self) {
-            println!("{:?}", self.raw.as_ref());
+            self.raw.print_memory();
         }
     }
 
@@ -308,11 +439,22 @@ pub(crate) mod multitask {
     }
 
     impl<T, R> MultitaskStack<T, R> {
-        pub(crate) unsafe fn new(n_tasks: usize, stack_size: usize) -> Self {
+        pub(crate) unsafe fn new(
+            n_tasks: usize,
+            stack_size: usize,
+            max_stack_size: Option<usize>,
+        ) -> Self {
             let mut raw = Vec::new();
 
-            for _ in 0..n_tasks + 1 {
-                raw.push(Some(TaskStack::new(stack_size)));
+            // The last stack is used by the runtime itself.
+            for i in 0..n_tasks + 1 {
+                let name = if i < n_tasks {
+                    format!("async task {}", i)
+                } else {
+                    "async runtime".to_string()
+                };
+
+                raw.push(Some(TaskStack::new(stack_size, max_stack_size, &name)));
             }
 
             let running = raw
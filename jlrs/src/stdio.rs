@@ -0,0 +1,193 @@
+//! Redirect Julia's `stdout` and `stderr` to Rust.
+//!
+//! By default everything Julia prints is written directly to the file descriptors of the
+//! process. With [`Julia::redirect_output`] and [`AsyncJulia::redirect_output`] you can replace
+//! Julia's `stdout` or `stderr` with any type that implements `std::io::Write`, and with
+//! [`Julia::capture_output`] everything that's printed while a closure is called is collected
+//! and returned as a [`CapturedOutput`]. A closure that's called with every chunk of output can
+//! be used as a writer by wrapping it in a [`FnWriter`].
+//!
+//! This works by replacing `Base.stdout` and `Base.stderr` with an `IO` type defined in
+//! `Main.Jlrs`, so only output written through these Julia streams is redirected. Output that is
+//! written directly by the C runtime, like the messages printed by `Core.println`, still goes to
+//! the process's file descriptors.
+//!
+//! [`Julia::redirect_output`]: ../struct.Julia.html#method.redirect_output
+//! [`Julia::capture_output`]: ../struct.Julia.html#method.capture_output
+//! [`AsyncJulia::redirect_output`]: ../multitask/struct.AsyncJulia.html#method.redirect_output
+//! [`CapturedOutput`]: struct.CapturedOutput.html
+//! [`FnWriter`]: struct.FnWriter.html
+
+use crate::error::{JlrsError, JlrsResult};
+use crate::global::Global;
+use crate::value::module::Module;
+use crate::value::symbol::Symbol;
+use crate::value::Value;
+use jl_sys::jl_box_voidpointer;
+use std::ffi::c_void;
+use std::io::{Result as IOResult, Write};
+use std::panic;
+use std::sync::{Arc, Mutex};
+
+pub(crate) type Sink = Mutex<Box<dyn Write + Send>>;
+
+/// One of Julia's standard output streams.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl Stream {
+    fn name(self) -> &'static str {
+        match self {
+            Stream::Stdout => "stdout",
+            Stream::Stderr => "stderr",
+        }
+    }
+}
+
+/// The output that was written to Julia's `stdout` and `stderr` while the closure passed to
+/// [`Julia::capture_output`] was called. Invalid UTF-8 is replaced with `U+FFFD`.
+///
+/// [`Julia::capture_output`]: ../struct.Julia.html#method.capture_output
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CapturedOutput {
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Wraps a closure that takes a slice of bytes so it can be used as a writer. The closure is
+/// called with every chunk of output that's written.
+pub struct FnWriter<F>(pub F)
+where
+    F: FnMut(&[u8]) + Send;
+
+impl<F> Write for FnWriter<F>
+where
+    F: FnMut(&[u8]) + Send,
+{
+    fn write(&mut self, buf: &[u8]) -> IOResult<usize> {
+        (self.0)(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> IOResult<()> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    pub(crate) fn take_string(&self) -> String {
+        let mut buffer = self.0.lock().expect("Cannot lock buffer");
+        let bytes = std::mem::take(&mut *buffer);
+        String::from_utf8(bytes).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into())
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> IOResult<usize> {
+        self.0
+            .lock()
+            .expect("Cannot lock buffer")
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> IOResult<()> {
+        Ok(())
+    }
+}
+
+// The writers that are currently active. They must stay alive until Julia no longer uses them.
+#[derive(Default)]
+pub(crate) struct Redirects {
+    stdout: Option<Box<Sink>>,
+    stderr: Option<Box<Sink>>,
+}
+
+impl Redirects {
+    // Replace the writer of `stream` with `writer`, or restore the original stream if `writer`
+    // is `None`.
+    pub(crate) unsafe fn set(
+        &mut self,
+        stream: Stream,
+        writer: Option<Box<dyn Write + Send>>,
+    ) -> JlrsResult<()> {
+        let global = Global::new();
+        let slot = match stream {
+            Stream::Stdout => &mut self.stdout,
+            Stream::Stderr => &mut self.stderr,
+        };
+
+        if let Some(old) = slot.take() {
+            if let Err(e) = restore(global, stream, &old) {
+                // Julia might still use the old writer, so it must not be dropped.
+                Box::leak(old);
+                return Err(e);
+            }
+        }
+
+        if let Some(writer) = writer {
+            let sink = Box::new(Mutex::new(writer));
+            redirect(global, stream, &sink)?;
+            *slot = Some(sink);
+        }
+
+        Ok(())
+    }
+}
+
+// Replace Julia's `stream` with an IO that writes to `sink`. The sink must stay alive until it
+// has been restored.
+pub(crate) unsafe fn redirect(global: Global, stream: Stream, sink: &Sink) -> JlrsResult<()> {
+    call_jlrs(global, "redirectoutput", stream, sink)
+}
+
+// Restore the stream that was replaced when `sink` was installed. If this succeeds, Julia no
+// longer uses the sink and it can be dropped.
+pub(crate) unsafe fn restore(global: Global, stream: Stream, sink: &Sink) -> JlrsResult<()> {
+    call_jlrs(global, "restoreoutput", stream, sink)
+}
+
+unsafe fn call_jlrs(global: Global, func: &str, stream: Stream, sink: &Sink) -> JlrsResult<()> {
+    let sink_ptr = sink as *const Sink as *mut c_void;
+    let stream = Symbol::new(global, stream.name());
+    // The arguments are protected by jl_call, the result is not used.
+    Module::main(global)
+        .submodule("Jlrs")?
+        .function(func)?
+        .call2_unprotected(
+            global,
+            stream.into(),
+            Value::wrap(jl_box_voidpointer(sink_ptr)),
+        )
+        .map_err(|e| JlrsError::Other(format!("{} threw {}", func, e.type_name()).into()))?;
+
+    Ok(())
+}
+
+// This function is set as a constant in `Main.Jlrs` and called using `ccall` to write the output
+// of a redirected stream to its sink.
+pub(crate) unsafe extern "C" fn write_rust(
+    sink: *mut c_void,
+    data: *const u8,
+    len: usize,
+) -> usize {
+    let sink = &*(sink as *const Sink);
+    let bytes = std::slice::from_raw_parts(data, len);
+
+    // A panic must not unwind into Julia, the sink is poisoned if the writer panics.
+    let written = panic::catch_unwind(|| match sink.lock() {
+        Ok(mut writer) => match writer.write_all(bytes) {
+            Ok(_) => len,
+            Err(_) => 0,
+        },
+        Err(_) => 0,
+    });
+
+    written.unwrap_or(0)
+}
@@ -35,6 +35,8 @@ pub mod bits_union;
 pub mod cast;
 pub mod frame;
 pub mod gc;
+pub mod into_arguments;
+pub mod into_jlrs_result;
 pub mod into_julia;
 pub mod julia_type;
 pub mod julia_typecheck;
@@ -47,6 +49,8 @@ pub use bits_union::{Align, BitsUnion, Flag};
 pub use cast::Cast;
 pub use frame::Frame;
 pub use gc::Gc;
+pub use into_arguments::{IntoArgument, IntoArguments, TypedArguments};
+pub use into_jlrs_result::IntoJlrsResult;
 pub use into_julia::IntoJulia;
 pub use julia_type::JuliaType;
 pub use julia_typecheck::JuliaTypecheck;
@@ -1,11 +1,12 @@
 use crate::error::{AllocError, JlrsError, JlrsResult};
 #[cfg(all(feature = "async", target_os = "linux"))]
 use crate::frame::AsyncFrame;
-use crate::frame::{DynamicFrame, NullFrame, Output, StaticFrame};
+use crate::frame::{DynamicFrame, FrameSite, NullFrame, Output, StaticFrame};
 use crate::global::Global;
 #[cfg(all(feature = "async", target_os = "linux"))]
 use crate::mode::Async;
 use crate::mode::{Mode, Sync};
+use crate::traits::gc::GcSafeRegion;
 
 /// Functionality shared by [`StaticFrame`] and [`DynamicFrame`]. These structs let you protect
 /// data from garbage collection. The lifetime of a frame is assigned to the values and outputs
@@ -67,30 +68,45 @@ pub trait Frame<'frame>: private::Frame<'frame> {
     fn global(&self) -> Global<'frame> {
         unsafe { Global::new() }
     }
+
+    /// Call `func` in a GC-safe region and return its result. While Rust code is running, other
+    /// threads that want to run the garbage collector must wait until it reaches a safepoint,
+    /// which doesn't happen until Julia is called again. In a GC-safe region the GC can run
+    /// without waiting for this thread, so long-running computations that don't involve Julia
+    /// don't stall other threads, eg tasks started with [`Value::call_async`]. When `func`
+    /// returns or panics, the thread waits until the GC has finished if it's running.
+    ///
+    /// Safety: `func` must not access any Julia data, including values and arrays borrowed from
+    /// Julia, and must not call into Julia.
+    ///
+    /// [`Value::call_async`]: ../value/struct.Value.html#method.call_async
+    unsafe fn gc_safe<T, F: FnOnce() -> T>(&mut self, func: F) -> T {
+        let _region = GcSafeRegion::enter();
+        func()
+    }
 }
 
 impl<'frame, M: Mode> Frame<'frame> for StaticFrame<'frame, M> {
+    #[cfg_attr(feature = "gc-diagnostics", track_caller)]
     fn frame<'nested, T, F: FnOnce(&mut StaticFrame<'nested, M>) -> JlrsResult<T>>(
         &'nested mut self,
         capacity: usize,
         func: F,
     ) -> JlrsResult<T> {
-        let mut frame = unsafe { self.nested_frame(capacity).unwrap() };
+        let mut frame = unsafe { self.nested_frame(capacity, FrameSite::caller())? };
         func(&mut frame)
     }
 
+    #[cfg_attr(feature = "gc-diagnostics", track_caller)]
     fn dynamic_frame<'nested, T, F: FnOnce(&mut DynamicFrame<'nested, M>) -> JlrsResult<T>>(
         &'nested mut self,
         func: F,
     ) -> JlrsResult<T> {
+        let site = FrameSite::caller();
         unsafe {
             let mut view = self.memory.nest_dynamic();
             let idx = view.new_frame()?;
-            let mut frame = DynamicFrame {
-                idx,
-                len: 0,
-                memory: view,
-            };
+            let mut frame = DynamicFrame::new(idx, view, site);
 
             func(&mut frame)
         }
@@ -98,6 +114,7 @@ impl<'frame, M: Mode> Frame<'frame> for StaticFrame<'frame, M> {
 
     fn output(&mut self) -> JlrsResult<Output<'frame>> {
         if self.capacity == self.len {
+            self.site.overflow();
             return Err(AllocError::FrameOverflow(1, self.len).into());
         }
 
@@ -120,28 +137,26 @@ impl<'frame, M: Mode> Frame<'frame> for StaticFrame<'frame, M> {
 }
 
 impl<'frame, M: Mode> Frame<'frame> for DynamicFrame<'frame, M> {
+    #[cfg_attr(feature = "gc-diagnostics", track_caller)]
     fn dynamic_frame<'nested, T, F: FnOnce(&mut DynamicFrame<'nested, M>) -> JlrsResult<T>>(
         &'nested mut self,
         func: F,
     ) -> JlrsResult<T> {
-        let mut frame = unsafe { self.nested_frame().unwrap() };
+        let mut frame = unsafe { self.nested_frame(FrameSite::caller())? };
         func(&mut frame)
     }
 
+    #[cfg_attr(feature = "gc-diagnostics", track_caller)]
     fn frame<'nested, T, F: FnOnce(&mut StaticFrame<'nested, M>) -> JlrsResult<T>>(
         &'nested mut self,
         capacity: usize,
         func: F,
     ) -> JlrsResult<T> {
+        let site = FrameSite::caller();
         unsafe {
             let mut view = self.memory.nest_static();
             let idx = view.new_frame(capacity)?;
-            let mut frame = StaticFrame {
-                idx,
-                capacity,
-                len: 0,
-                memory: view,
-            };
+            let mut frame = StaticFrame::with_capacity(idx, capacity, view, site);
 
             func(&mut frame)
         }
@@ -149,7 +164,7 @@ impl<'frame, M: Mode> Frame<'frame> for DynamicFrame<'frame, M> {
 
     fn output(&mut self) -> JlrsResult<Output<'frame>> {
         unsafe {
-            let out = self.memory.new_output(self.idx)?;
+            let out = self.memory.new_output(&mut self.tail)?;
             self.len += 1;
             Ok(out)
         }
@@ -193,36 +208,34 @@ impl<'frame> Frame<'frame> for NullFrame<'frame> {
 
 #[cfg(all(feature = "async", target_os = "linux"))]
 impl<'frame> Frame<'frame> for AsyncFrame<'frame> {
+    #[cfg_attr(feature = "gc-diagnostics", track_caller)]
     fn frame<'nested, T, F: FnOnce(&mut StaticFrame<'nested, Async>) -> JlrsResult<T>>(
         &'nested mut self,
         capacity: usize,
         func: F,
     ) -> JlrsResult<T> {
+        let site = FrameSite::caller();
         unsafe {
             let mut view = self.memory.nest_static();
             let idx = view.new_frame(capacity)?;
-            let mut frame = StaticFrame {
-                idx,
-                capacity,
-                len: 0,
-                memory: view,
-            };
+            let mut frame = StaticFrame::with_capacity(idx, capacity, view, site);
 
             func(&mut frame)
         }
     }
 
+    #[cfg_attr(feature = "gc-diagnostics", track_caller)]
     fn dynamic_frame<'nested, T, F: FnOnce(&mut DynamicFrame<'nested, Async>) -> JlrsResult<T>>(
         &'nested mut self,
         func: F,
     ) -> JlrsResult<T> {
-        let mut frame = unsafe { self.nested_frame().unwrap() };
+        let mut frame = unsafe { self.nested_frame(FrameSite::caller())? };
         func(&mut frame)
     }
 
     fn output(&mut self) -> JlrsResult<Output<'frame>> {
         unsafe {
-            let out = self.memory.new_output(self.idx)?;
+            let out = self.memory.new_output(&mut self.tail)?;
             self.len += 1;
             Ok(out)
         }
@@ -242,7 +255,7 @@ pub(crate) mod private {
     use crate::error::AllocError;
     #[cfg(all(feature = "async", target_os = "linux"))]
     use crate::frame::AsyncFrame;
-    use crate::frame::{DynamicFrame, FrameIdx, NullFrame, Output, StaticFrame};
+    use crate::frame::{DynamicFrame, NullFrame, Output, StaticFrame};
     #[cfg(all(feature = "async", target_os = "linux"))]
     use crate::mode::Async;
     use crate::mode::{Mode, Sync};
@@ -290,6 +303,7 @@ pub(crate) mod private {
             _: Internal,
         ) -> Result<Value<'frame, 'static>, AllocError> {
             if self.capacity == self.len {
+                self.site.overflow();
                 return Err(AllocError::FrameOverflow(1, self.len));
             }
 
@@ -309,6 +323,7 @@ pub(crate) mod private {
         ) -> Result<Values<'frame>, AllocError> {
             unsafe {
                 if self.capacity < self.len + values.len() {
+                    self.site.overflow();
                     return Err(AllocError::FrameOverflow(values.len(), self.capacity()));
                 }
 
@@ -330,6 +345,7 @@ pub(crate) mod private {
         ) -> Result<Values<'frame>, AllocError> {
             unsafe {
                 if self.capacity < self.len + values.len() {
+                    self.site.overflow();
                     return Err(AllocError::FrameOverflow(values.len(), self.capacity()));
                 }
 
@@ -350,10 +366,7 @@ pub(crate) mod private {
             value: *mut jl_value_t,
             _: Internal,
         ) -> Value<'output, 'static> {
-            unsafe {
-                self.memory
-                    .protect(FrameIdx::default(), output.offset, value.cast())
-            }
+            unsafe { self.memory.protect_output(output, value.cast()) }
         }
     }
 
@@ -364,7 +377,7 @@ pub(crate) mod private {
             value: *mut jl_value_t,
             _: Internal,
         ) -> Result<Value<'frame, 'static>, AllocError> {
-            let out = self.memory.protect(self.idx, value.cast())?;
+            let out = self.memory.protect(&mut self.tail, value.cast())?;
             self.len += 1;
             Ok(out)
         }
@@ -375,21 +388,17 @@ pub(crate) mod private {
             _: Internal,
         ) -> Result<Values<'frame>, AllocError> {
             unsafe {
-                let offset = self.len;
-                // TODO: check capacity
+                // The values must be stored in consecutive slots.
+                self.memory.reserve(&mut self.tail, values.len())?;
+                let offset = self.memory.next_offset(self.tail);
 
                 for value in values {
-                    match self.memory.protect(self.idx, value.into_julia().cast()) {
-                        Ok(_) => (),
-                        Err(AllocError::StackOverflow(_, n)) => {
-                            return Err(AllocError::StackOverflow(values.len(), n))
-                        }
-                        _ => unreachable!(),
-                    }
+                    self.memory
+                        .protect(&mut self.tail, value.into_julia().cast())?;
                     self.len += 1;
                 }
 
-                Ok(self.memory.as_values(self.idx, offset, values.len()))
+                Ok(self.memory.as_values(self.tail, offset, values.len()))
             }
         }
 
@@ -399,15 +408,17 @@ pub(crate) mod private {
             _: Internal,
         ) -> Result<Values<'frame>, AllocError> {
             unsafe {
-                let offset = self.len;
-                // TODO: check capacity in advance
+                // The values must be stored in consecutive slots.
+                self.memory.reserve(&mut self.tail, values.len())?;
+                let offset = self.memory.next_offset(self.tail);
 
                 for value in values {
-                    self.memory.protect(self.idx, value.into_julia().cast())?;
+                    self.memory
+                        .protect(&mut self.tail, value.into_julia().cast())?;
                     self.len += 1;
                 }
 
-                Ok(self.memory.as_values(self.idx, offset, values.len()))
+                Ok(self.memory.as_values(self.tail, offset, values.len()))
             }
         }
 
@@ -466,7 +477,7 @@ pub(crate) mod private {
             value: *mut jl_value_t,
             _: Internal,
         ) -> Result<Value<'frame, 'static>, AllocError> {
-            let out = self.memory.protect(self.idx, value.cast())?;
+            let out = self.memory.protect(&mut self.tail, value.cast())?;
             self.len += 1;
             Ok(out)
         }
@@ -477,21 +488,17 @@ pub(crate) mod private {
             _: Internal,
         ) -> Result<Values<'frame>, AllocError> {
             unsafe {
-                let offset = self.len;
-                // TODO: check capacity
+                // The values must be stored in consecutive slots.
+                self.memory.reserve(&mut self.tail, values.len())?;
+                let offset = self.memory.next_offset(self.tail);
 
                 for value in values {
-                    match self.memory.protect(self.idx, value.into_julia().cast()) {
-                        Ok(_) => (),
-                        Err(AllocError::StackOverflow(_, n)) => {
-                            return Err(AllocError::StackOverflow(values.len(), n))
-                        }
-                        _ => unreachable!(),
-                    }
+                    self.memory
+                        .protect(&mut self.tail, value.into_julia().cast())?;
                     self.len += 1;
                 }
 
-                Ok(self.memory.as_values(self.idx, offset, values.len()))
+                Ok(self.memory.as_values(self.tail, offset, values.len()))
             }
         }
 
@@ -501,15 +508,17 @@ pub(crate) mod private {
             _: Internal,
         ) -> Result<Values<'frame>, AllocError> {
             unsafe {
-                let offset = self.len;
-                // TODO: check capacity in advance
+                // The values must be stored in consecutive slots.
+                self.memory.reserve(&mut self.tail, values.len())?;
+                let offset = self.memory.next_offset(self.tail);
 
                 for value in values {
-                    self.memory.protect(self.idx, value.into_julia().cast())?;
+                    self.memory
+                        .protect(&mut self.tail, value.into_julia().cast())?;
                     self.len += 1;
                 }
 
-                Ok(self.memory.as_values(self.idx, offset, values.len()))
+                Ok(self.memory.as_values(self.tail, offset, values.len()))
             }
         }
 
@@ -1,8 +1,26 @@
 //! Control the garbage collector.
 
-use crate::traits::Frame;
+use crate::error::{JlrsError, JlrsResult};
+use crate::global::Global;
+use crate::traits::{Frame, IntoJulia};
+use crate::value::module::Module;
+use crate::value::Value;
 use crate::Julia;
-use jl_sys::{jl_gc_collect, jl_gc_collection_t, jl_gc_enable, jl_gc_is_enabled};
+use jl_sys::{
+    jl_gc_collect, jl_gc_collection_t, jl_gc_enable, jl_gc_is_enabled, jl_gc_safepoint,
+    jl_get_ptls_states, jl_value_t,
+};
+use std::os::raw::c_int;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicI8, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+type GcCallback = Box<dyn FnMut(bool) + Send>;
+
+static CALLBACKS_ENABLED: AtomicBool = AtomicBool::new(false);
+static START_CALLBACKS: Mutex<Vec<GcCallback>> = Mutex::new(Vec::new());
+static END_CALLBACKS: Mutex<Vec<GcCallback>> = Mutex::new(Vec::new());
 
 /// The different collection modes.
 #[derive(Debug, Copy, Clone)]
@@ -12,7 +30,46 @@ pub enum GcCollection {
     Incremental = 2,
 }
 
-/// This trait is used to enable and disable the garbage collector and to force a collection.
+/// When a callback registered with [`Gc::add_gc_callback`] is called.
+///
+/// [`Gc::add_gc_callback`]: trait.Gc.html#method.add_gc_callback
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GcPhase {
+    /// Before a collection starts.
+    Start,
+    /// After a collection has finished.
+    End,
+}
+
+/// Statistics of the garbage collector, see [`Gc::gc_stats`]. These are the same counters that
+/// are available through `Base.gc_num` in Julia, the counters are cumulative since Julia was
+/// initialized.
+///
+/// [`Gc::gc_stats`]: trait.Gc.html#method.gc_stats
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GcStats {
+    /// The total number of bytes that have been allocated.
+    pub allocated_bytes: u64,
+    /// The number of bytes that are currently in use.
+    pub live_bytes: u64,
+    /// The number of calls to `malloc`.
+    pub malloc: u64,
+    /// The number of calls to `realloc`.
+    pub realloc: u64,
+    /// The number of allocations from the pools used for small objects.
+    pub pool_alloc: u64,
+    /// The number of allocations of large objects.
+    pub big_alloc: u64,
+    /// The number of collections.
+    pub collections: u64,
+    /// The number of full collections.
+    pub full_collections: u64,
+    /// The total time the program has been paused by the GC.
+    pub total_pause: Duration,
+}
+
+/// This trait is used to enable and disable the garbage collector, to force a collection, and to
+/// monitor the GC.
 pub trait Gc: private::Gc {
     /// Enable or disable the GC.
     unsafe fn enable_gc(&mut self, on: bool) -> bool {
@@ -28,11 +85,176 @@ pub trait Gc: private::Gc {
     unsafe fn gc_collect(&mut self, mode: GcCollection) {
         jl_gc_collect(mode as jl_gc_collection_t)
     }
+
+    /// Returns the current statistics of the GC.
+    fn gc_stats(&mut self) -> JlrsResult<GcStats> {
+        unsafe {
+            let global = Global::new();
+            let stats = call_jlrs(global, "gcstats", None)?;
+
+            // The result is an `NTuple{9,Int64}`, it's read before anything else is allocated.
+            let data = std::ptr::read(stats.ptr().cast::<[i64; 9]>());
+            Ok(GcStats {
+                allocated_bytes: data[0] as u64,
+                live_bytes: data[1] as u64,
+                malloc: data[2] as u64,
+                realloc: data[3] as u64,
+                pool_alloc: data[4] as u64,
+                big_alloc: data[5] as u64,
+                collections: data[6] as u64,
+                full_collections: data[7] as u64,
+                total_pause: Duration::from_nanos(data[8] as u64),
+            })
+        }
+    }
+
+    /// Set a hint for the amount of memory the GC should try to stay under, in bytes. This is the
+    /// runtime equivalent of [`JuliaBuilder::heap_size_hint`]. Returns an error if this version of
+    /// Julia doesn't support a heap-size hint.
+    ///
+    /// [`JuliaBuilder::heap_size_hint`]: ../../builder/struct.JuliaBuilder.html#method.heap_size_hint
+    fn set_heap_size_hint(&mut self, bytes: u64) -> JlrsResult<()> {
+        unsafe {
+            let global = Global::new();
+            let supported =
+                call_jlrs(global, "setheapsizehint", Some(bytes.into_julia()))?.cast::<bool>()?;
+
+            if !supported {
+                Err(JlrsError::InvalidOption(
+                    "the heap size hint is not supported by this version of Julia".into(),
+                ))?;
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Report that `bytes` of memory that has been allocated outside Julia is kept alive by
+    /// Julia data, or that this memory has been freed if `bytes` is negative. This memory is
+    /// counted in Julia's malloc accounting, which is used by the GC to decide when to run. The
+    /// data of arrays that have been moved from or borrowed from Rust is counted automatically.
+    fn account_external_bytes(&mut self, bytes: isize) {
+        unsafe { account_bytes(bytes) }
+    }
+
+    /// Register a callback that is called before or after every collection. The callback is
+    /// called with `true` if the collection is a full collection.
+    ///
+    /// Callbacks are called while the GC is running, possibly from another thread. They must not
+    /// call into Julia or register other callbacks, and should return quickly. If a callback
+    /// panics the panic is caught and ignored. Callbacks can't be unregistered.
+    fn add_gc_callback<F>(&mut self, phase: GcPhase, callback: F) -> JlrsResult<()>
+    where
+        F: FnMut(bool) + Send + 'static,
+    {
+        let callbacks = match phase {
+            GcPhase::Start => &START_CALLBACKS,
+            GcPhase::End => &END_CALLBACKS,
+        };
+
+        callbacks
+            .lock()
+            .map_err(|_| JlrsError::Other("GC callbacks are poisoned".into()))?
+            .push(Box::new(callback));
+
+        if !CALLBACKS_ENABLED.swap(true, Ordering::AcqRel) {
+            unsafe {
+                let global = Global::new();
+                if let Err(e) = call_jlrs(global, "enablegccallbacks", None) {
+                    CALLBACKS_ENABLED.store(false, Ordering::Release);
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Gc for Julia {}
 impl<'frame, T: Frame<'frame>> Gc for T {}
 
+// This is the equivalent of jl_gc_count_allocd and the accounting done by
+// jl_gc_counted_free_with_size.
+pub(crate) unsafe extern "C" fn account_bytes(bytes: isize) {
+    let ptls = &mut *jl_get_ptls_states();
+    if bytes >= 0 {
+        ptls.gc_num.allocd += bytes as i64;
+        ptls.gc_num.malloc += 1;
+    } else {
+        ptls.gc_num.freed += -(bytes as i64);
+        ptls.gc_num.freecall += 1;
+    }
+}
+
+// The GC states of a thread, see julia_threads.h. A thread in the unsafe state must reach a
+// safepoint before the GC can run.
+const GC_STATE_UNSAFE: i8 = 0;
+const GC_STATE_SAFE: i8 = 2;
+
+// Puts the current thread in the GC-safe state until it's dropped, this is the equivalent of
+// jl_gc_safe_enter and jl_gc_safe_leave.
+pub(crate) struct GcSafeRegion {
+    old_state: i8,
+}
+
+impl GcSafeRegion {
+    pub(crate) unsafe fn enter() -> Self {
+        let old_state = set_gc_state(GC_STATE_SAFE);
+        GcSafeRegion { old_state }
+    }
+}
+
+impl Drop for GcSafeRegion {
+    fn drop(&mut self) {
+        unsafe {
+            set_gc_state(self.old_state);
+            if self.old_state == GC_STATE_UNSAFE {
+                // Wait for the GC if it's running.
+                jl_gc_safepoint();
+            }
+        }
+    }
+}
+
+unsafe fn set_gc_state(state: i8) -> i8 {
+    let ptls = &mut *jl_get_ptls_states();
+    let gc_state = &*(&mut ptls.gc_state as *mut i8).cast::<AtomicI8>();
+    gc_state.swap(state, Ordering::AcqRel)
+}
+
+pub(crate) unsafe extern "C" fn gc_start(full: c_int) {
+    run_callbacks(&START_CALLBACKS, full != 0)
+}
+
+pub(crate) unsafe extern "C" fn gc_end(full: c_int) {
+    run_callbacks(&END_CALLBACKS, full != 0)
+}
+
+fn run_callbacks(callbacks: &Mutex<Vec<GcCallback>>, full: bool) {
+    if let Ok(mut callbacks) = callbacks.lock() {
+        for callback in callbacks.iter_mut() {
+            catch_unwind(AssertUnwindSafe(|| callback(full))).ok();
+        }
+    }
+}
+
+unsafe fn call_jlrs<'base>(
+    global: Global<'base>,
+    name: &str,
+    arg: Option<*mut jl_value_t>,
+) -> JlrsResult<Value<'base, 'static>> {
+    // The argument is protected by jl_call, the result must be used before anything else is
+    // allocated.
+    let func = Module::main(global).submodule("Jlrs")?.function(name)?;
+    let res = match arg {
+        Some(arg) => func.call1_unprotected(global, Value::wrap(arg)),
+        None => func.call0_unprotected(global),
+    };
+
+    Ok(res.map_err(|e| JlrsError::Other(format!("{} threw {}", name, e.type_name()).into()))?)
+}
+
 mod private {
     use super::{Frame, Julia};
     pub trait Gc {}
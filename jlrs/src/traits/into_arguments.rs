@@ -0,0 +1,164 @@
+//! Convert Rust data to the arguments of a function call.
+//!
+//! Methods like [`Value::call_args`] take their arguments as a tuple of Rust data rather than a
+//! slice of `Value`s. Every element of this tuple must implement [`IntoArgument`], this trait is
+//! implemented for all types that implement [`IntoJulia`], for `Vec<T>` if `T` can be used as
+//! the element type of an array, and for `Value`s, `Array`s, `TypedArray`s and `Broadcasted`s.
+//! Tuples with up to twelve elements implement [`IntoArguments`].
+//!
+//! [`Value::call_args`]: ../../value/struct.Value.html#method.call_args
+//! [`IntoArgument`]: trait.IntoArgument.html
+//! [`IntoArguments`]: trait.IntoArguments.html
+//! [`IntoJulia`]: ../trait.IntoJulia.html
+
+use crate::error::JlrsResult;
+use crate::traits::{Frame, IntoJulia, JuliaType, ValidLayout};
+use crate::value::array::{Array, TypedArray};
+use crate::value::broadcast::Broadcasted;
+use crate::value::{Value, MAX_SIZE};
+use jl_sys::jl_datatype_t;
+use smallvec::SmallVec;
+
+/// Trait implemented by types that can be used as an argument of [`Value::call_args`]. Data
+/// that implements [`IntoJulia`] is converted with [`Value::new`], a `Vec<T>` is moved to Julia
+/// as a one-dimensional array with [`Value::move_array`], and `Value`s, arrays and lazy
+/// broadcast expressions are used as they are.
+///
+/// [`Value::call_args`]: ../../value/struct.Value.html#method.call_args
+/// [`Value::new`]: ../../value/struct.Value.html#method.new
+/// [`Value::move_array`]: ../../value/struct.Value.html#method.move_array
+/// [`IntoJulia`]: ../trait.IntoJulia.html
+pub trait IntoArgument<'data> {
+    #[doc(hidden)]
+    fn into_argument<'fr, F: Frame<'fr>>(self, frame: &mut F) -> JlrsResult<Value<'fr, 'data>>;
+}
+
+impl<'data, T: IntoJulia> IntoArgument<'data> for T {
+    fn into_argument<'fr, F: Frame<'fr>>(self, frame: &mut F) -> JlrsResult<Value<'fr, 'data>> {
+        Value::new(frame, self)
+    }
+}
+
+impl<'data, T: IntoJulia + JuliaType> IntoArgument<'data> for Vec<T> {
+    fn into_argument<'fr, F: Frame<'fr>>(self, frame: &mut F) -> JlrsResult<Value<'fr, 'data>> {
+        let n = self.len();
+        Value::move_array(frame, self, n)
+    }
+}
+
+impl<'value, 'data> IntoArgument<'data> for Value<'value, 'data> {
+    fn into_argument<'fr, F: Frame<'fr>>(self, _: &mut F) -> JlrsResult<Value<'fr, 'data>> {
+        // The value is already rooted in a frame that outlives the frame used to call the
+        // function.
+        unsafe { Ok(std::mem::transmute::<Value<'value, 'data>, Value<'fr, 'data>>(self)) }
+    }
+}
+
+impl<'value, 'data> IntoArgument<'data> for Array<'value, 'data> {
+    fn into_argument<'fr, F: Frame<'fr>>(self, frame: &mut F) -> JlrsResult<Value<'fr, 'data>> {
+        self.as_value().into_argument(frame)
+    }
+}
+
+impl<'value, 'data, T> IntoArgument<'data> for TypedArray<'value, 'data, T>
+where
+    T: Copy + ValidLayout,
+{
+    fn into_argument<'fr, F: Frame<'fr>>(self, frame: &mut F) -> JlrsResult<Value<'fr, 'data>> {
+        self.as_value().into_argument(frame)
+    }
+}
+
+impl<'value, 'data> IntoArgument<'data> for Broadcasted<'value, 'data> {
+    fn into_argument<'fr, F: Frame<'fr>>(self, frame: &mut F) -> JlrsResult<Value<'fr, 'data>> {
+        self.as_value().into_argument(frame)
+    }
+}
+
+/// Trait implemented by tuples whose elements implement [`IntoArgument`]. Tuples with up to
+/// twelve elements are supported.
+///
+/// [`IntoArgument`]: trait.IntoArgument.html
+pub trait IntoArguments<'data> {
+    #[doc(hidden)]
+    fn into_arguments<'fr, F: Frame<'fr>>(
+        self,
+        frame: &mut F,
+    ) -> JlrsResult<SmallVec<[Value<'fr, 'data>; MAX_SIZE]>>;
+}
+
+macro_rules! impl_into_arguments {
+    ($($name:ident),*) => {
+        impl<'data, $($name),*> IntoArguments<'data> for ($($name,)*)
+        where
+            $($name: IntoArgument<'data>),*
+        {
+            #[allow(non_snake_case, unused_variables, unused_mut)]
+            fn into_arguments<'fr, F: Frame<'fr>>(
+                self,
+                frame: &mut F,
+            ) -> JlrsResult<SmallVec<[Value<'fr, 'data>; MAX_SIZE]>> {
+                let ($($name,)*) = self;
+                let mut args = SmallVec::new();
+                $(args.push($name.into_argument(frame)?);)*
+                Ok(args)
+            }
+        }
+    };
+}
+
+impl_into_arguments!();
+impl_into_arguments!(T1);
+impl_into_arguments!(T1, T2);
+impl_into_arguments!(T1, T2, T3);
+impl_into_arguments!(T1, T2, T3, T4);
+impl_into_arguments!(T1, T2, T3, T4, T5);
+impl_into_arguments!(T1, T2, T3, T4, T5, T6);
+impl_into_arguments!(T1, T2, T3, T4, T5, T6, T7);
+impl_into_arguments!(T1, T2, T3, T4, T5, T6, T7, T8);
+impl_into_arguments!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+impl_into_arguments!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+impl_into_arguments!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+impl_into_arguments!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+
+/// Trait implemented by tuples whose elements implement both [`IntoJulia`] and [`JuliaType`].
+/// The argument types of a [`JuliaFunction`] must implement this trait, tuples with up to
+/// twelve elements are supported.
+///
+/// [`IntoJulia`]: ../trait.IntoJulia.html
+/// [`JuliaType`]: ../trait.JuliaType.html
+/// [`JuliaFunction`]: ../../value/julia_function/struct.JuliaFunction.html
+pub unsafe trait TypedArguments: IntoArguments<'static> {
+    #[doc(hidden)]
+    unsafe fn argument_types() -> SmallVec<[*mut jl_datatype_t; MAX_SIZE]>;
+}
+
+macro_rules! impl_typed_arguments {
+    ($($name:ident),*) => {
+        unsafe impl<$($name),*> TypedArguments for ($($name,)*)
+        where
+            $($name: IntoJulia + JuliaType),*
+        {
+            #[allow(unused_mut)]
+            unsafe fn argument_types() -> SmallVec<[*mut jl_datatype_t; MAX_SIZE]> {
+                let mut types = SmallVec::new();
+                $(types.push($name::julia_type());)*
+                types
+            }
+        }
+    };
+}
+
+impl_typed_arguments!();
+impl_typed_arguments!(T1);
+impl_typed_arguments!(T1, T2);
+impl_typed_arguments!(T1, T2, T3);
+impl_typed_arguments!(T1, T2, T3, T4);
+impl_typed_arguments!(T1, T2, T3, T4, T5);
+impl_typed_arguments!(T1, T2, T3, T4, T5, T6);
+impl_typed_arguments!(T1, T2, T3, T4, T5, T6, T7);
+impl_typed_arguments!(T1, T2, T3, T4, T5, T6, T7, T8);
+impl_typed_arguments!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+impl_typed_arguments!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+impl_typed_arguments!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+impl_typed_arguments!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
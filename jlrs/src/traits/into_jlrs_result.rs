@@ -0,0 +1,50 @@
+//! Convert the result of a function call to a `JlrsResult`.
+
+use crate::error::{JlrsResult, JuliaException};
+use crate::traits::Frame;
+use crate::value::CallResult;
+
+/// Calling a function returns a [`CallResult`], which contains the exception as a [`Value`] if
+/// one was thrown. This trait converts such a result to a `JlrsResult`, the exception is
+/// converted to a [`JuliaException`] and returned as `JlrsError::Exception` so it can be
+/// propagated with `?`.
+///
+/// Example:
+///
+/// ```no_run
+/// # use jlrs::prelude::*;
+/// # fn main() {
+/// # let mut julia = unsafe { Julia::init(16).unwrap() };
+/// julia.frame(4, |global, frame| {
+///     let i = Value::new(frame, 2u64)?;
+///     let s = Value::new(frame, "foo")?;
+///     let res = Module::base(global)
+///         .function("+")?
+///         .call2(frame, i, s)?
+///         .into_jlrs_result(frame);
+///
+///     assert!(res.is_err());
+///     Ok(())
+/// }).unwrap();
+/// # }
+/// ```
+///
+/// [`CallResult`]: ../value/type.CallResult.html
+/// [`Value`]: ../value/struct.Value.html
+/// [`JuliaException`]: ../error/struct.JuliaException.html
+pub trait IntoJlrsResult<V> {
+    /// Convert this result to a `JlrsResult`. If an exception was thrown, converting it requires
+    /// a new dynamic frame, see [`JuliaException::new`].
+    ///
+    /// [`JuliaException::new`]: ../error/struct.JuliaException.html#method.new
+    fn into_jlrs_result<'fr, F: Frame<'fr>>(self, frame: &mut F) -> JlrsResult<V>;
+}
+
+impl<'frame, 'data, V> IntoJlrsResult<V> for CallResult<'frame, 'data, V> {
+    fn into_jlrs_result<'fr, F: Frame<'fr>>(self, frame: &mut F) -> JlrsResult<V> {
+        match self {
+            Ok(v) => Ok(v),
+            Err(exc) => Err(JuliaException::new(frame, exc)?.into()),
+        }
+    }
+}
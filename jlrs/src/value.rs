@@ -84,6 +84,8 @@ macro_rules! named_tuple {
 }
 
 use self::array::{Array, Dimensions};
+use self::broadcast::Broadcasted;
+use self::cfunction::{CFunction, CFunctionSignature};
 use self::datatype::{Concrete, DataType};
 use self::module::Module;
 use self::symbol::Symbol;
@@ -93,27 +95,30 @@ use crate::error::{JlrsError, JlrsResult};
 use crate::frame::Output;
 use crate::global::Global;
 use crate::impl_julia_type;
+use crate::interrupt;
+use crate::traits::gc::account_bytes;
+use crate::traits::into_jlrs_result::IntoJlrsResult;
 use crate::traits::{
-    private::Internal, valid_layout::ValidLayout, Cast, Frame, IntoJulia, JuliaType,
+    private::Internal, valid_layout::ValidLayout, Cast, Frame, IntoArguments, IntoJulia, JuliaType,
     JuliaTypecheck, TemporarySymbol,
 };
 use jl_sys::{
     jl_alloc_array_1d, jl_alloc_array_2d, jl_alloc_array_3d, jl_an_empty_string,
     jl_an_empty_vec_any, jl_any_type, jl_apply_array_type, jl_apply_tuple_type_v, jl_apply_type,
-    jl_array_any_type, jl_array_int32_type, jl_array_symbol_type, jl_array_uint8_type,
-    jl_bottom_type, jl_call, jl_call0, jl_call1, jl_call2, jl_call3, jl_datatype_t,
-    jl_diverror_exception, jl_egal, jl_emptytuple, jl_eval_string, jl_exception_occurred, jl_false,
-    jl_field_index, jl_field_isptr, jl_field_names, jl_fieldref, jl_fieldref_noalloc, jl_finalize,
-    jl_gc_add_finalizer, jl_gc_wb, jl_get_kwsorter, jl_get_nth_field, jl_get_nth_field_noalloc,
-    jl_interrupt_exception, jl_is_kind, jl_isa, jl_memory_exception, jl_new_array,
-    jl_new_struct_uninit, jl_new_structv, jl_nfields, jl_nothing, jl_nothing_type, jl_object_id,
-    jl_ptr_to_array, jl_ptr_to_array_1d, jl_readonlymemory_exception, jl_set_nth_field,
-    jl_stackovf_exception, jl_subtype, jl_svec_data, jl_svec_len, jl_true, jl_type_union,
-    jl_type_unionall, jl_typeof, jl_typeof_str, jl_undefref_exception, jl_value_t,
+    jl_array_any_type, jl_array_int32_type, jl_array_symbol_type, jl_array_t, jl_array_uint8_type,
+    jl_bottom_type, jl_datatype_t, jl_diverror_exception, jl_egal, jl_emptytuple,
+    jl_exception_occurred, jl_false, jl_field_index, jl_field_isptr, jl_field_names, jl_fieldref,
+    jl_fieldref_noalloc, jl_finalize, jl_gc_add_finalizer, jl_gc_wb, jl_get_kwsorter,
+    jl_get_nth_field, jl_get_nth_field_noalloc, jl_interrupt_exception, jl_is_kind, jl_isa,
+    jl_memory_exception, jl_new_array, jl_new_struct_uninit, jl_new_structv, jl_nfields,
+    jl_nothing, jl_nothing_type, jl_object_id, jl_ptr_to_array, jl_ptr_to_array_1d,
+    jl_readonlymemory_exception, jl_set_nth_field, jl_stackovf_exception, jl_subtype, jl_svec_data,
+    jl_svec_len, jl_true, jl_type_union, jl_type_unionall, jl_typeof, jl_typeof_str,
+    jl_undefref_exception, jl_value_t,
 };
 use smallvec::SmallVec;
 use std::cell::UnsafeCell;
-use std::ffi::{CStr, CString};
+use std::ffi::{c_void, CStr, CString};
 use std::fmt::{Debug, Formatter, Result as FmtResult};
 use std::marker::PhantomData;
 use std::ptr::null_mut;
@@ -126,13 +131,17 @@ use std::slice;
 pub const MAX_SIZE: usize = 8;
 
 pub mod array;
+pub mod broadcast;
+pub mod cfunction;
 pub mod code_instance;
 pub mod datatype;
 pub mod expr;
+pub mod julia_function;
 pub mod method;
 pub mod method_instance;
 pub mod method_table;
 pub mod module;
+pub mod opaque;
 pub mod simple_vector;
 pub mod string;
 pub mod symbol;
@@ -454,6 +463,8 @@ impl<'frame, 'data> Value<'frame, 'data> {
     /// array with more dimensions, an extra frame is created with a single slot slot, temporarily
     /// taking 3 additional slots.
     ///
+    /// The borrowed data is counted in Julia's malloc accounting until the array is freed.
+    ///
     /// This function returns an error if there are not enough slots available.
     pub fn borrow_array<T, D, V, F>(
         frame: &mut F,
@@ -468,7 +479,11 @@ impl<'frame, 'data> Value<'frame, 'data> {
     {
         unsafe {
             let array = borrow_array(frame, data, dimensions)?;
-            frame.protect(array, Internal).map_err(Into::into)
+            let v = frame
+                .protect(array, Internal)
+                .map_err(JlrsError::AllocError)?;
+            account_borrowed_array(frame.global(), v);
+            Ok(v)
         }
     }
 
@@ -494,7 +509,9 @@ impl<'frame, 'data> Value<'frame, 'data> {
     {
         unsafe {
             let array = borrow_array(frame, data, dimensions)?;
-            Ok(frame.assign_output(output, array, Internal))
+            let v = frame.assign_output(output, array, Internal);
+            account_borrowed_array(frame.global(), v);
+            Ok(v)
         }
     }
 
@@ -504,6 +521,9 @@ impl<'frame, 'data> Value<'frame, 'data> {
     /// with more dimensions, an extra frame is created with a single slot slot, temporarily
     /// taking 3 additional slots.
     ///
+    /// The data is counted in Julia's malloc accounting, and freed by Rust when the array is
+    /// finalized.
+    ///
     /// This function returns an error if there are not enough slots available.
     pub fn move_array<T, D, F>(
         frame: &mut F,
@@ -1161,7 +1181,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
             let cmd = cmd.as_ref();
             let cmd_cstring = CString::new(cmd).map_err(JlrsError::other)?;
             let cmd_ptr = cmd_cstring.as_ptr();
-            let res = jl_eval_string(cmd_ptr);
+            let res = interrupt::eval_string(cmd_ptr);
             try_protect(frame, res)
         }
     }
@@ -1179,7 +1199,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
         unsafe {
             let cmd = cmd.as_ref();
             let cmd_ptr = cmd.as_ptr();
-            let res = jl_eval_string(cmd_ptr);
+            let res = interrupt::eval_string(cmd_ptr);
             try_protect(frame, res)
         }
     }
@@ -1192,7 +1212,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
         F: Frame<'frame>,
     {
         unsafe {
-            let res = jl_call0(self.ptr());
+            let res = interrupt::call0(self.ptr());
             try_protect(frame, res)
         }
     }
@@ -1201,7 +1221,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
     /// garbage collection. This is safe if you won't use the result or if you can guarantee it's
     /// a global value in Julia, e.g. `nothing` or a [`Module`].
     pub unsafe fn call0_unprotected<'base>(self, _: Global<'base>) -> CallResult<'base, 'static> {
-        let res = jl_call0(self.ptr());
+        let res = interrupt::call0(self.ptr());
         let exc = jl_exception_occurred();
 
         if exc.is_null() {
@@ -1223,7 +1243,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
         F: Frame<'frame>,
     {
         unsafe {
-            let res = jl_call1(self.ptr().cast(), arg.ptr());
+            let res = interrupt::call1(self.ptr().cast(), arg.ptr());
             try_protect(frame, res)
         }
     }
@@ -1236,7 +1256,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
         _: Global<'base>,
         arg: Value<'_, 'borrow>,
     ) -> CallResult<'base, 'borrow> {
-        let res = jl_call1(self.ptr().cast(), arg.ptr());
+        let res = interrupt::call1(self.ptr().cast(), arg.ptr());
         let exc = jl_exception_occurred();
 
         if exc.is_null() {
@@ -1259,7 +1279,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
         F: Frame<'frame>,
     {
         unsafe {
-            let res = jl_call2(self.ptr().cast(), arg0.ptr(), arg1.ptr());
+            let res = interrupt::call2(self.ptr().cast(), arg0.ptr(), arg1.ptr());
             try_protect(frame, res)
         }
     }
@@ -1273,7 +1293,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
         arg0: Value<'_, 'borrow>,
         arg1: Value<'_, 'borrow>,
     ) -> CallResult<'base, 'borrow> {
-        let res = jl_call2(self.ptr().cast(), arg0.ptr(), arg1.ptr());
+        let res = interrupt::call2(self.ptr().cast(), arg0.ptr(), arg1.ptr());
         let exc = jl_exception_occurred();
 
         if exc.is_null() {
@@ -1297,7 +1317,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
         F: Frame<'frame>,
     {
         unsafe {
-            let res = jl_call3(self.ptr().cast(), arg0.ptr(), arg1.ptr(), arg2.ptr());
+            let res = interrupt::call3(self.ptr().cast(), arg0.ptr(), arg1.ptr(), arg2.ptr());
             try_protect(frame, res)
         }
     }
@@ -1312,7 +1332,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
         arg1: Value<'_, 'borrow>,
         arg2: Value<'_, 'borrow>,
     ) -> CallResult<'base, 'borrow> {
-        let res = jl_call3(self.ptr().cast(), arg0.ptr(), arg1.ptr(), arg2.ptr());
+        let res = interrupt::call3(self.ptr().cast(), arg0.ptr(), arg1.ptr(), arg2.ptr());
         let exc = jl_exception_occurred();
 
         if exc.is_null() {
@@ -1337,7 +1357,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
         unsafe {
             let args = args.as_mut();
             let n = args.len();
-            let res = jl_call(self.ptr().cast(), args.as_mut_ptr().cast(), n as _);
+            let res = interrupt::call(self.ptr().cast(), args.as_mut_ptr().cast(), n as _);
             try_protect(frame, res)
         }
     }
@@ -1355,7 +1375,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
     {
         let args = args.as_mut();
         let n = args.len();
-        let res = jl_call(self.ptr().cast(), args.as_mut_ptr().cast(), n as _);
+        let res = interrupt::call(self.ptr().cast(), args.as_mut_ptr().cast(), n as _);
         let exc = jl_exception_occurred();
 
         if exc.is_null() {
@@ -1381,7 +1401,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
         let args = args.as_mut();
         let n = args.len();
 
-        let res = jl_call(func, args.as_mut_ptr().cast(), n as _);
+        let res = interrupt::call(func, args.as_mut_ptr().cast(), n as _);
         let exc = jl_exception_occurred();
 
         if exc.is_null() {
@@ -1422,7 +1442,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
         F: Frame<'frame>,
     {
         unsafe {
-            let res = jl_call(self.ptr().cast(), args.ptr(), args.len() as _);
+            let res = interrupt::call(self.ptr().cast(), args.ptr(), args.len() as _);
             try_protect(frame, res)
         }
     }
@@ -1435,7 +1455,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
         _: Global<'base>,
         args: Values,
     ) -> CallResult<'base, 'static> {
-        let res = jl_call(self.ptr().cast(), args.ptr(), args.len() as _);
+        let res = interrupt::call(self.ptr().cast(), args.ptr(), args.len() as _);
         let exc = jl_exception_occurred();
 
         if exc.is_null() {
@@ -1445,6 +1465,269 @@ impl<'fr, 'data> Value<'fr, 'data> {
         }
     }
 
+    /// Call this value as a function with the arguments in `args`, a tuple of Rust data and
+    /// `Value`s. The elements of this tuple are converted to Julia data with [`IntoArgument`] in
+    /// a new dynamic frame, which needs one slot on the GC stack for each element that isn't
+    /// already a `Value`. The result takes one slot in `frame`. Returns the result of this
+    /// function call if no exception is thrown, the exception if one is, or an error if an
+    /// argument can't be converted or no space is left on the stack.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// # use jlrs::prelude::*;
+    /// # fn main() {
+    /// # let mut julia = unsafe { Julia::init(16).unwrap() };
+    /// julia.frame(2, |global, frame| {
+    ///     let x = Value::new(frame, 1u64)?;
+    ///     let res = Module::base(global)
+    ///         .function("+")?
+    ///         .call_args(frame, (x, 2u64, 3u64))?
+    ///         .unwrap();
+    ///
+    ///     assert_eq!(res.cast::<u64>()?, 6);
+    ///     Ok(())
+    /// }).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`IntoArgument`]: ../traits/into_arguments/trait.IntoArgument.html
+    pub fn call_args<'frame, 'borrow, A, F>(
+        self,
+        frame: &mut F,
+        args: A,
+    ) -> JlrsResult<CallResult<'frame, 'borrow>>
+    where
+        'borrow: 'frame,
+        A: IntoArguments<'borrow>,
+        F: Frame<'frame>,
+    {
+        let output = frame.output()?;
+        frame.dynamic_frame(|frame| {
+            let mut args = args.into_arguments(frame)?;
+            Ok(self.with_output(output).call(frame, &mut args))
+        })
+    }
+
+    /// Call this value as a function with the arguments in `args` and cast the result to `T`.
+    /// This works like [`Value::call_args`], except that an exception is returned as
+    /// `JlrsError::Exception` and an error is returned if the result can't be cast to `T`.
+    ///
+    /// [`Value::call_args`]: struct.Value.html#method.call_args
+    pub fn call_args_as<'frame, 'borrow, T, A, F>(
+        self,
+        frame: &mut F,
+        args: A,
+    ) -> JlrsResult<<T as Cast<'frame, 'borrow>>::Output>
+    where
+        'borrow: 'frame,
+        T: Cast<'frame, 'borrow>,
+        A: IntoArguments<'borrow>,
+        F: Frame<'frame>,
+    {
+        self.call_args(frame, args)?
+            .into_jlrs_result(frame)?
+            .cast::<T>()
+    }
+
+    /// Broadcast this value as a function over the arguments in `args`, like `broadcast` or a
+    /// dot call in Julia. The arguments are a tuple of `TypedArray`s, `Array`s, `Value`s, scalars
+    /// and lazy operations created with [`Value::broadcasted`], which are fused with this
+    /// operation. The elements of this tuple are converted to Julia data in a new dynamic frame,
+    /// which needs one slot on the GC stack for each element that isn't already a `Value`. The
+    /// result takes one slot in `frame`. Returns the result if no exception is thrown, the
+    /// exception if one is, or an error if an argument can't be converted or no space is left on
+    /// the stack. See the [`broadcast`] module for more information.
+    ///
+    /// [`Value::broadcasted`]: struct.Value.html#method.broadcasted
+    /// [`broadcast`]: broadcast/index.html
+    pub fn broadcast<'frame, 'borrow, A, F>(
+        self,
+        frame: &mut F,
+        args: A,
+    ) -> JlrsResult<CallResult<'frame, 'borrow>>
+    where
+        'borrow: 'frame,
+        A: IntoArguments<'borrow>,
+        F: Frame<'frame>,
+    {
+        unsafe {
+            let global = frame.global();
+            let func = Module::base(global).function("broadcast")?;
+            call_prefixed(frame, func, &[self.ptr()], args)
+        }
+    }
+
+    /// Broadcast this value as a function over the arguments in `args` and store the result in
+    /// `dest`, like `broadcast!` or a dot call with a dotted assignment in Julia. This works like
+    /// [`Value::broadcast`], except that the result is `dest` if no exception is thrown.
+    ///
+    /// [`Value::broadcast`]: struct.Value.html#method.broadcast
+    pub fn broadcast_into<'frame, 'borrow, A, F>(
+        self,
+        frame: &mut F,
+        dest: Value<'_, 'borrow>,
+        args: A,
+    ) -> JlrsResult<CallResult<'frame, 'borrow>>
+    where
+        'borrow: 'frame,
+        A: IntoArguments<'borrow>,
+        F: Frame<'frame>,
+    {
+        unsafe {
+            let global = frame.global();
+            let func = Module::base(global).function("broadcast!")?;
+            call_prefixed(frame, func, &[self.ptr(), dest.ptr()], args)
+        }
+    }
+
+    /// Create a lazy operation that broadcasts this value as a function over the arguments in
+    /// `args` with `Base.Broadcast.broadcasted`. The result can be used as an argument of
+    /// another broadcast operation to fuse them, and is evaluated with
+    /// [`Broadcasted::materialize`] or [`Broadcasted::materialize_into`]. The arguments are
+    /// handled as in [`Value::broadcast`].
+    ///
+    /// [`Broadcasted::materialize`]: broadcast/struct.Broadcasted.html#method.materialize
+    /// [`Broadcasted::materialize_into`]: broadcast/struct.Broadcasted.html#method.materialize_into
+    /// [`Value::broadcast`]: struct.Value.html#method.broadcast
+    pub fn broadcasted<'frame, 'borrow, A, F>(
+        self,
+        frame: &mut F,
+        args: A,
+    ) -> JlrsResult<CallResult<'frame, 'borrow, Broadcasted<'frame, 'borrow>>>
+    where
+        'borrow: 'frame,
+        A: IntoArguments<'borrow>,
+        F: Frame<'frame>,
+    {
+        unsafe {
+            let global = frame.global();
+            let func = Module::base(global)
+                .submodule("Broadcast")?
+                .function("broadcasted")?;
+            let res = call_prefixed(frame, func, &[self.ptr()], args)?;
+            Ok(res.map(|v| Broadcasted::wrap(v)))
+        }
+    }
+
+    /// Call this value as a function that takes several arguments with `Base.invokelatest`,
+    /// this takes one slot on the GC stack. The function is called in the latest world age,
+    /// which is also the case for the other methods that call a function because `jl_call` sets
+    /// the world age to the latest world before calling it. The result is the same as calling
+    /// the function with [`Value::call`], but dispatch goes through `Base.invokelatest` first.
+    /// Returns the result of this function call if no exception is thrown, the exception if one
+    /// is, or an error if no space is left on the stack.
+    ///
+    /// [`Value::call`]: struct.Value.html#method.call
+    pub fn call_latest<'frame, 'value, 'borrow, V, F>(
+        self,
+        frame: &mut F,
+        mut args: V,
+    ) -> JlrsResult<CallResult<'frame, 'borrow>>
+    where
+        V: AsMut<[Value<'value, 'borrow>]>,
+        F: Frame<'frame>,
+    {
+        unsafe {
+            let global = frame.global();
+            let args = args.as_mut();
+            let mut vals: SmallVec<[Value; MAX_SIZE]> = SmallVec::with_capacity(1 + args.len());
+            vals.push(Value::wrap(self.ptr()));
+
+            for arg in args.iter().copied() {
+                vals.push(arg);
+            }
+
+            Module::base(global)
+                .function("invokelatest")?
+                .call(frame, &mut vals)
+        }
+    }
+
+    /// Call the method of this function that matches the signature `sig`, a tuple type, with
+    /// `Core.invoke`, this takes one slot on the GC stack. This can be used to call a less
+    /// specific method than the one that would be selected by the types of the arguments, which
+    /// must be subtypes of `sig`. Returns the result of this function call if no exception is
+    /// thrown, the exception if one is, or an error if no space is left on the stack.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// # use jlrs::prelude::*;
+    /// # fn main() {
+    /// # let mut julia = unsafe { Julia::init(16).unwrap() };
+    /// julia.frame(3, |_global, frame| {
+    ///     let func = Value::eval_string(frame, "f(x::Integer) = 1; f(x::Int) = 2; f")?.unwrap();
+    ///     let sig = Value::eval_string(frame, "Tuple{Integer}")?.unwrap();
+    ///     let x = Value::new(frame, 1i64)?;
+    ///
+    ///     let res = func.invoke_with_signature(frame, sig, &mut [x])?.unwrap();
+    ///     assert_eq!(res.cast::<i64>()?, 1);
+    ///     Ok(())
+    /// }).unwrap();
+    /// # }
+    /// ```
+    pub fn invoke_with_signature<'frame, 'value, 'borrow, V, F>(
+        self,
+        frame: &mut F,
+        sig: Value,
+        mut args: V,
+    ) -> JlrsResult<CallResult<'frame, 'borrow>>
+    where
+        V: AsMut<[Value<'value, 'borrow>]>,
+        F: Frame<'frame>,
+    {
+        unsafe {
+            let global = frame.global();
+            let args = args.as_mut();
+            let mut vals: SmallVec<[Value; MAX_SIZE]> = SmallVec::with_capacity(2 + args.len());
+            vals.push(Value::wrap(self.ptr()));
+            vals.push(Value::wrap(sig.ptr()));
+
+            for arg in args.iter().copied() {
+                vals.push(arg);
+            }
+
+            Module::core(global)
+                .function("invoke")?
+                .call(frame, &mut vals)
+        }
+    }
+
+    /// Compile this value as a function with the signature `S` with `@cfunction` and return the
+    /// function pointer, see the [`cfunction`] module for more information. Returns an error if
+    /// no method matches the argument types of `S`, or if the return type Julia infers for this
+    /// method isn't a subtype of the return type of `S`. This takes one slot on the GC stack.
+    ///
+    /// [`cfunction`]: cfunction/index.html
+    pub fn as_cfunction<'frame, S, F>(self, frame: &mut F) -> JlrsResult<CFunction<'frame, S>>
+    where
+        S: CFunctionSignature,
+        F: Frame<'frame>,
+    {
+        unsafe {
+            let global = frame.global();
+            let output = frame.output()?;
+
+            let mut args: SmallVec<[Value; MAX_SIZE]> = SmallVec::new();
+            args.push(Value::wrap(self.ptr()));
+            args.push(Value::wrap(S::return_type().cast()));
+            for ty in S::argument_types() {
+                args.push(Value::wrap(ty.cast()));
+            }
+
+            let handle = Module::main(global)
+                .submodule("Jlrs")?
+                .function("cfunction")?
+                .with_output(output)
+                .call(frame, &mut args)
+                .into_jlrs_result(frame)?;
+
+            let ptr = handle.cast::<*mut c_void>()?;
+            Ok(CFunction::new(handle, ptr))
+        }
+    }
+
     /// Returns an anonymous function that wraps this value in a try-catch block. Calling this
     /// anonymous function with some arguments will call the value as a function with those
     /// arguments and return its result, or catch the exception, print the stackstrace, and
@@ -1458,7 +1741,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
             let func = Module::main(global)
                 .submodule("Jlrs")?
                 .function("tracingcall")?;
-            let res = jl_call1(func.ptr(), self.ptr());
+            let res = interrupt::call1(func.ptr(), self.ptr());
             try_protect(frame, res)
         }
     }
@@ -1468,6 +1751,12 @@ impl<'fr, 'data> Value<'fr, 'data> {
     /// arguments and return its result, or catch the exception and throw a new one with two
     /// fields, `exc` and `stacktrace`, containing the original exception and the stacktrace
     /// respectively. This takes one slot on the GC stack.
+    ///
+    /// The exception can be converted to a [`JuliaException`], which contains the stacktrace as
+    /// a [`Backtrace`].
+    ///
+    /// [`JuliaException`]: ../error/struct.JuliaException.html
+    /// [`Backtrace`]: ../error/struct.Backtrace.html
     pub fn attach_stacktrace<'frame, F>(
         self,
         frame: &mut F,
@@ -1480,7 +1769,7 @@ impl<'fr, 'data> Value<'fr, 'data> {
             let func = Module::main(global)
                 .submodule("Jlrs")?
                 .function("attachstacktrace")?;
-            let res = jl_call1(func.ptr(), self.ptr());
+            let res = interrupt::call1(func.ptr(), self.ptr());
             try_protect(frame, res)
         }
     }
@@ -1506,6 +1795,43 @@ impl Value<'_, '_> {
     pub unsafe fn finalize(self) {
         jl_finalize(self.ptr())
     }
+
+    /// Add a finalizer that calls the Rust closure `func` when this value is about to be freed by
+    /// the garbage collector. This can be used to release resources owned by Rust, like a file
+    /// or a handle to a pool, that are kept alive by this value. The closure is stored by jlrs
+    /// until it has been called. Like Julia's `finalizer`, this returns a `JlrsError::Exception`
+    /// if this value is immutable.
+    ///
+    /// If the closure panics, the panic is caught and ignored. A new dynamic frame is created
+    /// which needs two slots on the GC stack, one for the boxed id of the closure and one for the
+    /// result of the call that adds the finalizer. If an exception is thrown, converting it needs
+    /// three more slots, see [`JuliaException::new`].
+    ///
+    /// [`JuliaException::new`]: ../error/struct.JuliaException.html#method.new
+    pub fn add_rust_finalizer<'frame, F, G>(self, frame: &mut F, func: G) -> JlrsResult<()>
+    where
+        F: Frame<'frame>,
+        G: FnOnce() + Send + 'static,
+    {
+        let id = crate::register_rust_finalizer(Box::new(func));
+        let res = frame.dynamic_frame(|frame| {
+            let global = frame.global();
+            let id = Value::new(frame, id)?;
+            Module::main(global)
+                .submodule("Jlrs")?
+                .function("addrustfinalizer")?
+                .call2(frame, self, id)?
+                .into_jlrs_result(frame)?;
+
+            Ok(())
+        });
+
+        if res.is_err() {
+            crate::unregister_rust_finalizer(id);
+        }
+
+        res
+    }
 }
 
 /// Constant values.
@@ -1639,7 +1965,7 @@ impl<'func, 'funcdata, 'kw, 'kwdata> WithKeywords<'func, 'funcdata, 'kw, 'kwdata
             let args = &mut [self.kws, self.func];
             let n = args.len();
 
-            let res = jl_call(func, args.as_mut_ptr().cast(), n as _);
+            let res = interrupt::call(func, args.as_mut_ptr().cast(), n as _);
             try_protect(frame, res)
         }
     }
@@ -1652,7 +1978,7 @@ impl<'func, 'funcdata, 'kw, 'kwdata> WithKeywords<'func, 'funcdata, 'kw, 'kwdata
         let args = &mut [self.kws, self.func];
         let n = args.len();
 
-        let res = jl_call(func, args.as_mut_ptr().cast(), n as _);
+        let res = interrupt::call(func, args.as_mut_ptr().cast(), n as _);
         let exc = jl_exception_occurred();
 
         if exc.is_null() {
@@ -1678,7 +2004,7 @@ impl<'func, 'funcdata, 'kw, 'kwdata> WithKeywords<'func, 'funcdata, 'kw, 'kwdata
             let args = &mut [self.kws, self.func, arg];
             let n = args.len();
 
-            let res = jl_call(func, args.as_mut_ptr().cast(), n as _);
+            let res = interrupt::call(func, args.as_mut_ptr().cast(), n as _);
             try_protect(frame, res)
         }
     }
@@ -1695,7 +2021,7 @@ impl<'func, 'funcdata, 'kw, 'kwdata> WithKeywords<'func, 'funcdata, 'kw, 'kwdata
         let args = &mut [self.kws, self.func, arg];
         let n = args.len();
 
-        let res = jl_call(func, args.as_mut_ptr().cast(), n as _);
+        let res = interrupt::call(func, args.as_mut_ptr().cast(), n as _);
         let exc = jl_exception_occurred();
 
         if exc.is_null() {
@@ -1722,7 +2048,7 @@ impl<'func, 'funcdata, 'kw, 'kwdata> WithKeywords<'func, 'funcdata, 'kw, 'kwdata
             let args = &mut [self.kws, self.func, arg0, arg1];
             let n = args.len();
 
-            let res = jl_call(func, args.as_mut_ptr().cast(), n as _);
+            let res = interrupt::call(func, args.as_mut_ptr().cast(), n as _);
             try_protect(frame, res)
         }
     }
@@ -1740,7 +2066,7 @@ impl<'func, 'funcdata, 'kw, 'kwdata> WithKeywords<'func, 'funcdata, 'kw, 'kwdata
         let args = &mut [self.kws, self.func, arg0, arg1];
         let n = args.len();
 
-        let res = jl_call(func, args.as_mut_ptr().cast(), n as _);
+        let res = interrupt::call(func, args.as_mut_ptr().cast(), n as _);
         let exc = jl_exception_occurred();
 
         if exc.is_null() {
@@ -1768,7 +2094,7 @@ impl<'func, 'funcdata, 'kw, 'kwdata> WithKeywords<'func, 'funcdata, 'kw, 'kwdata
             let args = &mut [self.kws, self.func, arg0, arg1, arg2];
             let n = args.len();
 
-            let res = jl_call(func, args.as_mut_ptr().cast(), n as _);
+            let res = interrupt::call(func, args.as_mut_ptr().cast(), n as _);
             try_protect(frame, res)
         }
     }
@@ -1787,7 +2113,7 @@ impl<'func, 'funcdata, 'kw, 'kwdata> WithKeywords<'func, 'funcdata, 'kw, 'kwdata
         let args = &mut [self.kws, self.func, arg0, arg1, arg2];
         let n = args.len();
 
-        let res = jl_call(func, args.as_mut_ptr().cast(), n as _);
+        let res = interrupt::call(func, args.as_mut_ptr().cast(), n as _);
         let exc = jl_exception_occurred();
 
         if exc.is_null() {
@@ -1821,7 +2147,7 @@ impl<'func, 'funcdata, 'kw, 'kwdata> WithKeywords<'func, 'funcdata, 'kw, 'kwdata
             }
 
             let n = vals.len();
-            let res = jl_call(func, vals.as_mut_ptr().cast(), n as _);
+            let res = interrupt::call(func, vals.as_mut_ptr().cast(), n as _);
             try_protect(frame, res)
         }
     }
@@ -1848,7 +2174,7 @@ impl<'func, 'funcdata, 'kw, 'kwdata> WithKeywords<'func, 'funcdata, 'kw, 'kwdata
         }
 
         let n = vals.len();
-        let res = jl_call(func, vals.as_mut_ptr().cast(), n as _);
+        let res = interrupt::call(func, vals.as_mut_ptr().cast(), n as _);
         let exc = jl_exception_occurred();
 
         if exc.is_null() {
@@ -1857,6 +2183,126 @@ impl<'func, 'funcdata, 'kw, 'kwdata> WithKeywords<'func, 'funcdata, 'kw, 'kwdata
             Err(Value::wrap(exc))
         }
     }
+
+    /// Call this function with keywords with several arguments with `Base.invokelatest`, this
+    /// takes one slot on the GC stack. See [`Value::call_latest`] for more information.
+    ///
+    /// [`Value::call_latest`]: struct.Value.html#method.call_latest
+    pub fn call_latest<'frame, 'value, 'borrow, V, F>(
+        self,
+        frame: &mut F,
+        mut args: V,
+    ) -> JlrsResult<CallResult<'frame, 'borrow>>
+    where
+        V: AsMut<[Value<'value, 'borrow>]>,
+        F: Frame<'frame>,
+    {
+        unsafe {
+            let global = frame.global();
+            let args = args.as_mut();
+            let mut vals: SmallVec<[Value; MAX_SIZE]> = SmallVec::with_capacity(1 + args.len());
+            vals.push(Value::wrap(self.func.ptr()));
+
+            for arg in args.iter().copied() {
+                vals.push(arg);
+            }
+
+            Module::base(global)
+                .function("invokelatest")?
+                .with_keywords(self.kws)
+                .call(frame, &mut vals)
+        }
+    }
+
+    /// Call the method of this function with keywords that matches the signature `sig` with
+    /// `Core.invoke`, this takes one slot on the GC stack. See [`Value::invoke_with_signature`]
+    /// for more information.
+    ///
+    /// [`Value::invoke_with_signature`]: struct.Value.html#method.invoke_with_signature
+    pub fn invoke_with_signature<'frame, 'value, 'borrow, V, F>(
+        self,
+        frame: &mut F,
+        sig: Value,
+        mut args: V,
+    ) -> JlrsResult<CallResult<'frame, 'borrow>>
+    where
+        V: AsMut<[Value<'value, 'borrow>]>,
+        F: Frame<'frame>,
+    {
+        unsafe {
+            let global = frame.global();
+            let args = args.as_mut();
+            let mut vals: SmallVec<[Value; MAX_SIZE]> = SmallVec::with_capacity(2 + args.len());
+            vals.push(Value::wrap(self.func.ptr()));
+            vals.push(Value::wrap(sig.ptr()));
+
+            for arg in args.iter().copied() {
+                vals.push(arg);
+            }
+
+            Module::core(global)
+                .function("invoke")?
+                .with_keywords(self.kws)
+                .call(frame, &mut vals)
+        }
+    }
+
+    /// Call this function with keywords with the arguments in `args`, a tuple of Rust data and
+    /// `Value`s. The elements of this tuple are converted to Julia data with [`IntoArgument`] in
+    /// a new dynamic frame, which needs one slot on the GC stack for each element that isn't
+    /// already a `Value`. The result takes one slot in `frame`. Returns the result of this
+    /// function call if no exception is thrown, the exception if one is, or an error if an
+    /// argument can't be converted or no space is left on the stack.
+    ///
+    /// [`IntoArgument`]: ../traits/into_arguments/trait.IntoArgument.html
+    pub fn call_args<'frame, 'borrow, A, F>(
+        self,
+        frame: &mut F,
+        args: A,
+    ) -> JlrsResult<CallResult<'frame, 'borrow>>
+    where
+        'borrow: 'frame,
+        A: IntoArguments<'borrow>,
+        F: Frame<'frame>,
+    {
+        let output = frame.output()?;
+        frame.dynamic_frame(|frame| unsafe {
+            let args = args.into_arguments(frame)?;
+            let func = jl_get_kwsorter(self.func.datatype().expect("").ptr().cast());
+            let mut vals: SmallVec<[Value; MAX_SIZE]> = SmallVec::with_capacity(2 + args.len());
+            vals.push(self.kws);
+            vals.push(self.func);
+
+            for arg in args.iter().copied() {
+                vals.push(arg);
+            }
+
+            let n = vals.len();
+            let res = interrupt::call(func, vals.as_mut_ptr().cast(), n as _);
+            Ok(assign(frame, output, res))
+        })
+    }
+
+    /// Call this function with keywords with the arguments in `args` and cast the result to
+    /// `T`. This works like [`WithKeywords::call_args`], except that an exception is returned as
+    /// `JlrsError::Exception` and an error is returned if the result can't be cast to `T`.
+    ///
+    /// [`WithKeywords::call_args`]: struct.WithKeywords.html#method.call_args
+    pub fn call_args_as<'frame, 'borrow, T, A, F>(
+        self,
+        frame: &mut F,
+        args: A,
+    ) -> JlrsResult<<T as Cast<'frame, 'borrow>>::Output>
+    where
+        'borrow: 'frame,
+        T: Cast<'frame, 'borrow>,
+        A: IntoArguments<'borrow>,
+        F: Frame<'frame>,
+    {
+        self.call_args(frame, args)?
+            .into_jlrs_result(frame)?
+            .cast::<T>()
+    }
 }
 
 /// A wrapper that will let you call a `Value` as a function and store the result using an
@@ -1881,7 +2327,7 @@ impl<'output, 'frame, 'data> WithOutput<'output, Value<'frame, 'data>> {
         F: Frame<'fr>,
     {
         unsafe {
-            let res = jl_call0(self.value.ptr());
+            let res = interrupt::call0(self.value.ptr());
             assign(frame, self.output, res)
         }
     }
@@ -1899,7 +2345,7 @@ impl<'output, 'frame, 'data> WithOutput<'output, Value<'frame, 'data>> {
         F: Frame<'fr>,
     {
         unsafe {
-            let res = jl_call1(self.value.ptr().cast(), arg.ptr());
+            let res = interrupt::call1(self.value.ptr().cast(), arg.ptr());
             assign(frame, self.output, res)
         }
     }
@@ -1918,7 +2364,7 @@ impl<'output, 'frame, 'data> WithOutput<'output, Value<'frame, 'data>> {
         F: Frame<'fr>,
     {
         unsafe {
-            let res = jl_call2(self.value.ptr().cast(), arg0.ptr(), arg1.ptr());
+            let res = interrupt::call2(self.value.ptr().cast(), arg0.ptr(), arg1.ptr());
             assign(frame, self.output, res)
         }
     }
@@ -1938,7 +2384,7 @@ impl<'output, 'frame, 'data> WithOutput<'output, Value<'frame, 'data>> {
         F: Frame<'fr>,
     {
         unsafe {
-            let res = jl_call3(self.value.ptr().cast(), arg0.ptr(), arg1.ptr(), arg2.ptr());
+            let res = interrupt::call3(self.value.ptr().cast(), arg0.ptr(), arg1.ptr(), arg2.ptr());
             assign(frame, self.output, res)
         }
     }
@@ -1959,7 +2405,7 @@ impl<'output, 'frame, 'data> WithOutput<'output, Value<'frame, 'data>> {
         unsafe {
             let args = args.as_mut();
             let n = args.len();
-            let res = jl_call(self.value.ptr().cast(), args.as_mut_ptr().cast(), n as _);
+            let res = interrupt::call(self.value.ptr().cast(), args.as_mut_ptr().cast(), n as _);
             assign(frame, self.output, res)
         }
     }
@@ -1972,11 +2418,123 @@ impl<'output, 'frame, 'data> WithOutput<'output, Value<'frame, 'data>> {
         F: Frame<'fr>,
     {
         unsafe {
-            let res = jl_call(self.value.ptr().cast(), args.ptr(), args.len() as _);
+            let res = interrupt::call(self.value.ptr().cast(), args.ptr(), args.len() as _);
             assign(frame, self.output, res)
         }
     }
 
+    /// Call the value as a function that takes several arguments with `Base.invokelatest` and
+    /// use the `Output` to extend the result's lifetime. This takes no space on the GC stack. See
+    /// [`Value::call_latest`] for more information.
+    ///
+    /// [`Value::call_latest`]: struct.Value.html#method.call_latest
+    pub fn call_latest<'value, 'borrow, 'fr, V, F>(
+        self,
+        frame: &mut F,
+        mut args: V,
+    ) -> JlrsResult<CallResult<'output, 'borrow>>
+    where
+        'borrow: 'output,
+        V: AsMut<[Value<'value, 'borrow>]>,
+        F: Frame<'fr>,
+    {
+        unsafe {
+            let global = frame.global();
+            let args = args.as_mut();
+            let mut vals: SmallVec<[Value; MAX_SIZE]> = SmallVec::with_capacity(1 + args.len());
+            vals.push(Value::wrap(self.value.ptr()));
+
+            for arg in args.iter().copied() {
+                vals.push(arg);
+            }
+
+            Ok(Module::base(global)
+                .function("invokelatest")?
+                .with_output(self.output)
+                .call(frame, &mut vals))
+        }
+    }
+
+    /// Call the method of the value that matches the signature `sig` with `Core.invoke` and use
+    /// the `Output` to extend the result's lifetime. This takes no space on the GC stack. See
+    /// [`Value::invoke_with_signature`] for more information.
+    ///
+    /// [`Value::invoke_with_signature`]: struct.Value.html#method.invoke_with_signature
+    pub fn invoke_with_signature<'value, 'borrow, 'fr, V, F>(
+        self,
+        frame: &mut F,
+        sig: Value,
+        mut args: V,
+    ) -> JlrsResult<CallResult<'output, 'borrow>>
+    where
+        'borrow: 'output,
+        V: AsMut<[Value<'value, 'borrow>]>,
+        F: Frame<'fr>,
+    {
+        unsafe {
+            let global = frame.global();
+            let args = args.as_mut();
+            let mut vals: SmallVec<[Value; MAX_SIZE]> = SmallVec::with_capacity(2 + args.len());
+            vals.push(Value::wrap(self.value.ptr()));
+            vals.push(Value::wrap(sig.ptr()));
+
+            for arg in args.iter().copied() {
+                vals.push(arg);
+            }
+
+            Ok(Module::core(global)
+                .function("invoke")?
+                .with_output(self.output)
+                .call(frame, &mut vals))
+        }
+    }
+
+    /// Call the value as a function with the arguments in `args`, a tuple of Rust data and
+    /// `Value`s, and use the `Output` to extend the result's lifetime. The elements of this
+    /// tuple are converted to Julia data with [`IntoArgument`] in a new dynamic frame, which
+    /// needs one slot on the GC stack for each element that isn't already a `Value`. Returns the
+    /// result of this function call if no exception is thrown, the exception if one is, or an
+    /// error if an argument can't be converted.
+    ///
+    /// [`IntoArgument`]: ../traits/into_arguments/trait.IntoArgument.html
+    pub fn call_args<'borrow, 'fr, A, F>(
+        self,
+        frame: &mut F,
+        args: A,
+    ) -> JlrsResult<CallResult<'output, 'borrow>>
+    where
+        'borrow: 'output,
+        A: IntoArguments<'borrow>,
+        F: Frame<'fr>,
+    {
+        frame.dynamic_frame(|frame| {
+            let mut args = args.into_arguments(frame)?;
+            Ok(self.call(frame, &mut args))
+        })
+    }
+
+    /// Call the value as a function with the arguments in `args`, use the `Output` to extend
+    /// the result's lifetime and cast it to `T`. This works like [`WithOutput::call_args`],
+    /// except that an exception is returned as `JlrsError::Exception` and an error is returned if
+    /// the result can't be cast to `T`.
+    ///
+    /// [`WithOutput::call_args`]: struct.WithOutput.html#method.call_args
+    pub fn call_args_as<'borrow, 'fr, T, A, F>(
+        self,
+        frame: &mut F,
+        args: A,
+    ) -> JlrsResult<<T as Cast<'output, 'borrow>>::Output>
+    where
+        'borrow: 'output,
+        T: Cast<'output, 'borrow>,
+        A: IntoArguments<'borrow>,
+        F: Frame<'fr>,
+    {
+        self.call_args(frame, args)?
+            .into_jlrs_result(frame)?
+            .cast::<T>()
+    }
+
     /// Returns an anonymous function that wraps the value in a try-catch block. Calling this
     /// anonymous function with some arguments will call the value as a function with those
     /// arguments and return its result, or catch the exception, print the stackstrace, and
@@ -1990,7 +2548,7 @@ impl<'output, 'frame, 'data> WithOutput<'output, Value<'frame, 'data>> {
             let func = Module::main(global)
                 .submodule("Jlrs")?
                 .function("tracingcall")?;
-            let res = jl_call1(func.ptr(), self.value.ptr());
+            let res = interrupt::call1(func.ptr(), self.value.ptr());
             Ok(assign(frame, self.output, res))
         }
     }
@@ -2009,7 +2567,7 @@ impl<'output, 'frame, 'data> WithOutput<'output, Value<'frame, 'data>> {
             let func = Module::main(global)
                 .submodule("Jlrs")?
                 .function("attachstacktrace")?;
-            let res = jl_call1(func.ptr(), self.value.ptr());
+            let res = interrupt::call1(func.ptr(), self.value.ptr());
             Ok(assign(frame, self.output, res))
         }
     }
@@ -2092,6 +2650,22 @@ where
     }
 }
 
+// The data of a borrowed array is counted in Julia's malloc accounting until the array is freed.
+// The data of moved arrays is counted by Julia because it owns that data.
+unsafe fn account_borrowed_array(global: Global, array: Value) {
+    let unaccount = Module::main(global)
+        .submodule("Jlrs")
+        .and_then(|jlrs| jlrs.function("unaccount"));
+
+    // The Jlrs module is unavailable if Julia has not been initialized by jlrs.
+    if let Ok(unaccount) = unaccount {
+        let array_ref = &*array.ptr().cast::<jl_array_t>();
+        let n_bytes = array_ref.length * array_ref.elsize as usize;
+        account_bytes(n_bytes as isize);
+        array.add_finalizer(unaccount);
+    }
+}
+
 unsafe fn move_array<'frame, T, D, F>(
     frame: &mut F,
     data: Vec<T>,
@@ -2138,6 +2712,37 @@ where
     }
 }
 
+// Call `func` with the values in `prefix` followed by the arguments in `args`, which are
+// converted in a new dynamic frame. The result is rooted in `frame`.
+unsafe fn call_prefixed<'frame, 'borrow, A, F>(
+    frame: &mut F,
+    func: Value,
+    prefix: &[*mut jl_value_t],
+    args: A,
+) -> JlrsResult<CallResult<'frame, 'borrow>>
+where
+    'borrow: 'frame,
+    A: IntoArguments<'borrow>,
+    F: Frame<'frame>,
+{
+    let output = frame.output()?;
+    frame.dynamic_frame(|frame| {
+        let args = args.into_arguments(frame)?;
+        let mut vals: SmallVec<[Value; MAX_SIZE]> =
+            SmallVec::with_capacity(prefix.len() + args.len());
+
+        for value in prefix.iter().copied() {
+            vals.push(Value::wrap(value));
+        }
+
+        for arg in args.iter().copied() {
+            vals.push(arg);
+        }
+
+        Ok(func.with_output(output).call(frame, &mut vals))
+    })
+}
+
 unsafe fn try_protect<'frame, F>(
     frame: &mut F,
     res: *mut jl_value_t,
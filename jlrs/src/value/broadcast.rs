@@ -0,0 +1,109 @@
+//! Broadcast functions over arrays and scalars.
+//!
+//! In Julia, a function call can be broadcast over its arguments with a dot call like
+//! `f.(x, y)`. This is lowered to `Base.Broadcast.materialize(Base.Broadcast.broadcasted(f, x,
+//! y))`: `broadcasted` creates a lazy representation of the operation and `materialize`
+//! evaluates it. Because the arguments of `broadcasted` can themselves be lazy operations,
+//! nested dot calls like `f.(g.(x))` are fused into a single loop without temporary arrays.
+//!
+//! [`Value::broadcast`] and [`Value::broadcast_into`] broadcast a function over some arguments
+//! immediately, like `broadcast` and `broadcast!` in Julia. To fuse several operations,
+//! [`Value::broadcasted`] returns a lazy [`Broadcasted`] that can be used as an argument of the
+//! next operation, the final one can be evaluated with [`Broadcasted::materialize`] or
+//! [`Broadcasted::materialize_into`]. The arguments of all these methods are tuples of
+//! `TypedArray`s, `Array`s, `Value`s, scalars and `Broadcasted`s.
+//!
+//! Example:
+//!
+//! ```no_run
+//! # use jlrs::prelude::*;
+//! # fn main() {
+//! # let mut julia = unsafe { Julia::init(16).unwrap() };
+//! julia.frame(4, |global, frame| {
+//!     let x = Value::move_array(frame, vec![1.0f64, 2.0, 3.0], 3)?;
+//!     let add = Module::base(global).function("+")?;
+//!     let mul = Module::base(global).function("*")?;
+//!
+//!     // (x .* 2.0) .+ 1.0, evaluated in a single loop
+//!     let doubled = mul.broadcasted(frame, (x, 2.0f64))?.unwrap();
+//!     let res = add.broadcasted(frame, (doubled, 1.0f64))?
+//!         .unwrap()
+//!         .materialize(frame)?
+//!         .unwrap();
+//!
+//!     let data = res.cast::<TypedArray<f64>>()?.copy_inline_data()?;
+//!     assert_eq!(data.as_slice(), &[3.0, 5.0, 7.0]);
+//!     Ok(())
+//! }).unwrap();
+//! # }
+//! ```
+//!
+//! [`Value::broadcast`]: ../struct.Value.html#method.broadcast
+//! [`Value::broadcast_into`]: ../struct.Value.html#method.broadcast_into
+//! [`Value::broadcasted`]: ../struct.Value.html#method.broadcasted
+//! [`Broadcasted`]: struct.Broadcasted.html
+//! [`Broadcasted::materialize`]: struct.Broadcasted.html#method.materialize
+//! [`Broadcasted::materialize_into`]: struct.Broadcasted.html#method.materialize_into
+
+use super::module::Module;
+use super::{CallResult, Value};
+use crate::error::JlrsResult;
+use crate::traits::Frame;
+use jl_sys::jl_value_t;
+use std::marker::PhantomData;
+
+/// A lazy broadcast operation created with [`Value::broadcasted`]. See the [`broadcast`] module
+/// for more information.
+///
+/// [`Value::broadcasted`]: ../struct.Value.html#method.broadcasted
+/// [`broadcast`]: index.html
+#[derive(Copy, Clone)]
+pub struct Broadcasted<'frame, 'data>(
+    *mut jl_value_t,
+    PhantomData<&'frame ()>,
+    PhantomData<&'data ()>,
+);
+
+impl<'frame, 'data> Broadcasted<'frame, 'data> {
+    pub(crate) unsafe fn wrap(value: Value<'frame, 'data>) -> Self {
+        Broadcasted(value.ptr(), PhantomData, PhantomData)
+    }
+
+    /// Evaluate this operation and return the result in a new array, or a scalar if all
+    /// arguments are scalars. This takes one slot on the GC stack. Returns the result if no
+    /// exception is thrown, the exception if one is, or an error if no space is left on the
+    /// stack.
+    pub fn materialize<'fr, F>(self, frame: &mut F) -> JlrsResult<CallResult<'fr, 'data>>
+    where
+        F: Frame<'fr>,
+    {
+        let global = frame.global();
+        Module::base(global)
+            .submodule("Broadcast")?
+            .function("materialize")?
+            .call1(frame, self.as_value())
+    }
+
+    /// Evaluate this operation and store the result in `dest`, which must be an array with a
+    /// compatible shape. This takes one slot on the GC stack. Returns `dest` if no exception is
+    /// thrown, the exception if one is, or an error if no space is left on the stack.
+    pub fn materialize_into<'fr, F>(
+        self,
+        frame: &mut F,
+        dest: Value<'_, 'data>,
+    ) -> JlrsResult<CallResult<'fr, 'data>>
+    where
+        F: Frame<'fr>,
+    {
+        let global = frame.global();
+        Module::base(global)
+            .submodule("Broadcast")?
+            .function("materialize!")?
+            .call2(frame, dest, self.as_value())
+    }
+
+    /// Convert `self` to a `Value`.
+    pub fn as_value(self) -> Value<'frame, 'data> {
+        unsafe { Value::wrap(self.0) }
+    }
+}
@@ -0,0 +1,136 @@
+//! Compile Julia functions to native function pointers.
+//!
+//! Calling a Julia function from Rust requires boxing every argument, which can be too slow for
+//! small functions that are called very often, like the objective function of an optimizer. With
+//! [`Value::as_cfunction`] a function can be compiled by Julia with `@cfunction` for a fixed
+//! signature, which returns a pointer to native code that can be called with unboxed arguments.
+//! The signature is given as an `extern "C" fn` type, all argument types and the return type
+//! must implement [`JuliaType`].
+//!
+//! When the function pointer is created, the return type that Julia infers for this signature
+//! is checked against the return type of the signature. The pointer is kept in a [`CFunction`],
+//! which is rooted in a frame. The pointer itself is never exposed, it can only be called with
+//! [`CFunction::call`] while the `CFunction` is borrowed so it can't outlive the frame. The
+//! arguments are passed as a tuple.
+//!
+//! Example:
+//!
+//! ```no_run
+//! # use jlrs::prelude::*;
+//! # fn main() {
+//! # let mut julia = unsafe { Julia::init(16).unwrap() };
+//! julia.frame(2, |_global, frame| {
+//!     let func = Value::eval_string(frame, "square(x::Float64) = x * x; square")?.unwrap();
+//!     let square = func.as_cfunction::<extern "C" fn(f64) -> f64, _>(frame)?;
+//!
+//!     assert_eq!(unsafe { square.call((3.0,)) }, 9.0);
+//!     Ok(())
+//! }).unwrap();
+//! # }
+//! ```
+//!
+//! [`Value::as_cfunction`]: ../struct.Value.html#method.as_cfunction
+//! [`CFunction`]: struct.CFunction.html
+//! [`CFunction::call`]: struct.CFunction.html#method.call
+//! [`JuliaType`]: ../../traits/trait.JuliaType.html
+
+use super::{Value, MAX_SIZE};
+use crate::traits::JuliaType;
+use jl_sys::jl_datatype_t;
+use smallvec::SmallVec;
+use std::ffi::c_void;
+use std::marker::PhantomData;
+
+/// A native function pointer to a Julia function that has been compiled with `@cfunction`. See
+/// the [`cfunction`] module for more information.
+///
+/// [`cfunction`]: index.html
+pub struct CFunction<'frame, F> {
+    func: F,
+    _handle: Value<'frame, 'static>,
+    _frame: PhantomData<&'frame ()>,
+}
+
+impl<'frame, F: CFunctionSignature> CFunction<'frame, F> {
+    pub(crate) unsafe fn new(handle: Value<'frame, 'static>, ptr: *mut c_void) -> Self {
+        CFunction {
+            func: F::from_ptr(ptr),
+            _handle: handle,
+            _frame: PhantomData,
+        }
+    }
+
+    /// Call the function with `args`, a tuple that contains all arguments.
+    ///
+    /// Safety: the function must not throw an exception when it's called. Because it's called
+    /// directly, there's no way to catch it before it unwinds through Rust code.
+    pub unsafe fn call(&self, args: F::Args) -> F::Output {
+        self.func.call_ptr(args)
+    }
+}
+
+/// Trait implemented by `extern "C" fn` types that can be used with [`Value::as_cfunction`]. It's
+/// implemented for functions with up to eight arguments, all argument types and the return type
+/// must implement [`JuliaType`].
+///
+/// [`Value::as_cfunction`]: ../struct.Value.html#method.as_cfunction
+/// [`JuliaType`]: ../../traits/trait.JuliaType.html
+pub unsafe trait CFunctionSignature: Copy {
+    /// The arguments of the function as a tuple.
+    type Args;
+    /// The return type of the function.
+    type Output;
+
+    #[doc(hidden)]
+    unsafe fn return_type() -> *mut jl_datatype_t;
+    #[doc(hidden)]
+    unsafe fn argument_types() -> SmallVec<[*mut jl_datatype_t; MAX_SIZE]>;
+    #[doc(hidden)]
+    unsafe fn from_ptr(ptr: *mut c_void) -> Self;
+    #[doc(hidden)]
+    unsafe fn call_ptr(self, args: Self::Args) -> Self::Output;
+}
+
+macro_rules! impl_cfunction_signature {
+    ($($name:ident),*) => {
+        unsafe impl<R, $($name),*> CFunctionSignature for extern "C" fn($($name),*) -> R
+        where
+            R: JuliaType,
+            $($name: JuliaType),*
+        {
+            type Args = ($($name,)*);
+            type Output = R;
+
+            unsafe fn return_type() -> *mut jl_datatype_t {
+                R::julia_type()
+            }
+
+            #[allow(unused_mut)]
+            unsafe fn argument_types() -> SmallVec<[*mut jl_datatype_t; MAX_SIZE]> {
+                let mut types = SmallVec::new();
+                $(types.push($name::julia_type());)*
+                types
+            }
+
+            unsafe fn from_ptr(ptr: *mut c_void) -> Self {
+                std::mem::transmute::<*mut c_void, Self>(ptr)
+            }
+
+            #[allow(non_snake_case)]
+            unsafe fn call_ptr(self, args: Self::Args) -> R {
+                let ($($name,)*) = args;
+                self($($name),*)
+            }
+        }
+    };
+}
+
+impl_cfunction_signature!();
+impl_cfunction_signature!(A1);
+impl_cfunction_signature!(A1, A2);
+impl_cfunction_signature!(A1, A2, A3);
+impl_cfunction_signature!(A1, A2, A3, A4);
+impl_cfunction_signature!(A1, A2, A3, A4, A5);
+impl_cfunction_signature!(A1, A2, A3, A4, A5, A6);
+impl_cfunction_signature!(A1, A2, A3, A4, A5, A6, A7);
+impl_cfunction_signature!(A1, A2, A3, A4, A5, A6, A7, A8);
@@ -0,0 +1,171 @@
+//! Call a Julia function with a fixed signature without looking up the method every time.
+//!
+//! Calling a `Value` as a function dispatches on the types of the arguments every time it's
+//! called. If the same function is called many times with arguments of the same types, a
+//! [`JuliaFunction`] can be used instead. It's created with the Rust types of the arguments and
+//! of the result, looks up the matching method once, and caches the `MethodInstance` that
+//! specializes that method for the argument types. Every call passes this `MethodInstance`
+//! directly to Julia's `jl_invoke`, so no dispatch happens and no intermediate Julia function is
+//! called. The arguments are still converted to Julia data, unless they're small integers or
+//! other values that Julia caches this allocates a box for every argument.
+//!
+//! Defining a new method can change which method is called, so the cache is only valid for the
+//! world age in which the lookup happened. When the world age has changed, the method is looked
+//! up again the next time the function is called.
+//!
+//! Example:
+//!
+//! ```no_run
+//! # use jlrs::prelude::*;
+//! # use jlrs::value::julia_function::JuliaFunction;
+//! # fn main() {
+//! # let mut julia = unsafe { Julia::init(16).unwrap() };
+//! julia.frame(2, |global, frame| {
+//!     let func = Module::base(global).function("+")?;
+//!     let add = JuliaFunction::<(f64, f64), f64>::new(frame, func)?;
+//!
+//!     let mut sum = 0.0;
+//!     for i in 0..100 {
+//!         sum = frame.frame(1, |frame| add.call(frame, (sum, i as f64)))?;
+//!     }
+//!
+//!     assert_eq!(sum, 4950.0);
+//!     Ok(())
+//! }).unwrap();
+//! # }
+//! ```
+//!
+//! [`JuliaFunction`]: struct.JuliaFunction.html
+
+use super::method_instance::MethodInstance;
+use super::module::Module;
+use super::{assign, Value, MAX_SIZE};
+use crate::error::JlrsResult;
+use crate::interrupt;
+use crate::traits::{Cast, Frame, IntoJlrsResult, TypedArguments};
+use jl_sys::{jl_get_world_counter, jl_value_t};
+use smallvec::SmallVec;
+use std::cell::Cell;
+use std::marker::PhantomData;
+
+/// A Julia function with arguments of type `Args` and a result of type `Ret`. See the
+/// [`julia_function`] module for more information.
+///
+/// [`julia_function`]: index.html
+pub struct JuliaFunction<'frame, Args, Ret> {
+    func: Value<'frame, 'static>,
+    cache: Cell<(MethodInstance<'frame>, usize)>,
+    _marker: PhantomData<fn(Args) -> Ret>,
+}
+
+impl<'frame, Args, Ret> JuliaFunction<'frame, Args, Ret>
+where
+    Args: TypedArguments,
+{
+    /// Create a new `JuliaFunction` for `func` and look up the method that is called with
+    /// arguments of type `Args`. Returns an error if no such method exists. A new dynamic frame
+    /// is created to look up the method, which needs two slots on the GC stack.
+    pub fn new<F>(frame: &mut F, func: Value<'frame, 'static>) -> JlrsResult<Self>
+    where
+        F: Frame<'frame>,
+    {
+        let cache = lookup::<Args, _>(frame, func)?;
+
+        Ok(JuliaFunction {
+            func,
+            cache: Cell::new(cache),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Call the function and cast the result to `Ret`. The arguments are converted to Julia
+    /// data in a new dynamic frame, which needs one slot on the GC stack for each argument. The
+    /// result takes one slot in `frame`. If the world age has changed since the method was
+    /// looked up, it's looked up again first.
+    ///
+    /// Returns an error if an exception is thrown or the result can't be cast to `Ret`.
+    pub fn call<'fr, F>(
+        &self,
+        frame: &mut F,
+        args: Args,
+    ) -> JlrsResult<<Ret as Cast<'fr, 'static>>::Output>
+    where
+        F: Frame<'fr>,
+        Ret: Cast<'fr, 'static>,
+    {
+        let output = frame.output()?;
+        let res = frame.dynamic_frame(|frame| unsafe {
+            let method_instance = self.current_method_instance(frame)?;
+            let args = args.into_arguments(frame)?;
+            let mut args: SmallVec<[*mut jl_value_t; MAX_SIZE]> =
+                args.iter().map(|arg| arg.ptr()).collect();
+
+            let res = interrupt::invoke(
+                self.func.ptr(),
+                args.as_mut_ptr(),
+                args.len() as u32,
+                method_instance.ptr(),
+            );
+
+            assign(frame, output, res).into_jlrs_result(frame)
+        })?;
+
+        res.cast::<Ret>()
+    }
+
+    /// Returns the `MethodInstance` that is currently cached. This might be outdated if a new
+    /// method has been defined since it was looked up.
+    pub fn method_instance(&self) -> MethodInstance<'frame> {
+        self.cache.get().0
+    }
+
+    /// Returns the function that is called.
+    pub fn function(&self) -> Value<'frame, 'static> {
+        self.func
+    }
+
+    fn current_method_instance<'fr, F>(&self, frame: &mut F) -> JlrsResult<MethodInstance<'frame>>
+    where
+        F: Frame<'fr>,
+    {
+        let (method_instance, world) = self.cache.get();
+        if world == unsafe { jl_get_world_counter() } {
+            return Ok(method_instance);
+        }
+
+        let cache = lookup::<Args, _>(frame, self.func)?;
+        self.cache.set(cache);
+        Ok(cache.0)
+    }
+}
+
+// Look up the method instance of `func` for the argument types `Args` in the current world.
+// Method instances are referenced by their method, so they don't need to be rooted.
+fn lookup<'frame, 'fr, Args, F>(
+    frame: &mut F,
+    func: Value<'frame, 'static>,
+) -> JlrsResult<(MethodInstance<'frame>, usize)>
+where
+    Args: TypedArguments,
+    F: Frame<'fr>,
+{
+    frame.dynamic_frame(|frame| unsafe {
+        let world = jl_get_world_counter();
+        let global = frame.global();
+
+        let mut args: SmallVec<[Value; MAX_SIZE]> = SmallVec::new();
+        args.push(func);
+        for ty in Args::argument_types() {
+            args.push(Value::wrap(ty.cast()));
+        }
+
+        let method_instance = Module::main(global)
+            .submodule("Jlrs")?
+            .function("methodinstance")?
+            .call(frame, &mut args)?
+            .into_jlrs_result(frame)?
+            .cast::<MethodInstance>()?;
+
+        Ok((MethodInstance::wrap(method_instance.ptr()), world))
+    })
+}
@@ -1,18 +1,33 @@
 //! Access Julia modules and the globals and functions defined in them.
 
 use crate::error::{JlrsError, JlrsResult};
+use crate::frame::DynamicFrame;
 use crate::global::Global;
-use crate::traits::{private::Internal, Cast, Frame, TemporarySymbol};
+use crate::mode::Sync;
+use crate::traits::{private::Internal, Cast, Frame, IntoJlrsResult, TemporarySymbol};
 use crate::value::symbol::Symbol;
 use crate::value::{CallResult, Value};
+use crate::CCall;
 use crate::{impl_julia_type, impl_julia_typecheck, impl_valid_layout};
 use jl_sys::{
-    jl_base_module, jl_core_module, jl_get_global, jl_main_module, jl_module_t, jl_module_type,
-    jl_set_const, jl_set_global, jl_typeis,
+    jl_array_t, jl_base_module, jl_core_module, jl_get_global, jl_main_module, jl_module_t,
+    jl_module_type, jl_set_const, jl_set_global, jl_typeis, jl_value_t,
 };
+use std::ffi::c_void;
 use std::fmt::{Debug, Formatter, Result as FmtResult};
 use std::marker::PhantomData;
 
+// A Rust function that has been exposed to Julia with `Module::define_function`.
+type RustFunction = Box<
+    dyn for<'fr> Fn(
+            &mut DynamicFrame<'fr, Sync>,
+            &[Value<'fr, 'static>],
+        ) -> JlrsResult<Value<'fr, 'static>>
+        + Send
+        + std::marker::Sync
+        + 'static,
+>;
+
 /// Functionality in Julia can be accessed through its module system. You can get a handle to the
 /// three standard modules, `Main`, `Base`, and `Core` and access their submodules through them.
 /// If you include your own Julia code with [`Julia::include`], its contents are made available
@@ -186,6 +201,83 @@ impl<'base> Module<'base> {
         self.into()
     }
 
+    /// Define a new function named `name` in this module that calls `func`. The function takes
+    /// any number of positional arguments, which are passed to `func` as a slice together with a
+    /// new dynamic frame that can be used to root the result. If `func` returns an error, or
+    /// panics, a Julia exception is thrown.
+    ///
+    /// The function is returned and rooted in `frame`, which requires one free slot on the GC
+    /// stack. A new dynamic frame is created which needs two slots on the GC stack. The closure
+    /// is never freed, it can be called for as long as the Julia function exists.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// # use jlrs::prelude::*;
+    /// # fn main() {
+    /// # let mut julia = unsafe { Julia::init(16).unwrap() };
+    /// julia.frame(2, |global, frame| {
+    ///     Module::main(global).define_function(frame, "rustadd", |frame, args| {
+    ///         let mut sum = 0u64;
+    ///         for arg in args {
+    ///             sum += arg.cast::<u64>()?;
+    ///         }
+    ///
+    ///         Value::new(frame, sum)
+    ///     })?;
+    ///
+    ///     let res = Value::eval_string(frame, "rustadd(UInt64(1), UInt64(2))")?.unwrap();
+    ///     assert_eq!(res.cast::<u64>()?, 3);
+    ///     Ok(())
+    /// }).unwrap();
+    /// # }
+    /// ```
+    pub fn define_function<'frame, F, N, G>(
+        self,
+        frame: &mut F,
+        name: N,
+        func: G,
+    ) -> JlrsResult<Value<'frame, 'static>>
+    where
+        F: Frame<'frame>,
+        N: TemporarySymbol,
+        G: for<'fr> Fn(
+                &mut DynamicFrame<'fr, Sync>,
+                &[Value<'fr, 'static>],
+            ) -> JlrsResult<Value<'fr, 'static>>
+            + Send
+            + std::marker::Sync
+            + 'static,
+    {
+        let output = frame.output()?;
+        let boxed: RustFunction = Box::new(func);
+        // The trait object is boxed again so it can be passed to Julia as a thin pointer.
+        let ptr = Box::into_raw(Box::new(boxed));
+
+        let res = frame.dynamic_frame(|frame| unsafe {
+            let global = frame.global();
+            let mut args = [
+                self.as_value(),
+                name.temporary_symbol(Internal).as_value(),
+                Value::new(frame, ptr.cast::<c_void>())?,
+            ];
+
+            Module::main(global)
+                .submodule("Jlrs")?
+                .function("definefunction")?
+                .with_output(output)
+                .call(frame, &mut args)
+                .into_jlrs_result(frame)
+        });
+
+        if res.is_err() {
+            // The function hasn't been defined, so the closure can't be called.
+            unsafe { std::mem::drop(Box::from_raw(ptr)) };
+        }
+
+        res
+    }
+
     /// Load a module by calling `Base.require` and return this module if it has been loaded
     /// successfully. This method can be used to load parts of the standard library like
     /// `LinearAlgebra`. This requires one slot on the GC stack. Note that the loaded module is
@@ -245,6 +337,32 @@ impl<'base> Module<'base> {
     }
 }
 
+// Called by the functions defined with `Module::define_function`. Errors and panics are converted
+// to Julia exceptions.
+pub(crate) unsafe extern "C" fn call_rust_function(
+    func: *mut c_void,
+    args: *mut jl_array_t,
+) -> *mut jl_value_t {
+    let func = &*func.cast::<RustFunction>();
+    let data = (&*args).data.cast::<Value>();
+    let len = (&*args).length;
+
+    let mut ccall = CCall::new(32);
+    let res = ccall.dynamic_frame(|_global, frame| {
+        let args = std::slice::from_raw_parts(data, len);
+        Ok(func(frame, args)?.ptr())
+    });
+
+    match res {
+        Ok(value) => value,
+        Err(e) => {
+            let msg = e.to_string();
+            std::mem::drop(e);
+            ccall.throw_error(msg)
+        }
+    }
+}
+
 impl<'base> Into<Value<'base, 'static>> for Module<'base> {
     fn into(self) -> Value<'base, 'static> {
         unsafe { Value::wrap(self.ptr().cast()) }
@@ -0,0 +1,190 @@
+//! Wrap Rust data as opaque Julia values.
+//!
+//! Data that doesn't have a layout that Julia understands, like a database connection or a
+//! `Box<dyn Trait>`, can't be moved to Julia as a `JuliaStruct`. Instead, it can be wrapped in
+//! an opaque value with [`OpaqueRust::new`]. The data is boxed and a pointer to it is stored in a
+//! new instance of `Jlrs.OpaqueRust{Name,Id}`, a mutable type that is unique for every Rust type.
+//! Julia code can pass this value around and store it, but can't access the data. When the value
+//! is freed by the garbage collector, its finalizer drops the data.
+//!
+//! Rust code can get the data back by casting the value to an [`OpaqueRust`], this checks that
+//! the value wraps data of the expected type. The data can then be borrowed with
+//! [`OpaqueRust::get`] and [`OpaqueRust::get_mut`].
+//!
+//! Example:
+//!
+//! ```no_run
+//! # use jlrs::prelude::*;
+//! # use jlrs::value::opaque::OpaqueRust;
+//! # fn main() {
+//! # let mut julia = unsafe { Julia::init(16).unwrap() };
+//! julia.frame(1, |_global, frame| {
+//!     let v = OpaqueRust::new(frame, vec![1, 2, 3])?.as_value();
+//!
+//!     let opaque = v.cast::<OpaqueRust<Vec<i32>>>()?;
+//!     opaque.get_mut(frame)?.push(4);
+//!     assert_eq!(opaque.get(frame)?.len(), 4);
+//!     Ok(())
+//! }).unwrap();
+//! # }
+//! ```
+//!
+//! [`OpaqueRust`]: struct.OpaqueRust.html
+//! [`OpaqueRust::new`]: struct.OpaqueRust.html#method.new
+//! [`OpaqueRust::get`]: struct.OpaqueRust.html#method.get
+//! [`OpaqueRust::get_mut`]: struct.OpaqueRust.html#method.get_mut
+
+use super::module::Module;
+use super::Value;
+use crate::error::{JlrsError, JlrsResult};
+use crate::traits::{Cast, Frame, IntoJlrsResult, JuliaTypecheck};
+use crate::value::datatype::DataType;
+use jl_sys::{jl_typeof, jl_value_t};
+use std::any::{type_name, TypeId};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::ffi::c_void;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Mutex;
+
+// The Julia type that has been used for each Rust type.
+static OPAQUE_TYPES: Mutex<BTreeMap<TypeId, usize>> = Mutex::new(BTreeMap::new());
+
+/// A Julia value that wraps data of type `T` which is owned by Rust. See the [`opaque`] module
+/// for more information.
+///
+/// [`opaque`]: index.html
+pub struct OpaqueRust<'frame, T>(*mut jl_value_t, PhantomData<&'frame ()>, PhantomData<T>);
+
+impl<'frame, T> Clone for OpaqueRust<'frame, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'frame, T> Copy for OpaqueRust<'frame, T> {}
+
+impl<'frame, T: Send + 'static> OpaqueRust<'frame, T> {
+    /// Move `data` to Julia as an opaque value. The data is dropped when the value is freed by
+    /// the garbage collector. One free slot on the GC stack is required for the value, a new
+    /// dynamic frame is created which needs seven slots on the GC stack.
+    ///
+    /// The data is dropped by a finalizer, so it must be `Send`.
+    pub fn new<F>(frame: &mut F, data: T) -> JlrsResult<Self>
+    where
+        F: Frame<'frame>,
+    {
+        let output = frame.output()?;
+        let ptr = Box::into_raw(Box::new(data));
+
+        let res = frame.dynamic_frame(|frame| {
+            let global = frame.global();
+            let mut args = [
+                Value::new(frame, type_name::<T>())?,
+                Value::new(frame, type_hash::<T>())?,
+                Value::new(frame, ptr.cast::<c_void>())?,
+                Value::new(frame, drop_opaque::<T> as *mut c_void)?,
+            ];
+
+            Module::main(global)
+                .submodule("Jlrs")?
+                .function("newopaque")?
+                .with_output(output)
+                .call(frame, &mut args)
+                .into_jlrs_result(frame)
+        });
+
+        match res {
+            Ok(value) => unsafe {
+                let ty = jl_typeof(value.ptr()) as usize;
+                OPAQUE_TYPES
+                    .lock()
+                    .expect("Opaque types are poisoned")
+                    .insert(TypeId::of::<T>(), ty);
+
+                Ok(Self::cast_unchecked(value))
+            },
+            Err(e) => {
+                // The finalizer hasn't been added, so the data must be dropped here.
+                unsafe { std::mem::drop(Box::from_raw(ptr)) };
+                Err(e)
+            }
+        }
+    }
+
+    /// Borrow the wrapped data. Returns an error if the data has already been dropped, which can
+    /// only happen if the value has been finalized explicitly.
+    pub fn get<'borrow, 'fr, F>(self, _frame: &'borrow F) -> JlrsResult<&'borrow T>
+    where
+        F: Frame<'fr>,
+    {
+        unsafe { Ok(&*self.data()?) }
+    }
+
+    /// Mutably borrow the wrapped data. Returns an error if the data has already been dropped,
+    /// which can only happen if the value has been finalized explicitly.
+    pub fn get_mut<'borrow, 'fr, F>(self, _frame: &'borrow mut F) -> JlrsResult<&'borrow mut T>
+    where
+        F: Frame<'fr>,
+    {
+        unsafe { Ok(&mut *self.data()?) }
+    }
+
+    /// Convert `self` to a `Value`.
+    pub fn as_value(self) -> Value<'frame, 'static> {
+        unsafe { Value::wrap(self.0) }
+    }
+
+    unsafe fn data(self) -> JlrsResult<*mut T> {
+        // The first field of the Julia type is the pointer to the data.
+        let ptr = *self.0.cast::<*mut T>();
+        if ptr.is_null() {
+            Err(JlrsError::OpaqueDropped(type_name::<T>().into()))?;
+        }
+
+        Ok(ptr)
+    }
+}
+
+unsafe impl<'frame, T: Send + 'static> JuliaTypecheck for OpaqueRust<'frame, T> {
+    unsafe fn julia_typecheck(t: DataType) -> bool {
+        OPAQUE_TYPES
+            .lock()
+            .expect("Opaque types are poisoned")
+            .get(&TypeId::of::<T>())
+            .map(|ty| *ty == t.ptr() as usize)
+            .unwrap_or(false)
+    }
+}
+
+unsafe impl<'frame, 'data, T: Send + 'static> Cast<'frame, 'data> for OpaqueRust<'frame, T> {
+    type Output = Self;
+    fn cast(value: Value<'frame, 'data>) -> JlrsResult<Self::Output> {
+        if value.is::<Self::Output>() {
+            return unsafe { Ok(Self::cast_unchecked(value)) };
+        }
+
+        Err(JlrsError::NotOpaque(type_name::<T>().into()))?
+    }
+
+    unsafe fn cast_unchecked(value: Value<'frame, 'data>) -> Self::Output {
+        OpaqueRust(value.ptr(), PhantomData, PhantomData)
+    }
+}
+
+// Type names aren't guaranteed to be unique, so a hash of the type id is used as a second type
+// parameter of the Julia type.
+fn type_hash<T: 'static>() -> u64 {
+    let mut hasher = DefaultHasher::new();
+    TypeId::of::<T>().hash(&mut hasher);
+    hasher.finish()
+}
+
+unsafe extern "C" fn drop_opaque<T>(ptr: *mut c_void) {
+    catch_unwind(AssertUnwindSafe(|| {
+        std::mem::drop(Box::from_raw(ptr.cast::<T>()));
+    }))
+    .ok();
+}
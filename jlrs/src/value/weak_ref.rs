@@ -2,17 +2,39 @@
 
 use super::Value;
 use crate::error::{JlrsError, JlrsResult};
-use crate::traits::Cast;
+use crate::traits::{private::Internal, Cast, Frame};
 use crate::{impl_julia_type, impl_julia_typecheck, impl_valid_layout};
-use jl_sys::{jl_weakref_t, jl_weakref_type};
+use jl_sys::{jl_gc_new_weakref, jl_nothing, jl_weakref_t, jl_weakref_type};
 use std::marker::PhantomData;
 
-/// A weak reference.
+/// A weak reference. A weak reference doesn't protect the referenced value from garbage
+/// collection, after that value has been freed the reference points to `nothing`. A `WeakRef`
+/// can be created with [`WeakRef::new`], and [`WeakRef::upgrade`] can be used to protect the
+/// referenced value again if it's still alive.
+///
+/// [`WeakRef::new`]: struct.WeakRef.html#method.new
+/// [`WeakRef::upgrade`]: struct.WeakRef.html#method.upgrade
 #[derive(Copy, Clone, Hash, PartialEq, Eq)]
 #[repr(transparent)]
 pub struct WeakRef<'frame>(*mut jl_weakref_t, PhantomData<&'frame ()>);
 
 impl<'frame> WeakRef<'frame> {
+    /// Create a new weak reference to `value`. The `WeakRef` itself is protected by `frame`, the
+    /// referenced value is not. One free slot on the GC stack is required for this function to
+    /// succeed, returns an error if no slot is available.
+    pub fn new<F>(frame: &mut F, value: Value<'_, 'static>) -> JlrsResult<Self>
+    where
+        F: Frame<'frame>,
+    {
+        unsafe {
+            let weak_ref = jl_gc_new_weakref(value.ptr());
+            let weak_ref = frame
+                .protect(weak_ref.cast(), Internal)
+                .map_err(JlrsError::AllocError)?;
+            Ok(Self::cast_unchecked(weak_ref))
+        }
+    }
+
     pub(crate) unsafe fn wrap(weak_ref: *mut jl_weakref_t) -> Self {
         WeakRef(weak_ref, PhantomData)
     }
@@ -27,6 +49,27 @@ impl<'frame> WeakRef<'frame> {
         unsafe { Value::wrap((&*self.ptr()).value) }
     }
 
+    /// Returns the referenced value, protected by `frame`, if it hasn't been freed by the garbage
+    /// collector yet. If the value has been freed, or if the reference refers to `nothing`,
+    /// `None` is returned. One free slot on the GC stack is required if the value is still alive,
+    /// returns an error if no slot is available.
+    pub fn upgrade<'target, F>(self, frame: &mut F) -> JlrsResult<Option<Value<'target, 'static>>>
+    where
+        F: Frame<'target>,
+    {
+        unsafe {
+            let value = (&*self.ptr()).value;
+            if value == jl_nothing {
+                return Ok(None);
+            }
+
+            let value = frame
+                .protect(value, Internal)
+                .map_err(JlrsError::AllocError)?;
+            Ok(Some(value))
+        }
+    }
+
     /// Convert `self` to a `Value`.
     pub fn as_value(self) -> Value<'frame, 'static> {
         self.into()
@@ -0,0 +1,94 @@
+use jlrs::prelude::*;
+use jlrs::util::JULIA;
+
+#[test]
+fn broadcast_over_array_and_scalar() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(3, |global, frame| {
+            let x =
+                Value::move_array(frame, vec![1.0f64, 2.0, 3.0], 3)?.cast::<TypedArray<f64>>()?;
+            let add = Module::base(global).function("+")?;
+
+            let res = add.broadcast(frame, (x, 1.0f64))?.unwrap();
+            let data = res.cast::<TypedArray<f64>>()?.copy_inline_data()?;
+            assert_eq!(data.as_slice(), &[2.0, 3.0, 4.0]);
+
+            let scalar = add.broadcast(frame, (1u8, 2u8))?.unwrap();
+            assert_eq!(scalar.cast::<u8>()?, 3);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn broadcast_into_array() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(4, |global, frame| {
+            let x = Value::move_array(frame, vec![1i64, 2, 3], 3)?;
+            let dest = Value::new_array::<i64, _, _>(frame, 3)?;
+            let mul = Module::base(global).function("*")?;
+
+            let res = mul.broadcast_into(frame, dest, (x, x))?.unwrap();
+            assert!(res.egal(dest));
+
+            let data = dest.cast::<Array>()?.copy_inline_data::<i64>()?;
+            assert_eq!(data.as_slice(), &[1, 4, 9]);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn fuse_broadcast_operations() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(6, |global, frame| {
+            let x = Value::move_array(frame, vec![1.0f64, 2.0, 3.0], 3)?;
+            let add = Module::base(global).function("+")?;
+            let mul = Module::base(global).function("*")?;
+
+            let doubled = mul.broadcasted(frame, (x, 2.0f64))?.unwrap();
+            let res = add
+                .broadcasted(frame, (doubled, 1.0f64))?
+                .unwrap()
+                .materialize(frame)?
+                .unwrap();
+
+            let data = res.cast::<TypedArray<f64>>()?.copy_inline_data()?;
+            assert_eq!(data.as_slice(), &[3.0, 5.0, 7.0]);
+
+            let doubled = mul.broadcasted(frame, (x, 2.0f64))?.unwrap();
+            let res = add.broadcast_into(frame, x, (doubled, x))?.unwrap();
+            assert!(res.egal(x));
+
+            let data = x.cast::<Array>()?.copy_inline_data::<f64>()?;
+            assert_eq!(data.as_slice(), &[3.0, 6.0, 9.0]);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn broadcast_dimension_mismatch_is_exception() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(4, |global, frame| {
+            let x = Value::move_array(frame, vec![1.0f64, 2.0, 3.0], 3)?;
+            let y = Value::move_array(frame, vec![1.0f64, 2.0], 2)?;
+            let add = Module::base(global).function("+")?;
+
+            assert!(add.broadcast(frame, (x, y))?.is_err());
+            Ok(())
+        })
+        .unwrap();
+    });
+}
@@ -0,0 +1,22 @@
+use jlrs::prelude::*;
+
+#[test]
+fn invalid_option_does_not_prevent_init() {
+    unsafe {
+        // Paths that contain the separator can't be joined.
+        let sep = if cfg!(windows) { ";" } else { ":" };
+        match JuliaBuilder::new()
+            .load_path(&[format!("a{}b", sep)])
+            .init()
+        {
+            Err(e) => match *e {
+                JlrsError::InvalidOption(_) => (),
+                _ => panic!("Expected InvalidOption"),
+            },
+            Ok(_) => panic!("Expected an error"),
+        }
+
+        let julia = JuliaBuilder::new().init().unwrap();
+        assert!(julia.ignored_options().is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+use jlrs::prelude::*;
+use jlrs::util::JULIA;
+
+#[test]
+fn call_args_converts_rust_data() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(3, |global, frame| {
+            let x = Value::new(frame, 1.0f64)?;
+            let res = Module::base(global)
+                .function("+")?
+                .call_args(frame, (x, 2.0f64, 3.0f64))?
+                .unwrap()
+                .cast::<f64>()?;
+            assert_eq!(res, 6.0);
+
+            let len = Module::base(global)
+                .function("length")?
+                .call_args_as::<usize, _, _>(frame, (vec![1u32, 2, 3],))?;
+            assert_eq!(len, 3);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn call_args_with_string_and_no_args() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(2, |global, frame| {
+            let s = Module::base(global)
+                .function("string")?
+                .call_args_as::<String, _, _>(frame, ("name", 1u8))?;
+            assert_eq!(s, "name1");
+
+            let t = Module::base(global)
+                .function("time_ns")?
+                .call_args_as::<u64, _, _>(frame, ())?;
+            assert!(t > 0);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn call_args_exception_is_error() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(2, |global, frame| {
+            let func = Module::base(global).function("+")?;
+            assert!(func.call_args(frame, (1u64, "foo"))?.is_err());
+            assert!(func
+                .call_args_as::<u64, _, _>(frame, (1u64, "foo"))
+                .is_err());
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn call_args_with_keywords() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(4, |global, frame| {
+            let b_value = Value::new(frame, 10isize)?;
+            let func = Module::main(global)
+                .submodule("JlrsTests")?
+                .function("funcwithkw")?;
+
+            let kw = named_tuple!(frame, "b" => b_value)?;
+            let v = func
+                .with_keywords(kw)
+                .call_args_as::<isize, _, _>(frame, (1isize,))?;
+
+            assert_eq!(v, 11);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn call_args_with_output() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(1, |global, frame| {
+            let output = frame.output()?;
+            let res = frame.frame(1, |frame| {
+                Module::base(global)
+                    .function("*")?
+                    .with_output(output)
+                    .call_args(frame, (2u64, 3u64))
+            })?;
+
+            assert_eq!(res.unwrap().cast::<u64>()?, 6);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
@@ -0,0 +1,74 @@
+use jlrs::prelude::*;
+use jlrs::util::JULIA;
+use std::ffi::c_void;
+
+unsafe extern "C" fn throw_argument_error() {
+    let mut ccall = CCall::new(8);
+    ccall.throw_exception(|global, frame| {
+        let msg = Value::new(frame, "invalid argument")?;
+        let exc = Module::core(global)
+            .function("ArgumentError")?
+            .call1(frame, msg)?
+            .unwrap_or_else(|e| e);
+
+        Ok(exc)
+    })
+}
+
+unsafe extern "C" fn throw_jlrs_error() {
+    let mut ccall = CCall::new(8);
+    ccall.throw_exception(|global, _frame| {
+        Module::core(global).global("NonexistentGlobal")?;
+        unreachable!()
+    })
+}
+
+unsafe extern "C" fn panic_in_frame() {
+    let mut ccall = CCall::new(8);
+    ccall
+        .frame(1, |_global, _frame| -> JlrsResult<()> {
+            panic!("ccall panicked")
+        })
+        .ok();
+}
+
+// Calls the function pointer with `ccall` and returns the exception that was caught.
+fn call_and_catch(func: unsafe extern "C" fn()) -> JuliaException {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        jlrs.frame(6, |_global, frame| {
+            let caller = Value::eval_string(
+                frame,
+                "p -> try ccall(p, Cvoid, ()); nothing catch e; e end",
+            )?
+            .unwrap();
+            let ptr = Value::new(frame, func as *mut c_void)?;
+            let exc = caller.call1(frame, ptr)?.unwrap();
+            assert!(!exc.is_nothing());
+
+            JuliaException::new(frame, exc)
+        })
+        .unwrap()
+    })
+}
+
+#[test]
+fn throw_exception_from_ccall() {
+    let exc = call_and_catch(throw_argument_error);
+    assert_eq!(exc.type_name(), "ArgumentError");
+    assert!(exc.message().contains("invalid argument"));
+}
+
+#[test]
+fn jlrs_error_is_thrown_as_error_exception() {
+    let exc = call_and_catch(throw_jlrs_error);
+    assert_eq!(exc.type_name(), "ErrorException");
+    assert!(exc.message().contains("NonexistentGlobal"));
+}
+
+#[test]
+fn panic_is_thrown_as_error_exception() {
+    let exc = call_and_catch(panic_in_frame);
+    assert_eq!(exc.type_name(), "ErrorException");
+    assert!(exc.message().contains("ccall panicked"));
+}
@@ -0,0 +1,57 @@
+use jlrs::prelude::*;
+use jlrs::util::JULIA;
+
+#[test]
+fn call_cfunction() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(2, |_global, frame| {
+            let func = Value::eval_string(
+                frame,
+                "cfunctionsquare(x::Float64) = x * x; cfunctionsquare",
+            )?
+            .unwrap();
+            let square = func.as_cfunction::<extern "C" fn(f64) -> f64, _>(frame)?;
+
+            assert_eq!(unsafe { square.call((3.0,)) }, 9.0);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn call_cfunction_multiple_args() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(1, |global, frame| {
+            let func = Module::base(global).function("+")?;
+            let add = func.as_cfunction::<extern "C" fn(i64, i64, i64) -> i64, _>(frame)?;
+
+            assert_eq!(unsafe { add.call((1, 2, 3)) }, 6);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn wrong_return_type_is_error() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(1, |global, frame| {
+            let func = Module::base(global).function("+")?;
+            assert!(func
+                .as_cfunction::<extern "C" fn(f64, f64) -> i32, _>(frame)
+                .is_err());
+            assert!(func
+                .as_cfunction::<extern "C" fn(bool, char) -> f64, _>(frame)
+                .is_err());
+            Ok(())
+        })
+        .unwrap();
+    });
+}
@@ -0,0 +1,47 @@
+use jlrs::prelude::*;
+use jlrs::util::JULIA;
+
+#[test]
+fn call_rust_function_from_julia() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        jlrs.frame(3, |global, frame| {
+            let func = Module::main(global).define_function(frame, "rustsum", |frame, args| {
+                let mut sum = 0u64;
+                for arg in args {
+                    sum += arg.cast::<u64>()?;
+                }
+
+                Value::new(frame, sum)
+            })?;
+            assert!(func.egal(Module::main(global).function("rustsum")?));
+
+            let res =
+                Value::eval_string(frame, "rustsum(UInt64(1), UInt64(2), UInt64(3))")?.unwrap();
+            assert_eq!(res.cast::<u64>()?, 6);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn rust_error_is_thrown() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        jlrs.frame(3, |global, frame| {
+            Module::main(global).define_function(frame, "rustfail", |_frame, _args| {
+                Err(JlrsError::Immutable)?
+            })?;
+
+            let res = Value::eval_string(
+                frame,
+                "try rustfail(1); false catch e; e isa ErrorException end",
+            )?
+            .unwrap();
+            assert!(res.cast::<bool>()?);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
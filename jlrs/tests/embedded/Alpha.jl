@@ -0,0 +1,5 @@
+module Alpha
+using ..Beta
+
+alpha() = Beta.beta() + 1
+end
@@ -0,0 +1,3 @@
+module Beta
+beta() = 41
+end
@@ -0,0 +1,168 @@
+use jlrs::prelude::*;
+use jlrs::util::JULIA;
+
+#[test]
+fn method_error() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let exc = jlrs
+            .frame(4, |global, frame| {
+                let i = Value::new(frame, 2u64)?;
+                let s = Value::new(frame, "foo")?;
+                let out = Module::base(global)
+                    .function("+")?
+                    .call2(frame, i, s)?
+                    .unwrap_err();
+
+                JuliaException::new(frame, out)
+            })
+            .unwrap();
+
+        assert_eq!(exc.type_name(), "MethodError");
+        assert!(exc.message().contains("no method matching"));
+        assert!(exc.backtrace().is_none());
+
+        match exc.kind() {
+            ExceptionKind::MethodError {
+                function,
+                arg_types,
+                ..
+            } => {
+                assert_eq!(function, "+");
+                assert_eq!(arg_types, &["UInt64".to_string(), "String".to_string()]);
+            }
+            _ => panic!("Expected MethodError"),
+        }
+    });
+}
+
+#[test]
+fn bounds_error() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let exc = jlrs
+            .frame(5, |global, frame| {
+                let idx = Value::new(frame, 4usize)?;
+                let data = vec![1.0f64, 2., 3.];
+                let array = Value::move_array(frame, data, 3)?;
+                let out = Module::base(global)
+                    .function("getindex")?
+                    .call2(frame, array, idx)?
+                    .unwrap_err();
+
+                JuliaException::new(frame, out)
+            })
+            .unwrap();
+
+        assert_eq!(exc.type_name(), "BoundsError");
+        match exc.kind() {
+            ExceptionKind::BoundsError { array, index } => {
+                assert!(array.as_ref().unwrap().contains("Array{Float64,1}"));
+                assert_eq!(index.as_ref().unwrap(), "(4,)");
+            }
+            _ => panic!("Expected BoundsError"),
+        }
+    });
+}
+
+#[test]
+fn undef_var_error() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let res = jlrs.frame(1, |_global, frame| {
+            Value::eval_string(frame, "this_variable_does_not_exist")?.into_jlrs_result(frame)
+        });
+
+        match res {
+            Err(e) => match *e {
+                JlrsError::Exception(exc) => {
+                    assert_eq!(exc.type_name(), "UndefVarError");
+                    assert_eq!(
+                        exc.kind(),
+                        &ExceptionKind::UndefVarError {
+                            var: "this_variable_does_not_exist".into()
+                        }
+                    );
+                }
+                _ => panic!("Expected an exception"),
+            },
+            Ok(_) => panic!("Expected an error"),
+        }
+    });
+}
+
+#[test]
+fn traced_exception() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let exc = jlrs
+            .frame(7, |global, frame| {
+                let idx = Value::new(frame, 4usize)?;
+                let data = vec![1.0f64, 2., 3.];
+                let array = Value::move_array(frame, data, 3)?;
+                let out = Module::base(global)
+                    .function("getindex")?
+                    .attach_stacktrace(frame)?
+                    .unwrap()
+                    .call2(frame, array, idx)?
+                    .unwrap_err();
+
+                JuliaException::new(frame, out)
+            })
+            .unwrap();
+
+        assert_eq!(exc.type_name(), "BoundsError");
+        let backtrace = exc.backtrace().unwrap();
+        assert!(!backtrace.frames().is_empty());
+        assert!(backtrace
+            .frames()
+            .iter()
+            .any(|frame| frame.func() == "getindex" && frame.module() == Some("Base")));
+    });
+}
+
+#[test]
+fn error_exception_contents() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let res = jlrs.frame(1, |_global, frame| {
+            Value::eval_string(frame, "error(\"x\")")?.into_jlrs_result(frame)
+        });
+
+        match res {
+            Err(e) => match *e {
+                JlrsError::Exception(exc) => {
+                    assert_eq!(exc.type_name(), "ErrorException");
+                    assert_eq!(exc.message(), "x");
+                    assert!(exc.backtrace().is_none());
+                    assert!(matches!(exc.kind(), ExceptionKind::Other));
+                }
+                _ => panic!("Expected an exception"),
+            },
+            _ => panic!("Expected an error"),
+        }
+    });
+}
+
+#[test]
+fn divide_error_contents() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let res = jlrs.frame(1, |_global, frame| {
+            Value::eval_string(frame, "div(1, 0)")?.into_jlrs_result(frame)
+        });
+
+        match res {
+            Err(e) => match *e {
+                JlrsError::Exception(exc) => {
+                    assert_eq!(exc.type_name(), "DivideError");
+                    assert!(exc.message().contains("integer division error"));
+                    assert!(exc.backtrace().is_none());
+                    assert!(matches!(exc.kind(), ExceptionKind::Other));
+                }
+                _ => panic!("Expected an exception"),
+            },
+            _ => panic!("Expected an error"),
+        }
+    });
+}
@@ -0,0 +1,145 @@
+use jlrs::prelude::*;
+use jlrs::traits::gc::{Gc, GcCollection, GcPhase};
+use jlrs::util::JULIA;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[test]
+fn collections_are_counted() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let before = jlrs.gc_stats().unwrap();
+
+        jlrs.frame(1, |_global, frame| {
+            Value::eval_string(frame, "zeros(UInt8, 1_000_000)")?.unwrap();
+            unsafe { frame.gc_collect(GcCollection::Full) };
+            Ok(())
+        })
+        .unwrap();
+
+        let after = jlrs.gc_stats().unwrap();
+        assert!(after.collections > before.collections);
+        assert!(after.full_collections > before.full_collections);
+        assert!(after.allocated_bytes >= before.allocated_bytes + 1_000_000);
+        assert!(after.total_pause >= before.total_pause);
+        assert!(after.live_bytes > 0);
+    });
+}
+
+#[test]
+fn callbacks_are_called() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let started = Arc::new(AtomicUsize::new(0));
+        let ended = Arc::new(AtomicUsize::new(0));
+
+        let s = started.clone();
+        jlrs.add_gc_callback(GcPhase::Start, move |_| {
+            s.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+
+        let e = ended.clone();
+        jlrs.add_gc_callback(GcPhase::End, move |full| {
+            if full {
+                e.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .unwrap();
+
+        unsafe { jlrs.gc_collect(GcCollection::Full) };
+        assert!(started.load(Ordering::SeqCst) >= 1);
+        assert!(ended.load(Ordering::SeqCst) >= 1);
+    });
+}
+
+#[test]
+fn set_heap_size_hint() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let supported = jlrs
+            .frame(1, |_global, frame| {
+                Value::eval_string(frame, "VERSION >= v\"1.9\"")?
+                    .unwrap()
+                    .cast::<bool>()
+            })
+            .unwrap();
+
+        assert_eq!(jlrs.set_heap_size_hint(1 << 32).is_ok(), supported);
+    });
+}
+
+#[test]
+fn external_bytes_are_counted() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let before = jlrs.gc_stats().unwrap();
+        jlrs.account_external_bytes(1 << 20);
+        let after = jlrs.gc_stats().unwrap();
+        assert!(after.allocated_bytes >= before.allocated_bytes + (1 << 20));
+        jlrs.account_external_bytes(-(1 << 20));
+    });
+}
+
+#[test]
+fn borrowed_arrays_are_counted() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let mut data = vec![0u64; 1 << 17];
+        let before = jlrs.gc_stats().unwrap();
+
+        jlrs.frame(1, |_global, frame| {
+            Value::borrow_array(frame, &mut data, 1 << 17)?;
+            Ok(())
+        })
+        .unwrap();
+
+        let after = jlrs.gc_stats().unwrap();
+        assert!(after.allocated_bytes >= before.allocated_bytes + (1 << 20));
+        unsafe { jlrs.gc_collect(GcCollection::Full) };
+    });
+}
+
+#[test]
+fn moved_arrays_are_freed() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let before = jlrs.gc_stats().unwrap();
+
+        jlrs.frame(1, |_global, frame| {
+            Value::move_array(frame, vec![1u32; 1 << 18], 1 << 18)?;
+            Ok(())
+        })
+        .unwrap();
+
+        let after = jlrs.gc_stats().unwrap();
+        assert!(after.allocated_bytes >= before.allocated_bytes + (1 << 20));
+
+        // The first collection runs the finalizer, the second frees the array.
+        unsafe {
+            jlrs.gc_collect(GcCollection::Full);
+            jlrs.gc_collect(GcCollection::Full);
+        }
+    });
+}
+
+#[test]
+fn gc_safe_region() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        jlrs.frame(2, |_global, frame| {
+            let v = Value::new(frame, 3u64)?;
+            let n = v.cast::<u64>()?;
+
+            let sum = unsafe { frame.gc_safe(|| (0..1_000_000u64).map(|i| i % n).sum::<u64>()) };
+            assert_eq!(sum, 999_999);
+
+            // Julia can be called again after leaving the region.
+            unsafe { frame.gc_collect(GcCollection::Full) };
+            assert_eq!(v.cast::<u64>()?, 3);
+            Value::new(frame, sum)?;
+            Ok(())
+        })
+        .unwrap();
+    });
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "gc-diagnostics")]
+use jlrs::diagnostics::{self, FrameKind};
+use jlrs::prelude::*;
+use jlrs::util::JULIA;
+
+#[test]
+fn frame_usage_is_recorded() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        let line = line!() + 1;
+        jlrs.frame(8, |_global, frame| {
+            Value::new(frame, 1u64)?;
+            Ok(())
+        })
+        .unwrap();
+
+        let report = diagnostics::report();
+        let stats = report
+            .frames
+            .iter()
+            .find(|f| f.location.file() == file!() && f.location.line() == line)
+            .unwrap();
+
+        assert_eq!(stats.kind, FrameKind::Static);
+        assert_eq!(stats.capacity, 8);
+        assert_eq!(stats.peak, 1);
+        assert!(stats.is_over_provisioned());
+        assert!(report.over_provisioned().any(|f| f.location.line() == line));
+    });
+}
+
+#[test]
+fn exceeded_capacity_is_recorded() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.dynamic_frame(|_global, frame| {
+            let line = line!() + 1;
+            let res = frame.frame(1, |frame| {
+                Value::new(frame, 1u64)?;
+                Value::new(frame, 2u64)?;
+                Ok(())
+            });
+
+            assert!(res.is_err());
+
+            let report = diagnostics::report();
+            let stats = report
+                .exceeded()
+                .find(|f| f.location.file() == file!() && f.location.line() == line)
+                .unwrap();
+
+            assert_eq!(stats.overflows, 1);
+            assert_eq!(stats.peak, 1);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn stack_usage_is_recorded() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(16, |_global, frame| {
+            Values::new(frame, [1u64; 16])?;
+            Ok(())
+        })
+        .unwrap();
+
+        let report = diagnostics::report();
+        let stack = report.stacks.iter().find(|s| s.name == "julia").unwrap();
+        assert!(stack.peak >= 18);
+        assert!(stack.size >= stack.peak);
+    });
+}
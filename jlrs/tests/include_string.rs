@@ -0,0 +1,79 @@
+use jlrs::prelude::*;
+use jlrs::util::JULIA;
+
+const SOURCES: &[(&str, &str)] = include_julia_dir!("tests/embedded");
+
+#[test]
+fn include_string_keeps_file_and_line() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let code = "module IncludeStringTest\nfunction thrower()\n    error(\"thrown\")\nend\nend";
+        jlrs.include_string(code, "virtual/thrower.jl").unwrap();
+
+        let exc = jlrs
+            .frame(4, |global, frame| {
+                let out = Module::main(global)
+                    .submodule("IncludeStringTest")?
+                    .function("thrower")?
+                    .attach_stacktrace(frame)?
+                    .unwrap()
+                    .call0(frame)?
+                    .unwrap_err();
+
+                JuliaException::new(frame, out)
+            })
+            .unwrap();
+
+        let backtrace = exc.backtrace().unwrap();
+        assert!(backtrace
+            .frames()
+            .iter()
+            .any(|f| f.file() == "virtual/thrower.jl" && f.line() == 3));
+    });
+}
+
+#[test]
+fn include_string_error() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        match *jlrs
+            .include_string("error(\"include failed\")", "virtual/error.jl")
+            .unwrap_err()
+        {
+            JlrsError::Exception(ref exc) => {
+                assert_eq!(exc.type_name(), "LoadError");
+                assert!(exc.message().contains("include failed"));
+            }
+            ref e => panic!("Expected an exception, got {}", e),
+        }
+    });
+}
+
+#[test]
+fn embedded_sources_are_sorted() {
+    let names: Vec<_> = SOURCES.iter().map(|(name, _)| *name).collect();
+    assert_eq!(names, ["tests/embedded/Beta.jl", "tests/embedded/Alpha.jl"]);
+}
+
+#[test]
+fn include_embedded_sources() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        for (name, code) in SOURCES {
+            jlrs.include_string(code, name).unwrap();
+        }
+
+        let out = jlrs
+            .frame(1, |global, frame| {
+                Module::main(global)
+                    .submodule("Alpha")?
+                    .function("alpha")?
+                    .call0(frame)?
+                    .unwrap()
+                    .cast::<i64>()
+            })
+            .unwrap();
+
+        assert_eq!(out, 42);
+    });
+}
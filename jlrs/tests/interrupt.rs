@@ -0,0 +1,111 @@
+use jlrs::interrupt::InterruptHandle;
+use jlrs::prelude::*;
+use jlrs::util::JULIA;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+fn assert_send_clone<T: Send + Clone + 'static>(_: &T) {}
+
+#[test]
+fn interrupt_handle_can_be_sent() {
+    JULIA.with(|j| {
+        let jlrs = j.borrow();
+        let handle: InterruptHandle = jlrs.interrupt_handle();
+        assert_send_clone(&handle);
+        std::thread::spawn(move || drop(handle.clone()))
+            .join()
+            .unwrap();
+    });
+}
+
+#[test]
+fn interrupt_exception_kind() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let exc = jlrs
+            .frame(3, |_global, frame| {
+                let out = Value::eval_string(frame, "throw(InterruptException())")?.unwrap_err();
+                JuliaException::new(frame, out)
+            })
+            .unwrap();
+
+        assert_eq!(exc.type_name(), "InterruptException");
+        assert_eq!(exc.kind(), &ExceptionKind::InterruptException);
+    });
+}
+
+// Interrupt Julia until the call has returned.
+fn interrupt_until_done(handle: InterruptHandle, done: Arc<AtomicBool>) -> JoinHandle<()> {
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(500));
+        while !done.load(Ordering::Acquire) {
+            handle.interrupt().unwrap();
+            thread::sleep(Duration::from_millis(100));
+        }
+    })
+}
+
+#[test]
+fn interrupt_sleep() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let done = Arc::new(AtomicBool::new(false));
+        let interrupter = interrupt_until_done(jlrs.interrupt_handle(), done.clone());
+
+        let start = Instant::now();
+        let exc = jlrs
+            .frame(3, |global, frame| {
+                let sleep = Module::base(global).function("sleep")?;
+                let secs = Value::new(frame, 10.0f64)?;
+                let out = sleep.call1(frame, secs)?;
+                done.store(true, Ordering::Release);
+                JuliaException::new(frame, out.unwrap_err())
+            })
+            .unwrap();
+
+        interrupter.join().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(exc.kind(), &ExceptionKind::InterruptException);
+    });
+}
+
+#[test]
+fn interrupt_busy_loop() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let done = Arc::new(AtomicBool::new(false));
+        let interrupter = interrupt_until_done(jlrs.interrupt_handle(), done.clone());
+
+        let exc = jlrs
+            .frame(3, |_global, frame| {
+                let out = Value::eval_string(
+                    frame,
+                    "function busyloop() while true; GC.safepoint(); end end; busyloop()",
+                )?;
+                done.store(true, Ordering::Release);
+                JuliaException::new(frame, out.unwrap_err())
+            })
+            .unwrap();
+
+        interrupter.join().unwrap();
+        assert_eq!(exc.kind(), &ExceptionKind::InterruptException);
+    });
+}
+
+#[test]
+fn interrupt_without_call_is_dropped() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        jlrs.interrupt_handle().interrupt().unwrap();
+
+        jlrs.frame(3, |global, frame| {
+            let sleep = Module::base(global).function("sleep")?;
+            let secs = Value::new(frame, 0.5f64)?;
+            assert!(sleep.call1(frame, secs)?.is_ok());
+            Ok(())
+        })
+        .unwrap();
+    });
+}
@@ -0,0 +1,89 @@
+use jlrs::prelude::*;
+use jlrs::util::JULIA;
+
+#[test]
+fn call_latest() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(4, |_global, frame| {
+            let func = Value::eval_string(frame, "invoketestlatest(x) = x + 1; invoketestlatest")?
+                .unwrap();
+            let x = Value::new(frame, 1i64)?;
+            let res = func.call_latest(frame, &mut [x])?.unwrap();
+            assert_eq!(res.cast::<i64>()?, 2);
+
+            let output = frame.output()?;
+            let res = frame.frame(0, |frame| {
+                func.with_output(output).call_latest(frame, &mut [x])
+            })?;
+            assert_eq!(res.unwrap().cast::<i64>()?, 2);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn invoke_less_specific_method() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(6, |_global, frame| {
+            let func = Value::eval_string(
+                frame,
+                "invoketest(x::Integer) = 1; invoketest(x::Int) = 2; invoketest",
+            )?
+            .unwrap();
+            let sig = Value::eval_string(frame, "Tuple{Integer}")?.unwrap();
+            let x = Value::new(frame, 1i64)?;
+
+            assert_eq!(func.call1(frame, x)?.unwrap().cast::<i64>()?, 2);
+            let res = func.invoke_with_signature(frame, sig, &mut [x])?.unwrap();
+            assert_eq!(res.cast::<i64>()?, 1);
+
+            let output = frame.output()?;
+            let res = frame.frame(0, |frame| {
+                func.with_output(output)
+                    .invoke_with_signature(frame, sig, &mut [x])
+            })?;
+            assert_eq!(res.unwrap().cast::<i64>()?, 1);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn invoke_with_keywords() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(7, |global, frame| {
+            let a_value = Value::new(frame, 1isize)?;
+            let b_value = Value::new(frame, 10isize)?;
+            let c_value = Value::new(frame, 5isize)?;
+            let func = Module::main(global)
+                .submodule("JlrsTests")?
+                .function("funcwithkw")?;
+
+            let kw = named_tuple!(frame, "b" => b_value)?;
+            let v = func
+                .with_keywords(kw)
+                .call_latest(frame, &mut [a_value])?
+                .unwrap()
+                .cast::<isize>()?;
+            assert_eq!(v, 11);
+
+            let sig = Value::eval_string(frame, "Tuple{Int, Vararg{Any}}")?.unwrap();
+            let v = func
+                .with_keywords(kw)
+                .invoke_with_signature(frame, sig, &mut [a_value, c_value])?
+                .unwrap()
+                .cast::<isize>()?;
+            assert_eq!(v, 16);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
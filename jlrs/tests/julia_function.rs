@@ -0,0 +1,112 @@
+use jlrs::prelude::*;
+use jlrs::traits::gc::Gc;
+use jlrs::util::JULIA;
+use jlrs::value::julia_function::JuliaFunction;
+
+#[test]
+fn call_julia_function() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(2, |global, frame| {
+            let func = Module::base(global).function("+")?;
+            let add = JuliaFunction::<(f64, f64), f64>::new(frame, func)?;
+
+            let mut sum = 0.0;
+            for i in 0..10 {
+                sum = frame.frame(1, |frame| add.call(frame, (sum, i as f64)))?;
+            }
+
+            assert_eq!(sum, 45.0);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn new_method_invalidates_cache() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(4, |global, frame| {
+            Value::eval_string(frame, "juliafunctiontest(x) = 1")?.unwrap();
+            let func = Module::main(global).function("juliafunctiontest")?;
+            let f = JuliaFunction::<(u32,), i64>::new(frame, func)?;
+            let before = f.method_instance();
+            assert_eq!(f.call(frame, (1u32,))?, 1);
+
+            Value::eval_string(frame, "juliafunctiontest(x::UInt32) = 2")?.unwrap();
+            assert_eq!(f.call(frame, (1u32,))?, 2);
+            assert!(f.method_instance() != before);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn missing_method_is_error() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(1, |global, frame| {
+            let func = Module::base(global).function("+")?;
+            assert!(JuliaFunction::<(bool, char, f32), f32>::new(frame, func).is_err());
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn call_does_not_allocate() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(2, |global, frame| {
+            let func = Module::base(global).function("+")?;
+            let add = JuliaFunction::<(i64, i64), i64>::new(frame, func)?;
+            frame.frame(1, |frame| add.call(frame, (1, 1)))?;
+
+            // Small integers are cached by Julia, so calling the method instance directly doesn't
+            // allocate anything. The only allocation is the result of the first `gc_stats`.
+            let before = frame.gc_stats()?;
+            let mut sum = 0;
+            for _ in 0..100 {
+                sum = frame.frame(1, |frame| add.call(frame, (sum, 1)))?;
+            }
+            let after = frame.gc_stats()?;
+
+            assert_eq!(sum, 100);
+            assert!(after.pool_alloc - before.pool_alloc <= 1);
+            assert_eq!(after.big_alloc, before.big_alloc);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn exception_is_caught() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        jlrs.frame(2, |global, frame| {
+            let func = Module::base(global).function("div")?;
+            let div = JuliaFunction::<(i64, i64), i64>::new(frame, func)?;
+            assert_eq!(div.call(frame, (6, 3))?, 2);
+
+            match div.call(frame, (1, 0)) {
+                Err(e) => match *e {
+                    JlrsError::Exception(exc) => assert_eq!(exc.type_name(), "DivideError"),
+                    _ => panic!("Expected an exception"),
+                },
+                _ => panic!("Expected an error"),
+            }
+
+            Ok(())
+        })
+        .unwrap();
+    });
+}
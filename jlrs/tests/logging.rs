@@ -0,0 +1,87 @@
+use jlrs::logging::{LogLevel, LogRecord, Logger};
+use jlrs::prelude::*;
+use jlrs::util::JULIA;
+use std::sync::{Arc, Mutex};
+
+fn collecting_logger(min_level: LogLevel) -> (Logger, Arc<Mutex<Vec<LogRecord>>>) {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let records2 = records.clone();
+    let logger = Logger::new(min_level, move |record| {
+        records2.lock().unwrap().push(record.clone());
+    });
+
+    (logger, records)
+}
+
+#[test]
+fn forward_log_record() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let (logger, records) = collecting_logger(LogLevel::Debug);
+        jlrs.set_logger(Some(logger)).unwrap();
+
+        jlrs.frame(1, |_global, frame| {
+            Value::eval_string(frame, "@warn \"careful\" x = 1 y = \"two\"")?.unwrap();
+            Ok(())
+        })
+        .unwrap();
+
+        jlrs.set_logger(None).unwrap();
+
+        let records = records.lock().unwrap();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.level, LogLevel::Warn);
+        assert_eq!(record.message, "careful");
+        assert_eq!(record.module, "Main");
+        assert!(record.line > 0);
+        assert_eq!(
+            record.kwargs,
+            vec![
+                ("x".to_string(), "1".to_string()),
+                ("y".to_string(), "two".to_string())
+            ]
+        );
+    });
+}
+
+#[test]
+fn records_below_min_level_are_discarded() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let (logger, records) = collecting_logger(LogLevel::Warn);
+        jlrs.set_logger(Some(logger)).unwrap();
+
+        jlrs.frame(2, |_global, frame| {
+            Value::eval_string(frame, "@info \"ignored\"")?.unwrap();
+            Value::eval_string(frame, "@error \"reported\"")?.unwrap();
+            Ok(())
+        })
+        .unwrap();
+
+        jlrs.set_logger(None).unwrap();
+
+        let records = records.lock().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].level, LogLevel::Error);
+        assert_eq!(records[0].message, "reported");
+    });
+}
+
+#[test]
+fn logger_is_removed() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let (logger, records) = collecting_logger(LogLevel::Debug);
+        jlrs.set_logger(Some(logger)).unwrap();
+        jlrs.set_logger(None).unwrap();
+
+        jlrs.frame(1, |_global, frame| {
+            Value::eval_string(frame, "@info \"not forwarded\"")?.unwrap();
+            Ok(())
+        })
+        .unwrap();
+
+        assert!(records.lock().unwrap().is_empty());
+    });
+}
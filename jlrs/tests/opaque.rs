@@ -0,0 +1,74 @@
+use jlrs::prelude::*;
+use jlrs::traits::gc::{Gc, GcCollection};
+use jlrs::util::JULIA;
+use jlrs::value::opaque::OpaqueRust;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+struct Counter(Arc<AtomicUsize>);
+
+impl Drop for Counter {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn borrow_opaque_data() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        jlrs.frame(2, |_global, frame| {
+            let v = OpaqueRust::new(frame, vec![1, 2, 3])?.as_value();
+            assert!(v.type_name().contains("OpaqueRust"));
+
+            let opaque = v.cast::<OpaqueRust<Vec<i32>>>()?;
+            opaque.get_mut(frame)?.push(4);
+            assert_eq!(opaque.get(frame)?, &[1, 2, 3, 4]);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn cast_to_wrong_type_fails() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        jlrs.frame(2, |_global, frame| {
+            let v = OpaqueRust::new(frame, 1u32)?.as_value();
+            assert!(v.cast::<OpaqueRust<u64>>().is_err());
+            assert!(v.is::<OpaqueRust<u32>>());
+
+            let other = Value::new(frame, 1u32)?;
+            assert!(other.cast::<OpaqueRust<u32>>().is_err());
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn opaque_data_is_dropped() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let dropped = Arc::new(AtomicUsize::new(0));
+
+        jlrs.frame(1, |_global, frame| {
+            OpaqueRust::new(frame, Counter(dropped.clone()))?;
+            Ok(())
+        })
+        .unwrap();
+
+        unsafe { jlrs.gc_collect(GcCollection::Full) };
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+
+        jlrs.frame(1, |_global, frame| {
+            let opaque = OpaqueRust::new(frame, Counter(dropped.clone()))?;
+            unsafe { opaque.as_value().finalize() };
+            assert_eq!(dropped.load(Ordering::SeqCst), 2);
+            assert!(opaque.get(frame).is_err());
+            Ok(())
+        })
+        .unwrap();
+    });
+}
@@ -0,0 +1,74 @@
+use jlrs::prelude::*;
+use jlrs::util::JULIA;
+
+#[test]
+fn panic_in_frame() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let res = jlrs.frame(1, |_global, frame| -> JlrsResult<()> {
+            Value::new(frame, 1u64)?;
+            panic!("frame panicked")
+        });
+
+        match *res.unwrap_err() {
+            JlrsError::Panic(ref msg) => assert_eq!(msg, "frame panicked"),
+            ref e => panic!("Expected a panic, got {}", e),
+        }
+    });
+}
+
+#[test]
+fn panic_in_nested_dynamic_frame() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let res = jlrs.dynamic_frame(|_global, frame| {
+            Value::new(frame, 1u64)?;
+            frame.dynamic_frame(|frame| -> JlrsResult<()> {
+                Value::new(frame, 2u64)?;
+                panic!("nested frame panicked: {}", 3)
+            })
+        });
+
+        match *res.unwrap_err() {
+            JlrsError::Panic(ref msg) => assert_eq!(msg, "nested frame panicked: 3"),
+            ref e => panic!("Expected a panic, got {}", e),
+        }
+    });
+}
+
+#[test]
+fn stack_is_usable_after_panic() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let stack_size = jlrs.stack_size();
+        let res = jlrs.frame(stack_size - 2, |_global, _frame| -> JlrsResult<()> {
+            panic!("frame panicked")
+        });
+        assert!(res.is_err());
+
+        let out = jlrs.frame(stack_size - 2, |_global, frame| {
+            Value::new(frame, 3u64)?.cast::<u64>()
+        });
+        assert_eq!(out.unwrap(), 3);
+    });
+}
+
+#[test]
+fn output_is_restored_after_panic() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let res = jlrs.capture_output(0, |_global, _frame| -> JlrsResult<()> {
+            panic!("capture panicked")
+        });
+        assert!(res.is_err());
+
+        let (_, output) = jlrs
+            .capture_output(1, |_global, frame| {
+                Value::eval_string(frame, "print(\"restored\")")?.unwrap();
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(output.stdout, "restored");
+    });
+}
@@ -0,0 +1,141 @@
+use jlrs::persistent::Persistent;
+use jlrs::prelude::*;
+use jlrs::traits::gc::{Gc, GcCollection};
+use jlrs::util::JULIA;
+use std::thread;
+
+fn n_roots(jlrs: &mut Julia) -> usize {
+    jlrs.frame(1, |_global, frame| {
+        Value::eval_string(frame, "length(Main.Jlrs.roots)")?
+            .unwrap()
+            .cast::<usize>()
+    })
+    .unwrap()
+}
+
+#[test]
+fn value_outlives_frame() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let persistent = jlrs
+            .frame(1, |_global, frame| {
+                let v = Value::eval_string(frame, "collect(1.0:5.0)")?.unwrap();
+                Persistent::new(frame, v)
+            })
+            .unwrap();
+
+        unsafe { jlrs.gc_collect(GcCollection::Full) };
+
+        let sum = jlrs
+            .frame(1, |global, frame| {
+                let array = persistent.value(frame);
+                Module::base(global)
+                    .function("sum")?
+                    .call1(frame, array)?
+                    .unwrap()
+                    .cast::<f64>()
+            })
+            .unwrap();
+
+        assert_eq!(sum, 15.0);
+    });
+}
+
+#[test]
+fn cast_persistent_value() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let persistent = jlrs
+            .frame(0, |global, frame| {
+                let v = Module::base(global).global("Float64")?;
+                Persistent::new(frame, v)
+            })
+            .unwrap();
+
+        jlrs.frame(0, |_global, frame| {
+            let ty = persistent.cast::<DataType, _>(frame)?;
+            assert_eq!(ty.name(), "Float64");
+            assert!(persistent.cast::<Module, _>(frame).is_err());
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn value_is_released_on_drop() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let before = n_roots(&mut jlrs);
+
+        let (a, b) = jlrs
+            .frame(1, |_global, frame| {
+                let v = Value::eval_string(frame, "[1, 2, 3]")?.unwrap();
+                Ok((Persistent::new(frame, v)?, Persistent::new(frame, v)?))
+            })
+            .unwrap();
+
+        assert_eq!(n_roots(&mut jlrs), before + 2);
+        std::mem::drop(a);
+        assert_eq!(n_roots(&mut jlrs), before + 1);
+        std::mem::drop(b);
+        assert_eq!(n_roots(&mut jlrs), before);
+    });
+}
+
+#[test]
+fn drop_in_other_thread_is_deferred() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let before = n_roots(&mut jlrs);
+
+        let persistent = jlrs
+            .frame(1, |_global, frame| {
+                let v = Value::eval_string(frame, "Ref(1)")?.unwrap();
+                Persistent::new(frame, v)
+            })
+            .unwrap();
+
+        thread::spawn(move || std::mem::drop(persistent))
+            .join()
+            .unwrap();
+
+        assert_eq!(n_roots(&mut jlrs), before + 1);
+
+        // Pending releases are handled when a new value is rooted.
+        let other = jlrs
+            .frame(1, |_global, frame| {
+                let v = Value::eval_string(frame, "Ref(2)")?.unwrap();
+                Persistent::new(frame, v)
+            })
+            .unwrap();
+
+        assert_eq!(n_roots(&mut jlrs), before + 1);
+        std::mem::drop(other);
+        assert_eq!(n_roots(&mut jlrs), before);
+    });
+}
+
+#[test]
+fn egal_values_are_rooted_separately() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+
+        let (a, b) = jlrs
+            .frame(2, |_global, frame| {
+                let x = Value::new(frame, 1.5f64)?;
+                let y = Value::new(frame, 1.5f64)?;
+                assert!(x.egal(y));
+                Ok((Persistent::new(frame, x)?, Persistent::new(frame, y)?))
+            })
+            .unwrap();
+
+        std::mem::drop(a);
+        unsafe { jlrs.gc_collect(GcCollection::Full) };
+
+        let y = jlrs
+            .frame(0, |_global, frame| b.cast::<f64, _>(frame))
+            .unwrap();
+        assert_eq!(y, 1.5);
+    });
+}
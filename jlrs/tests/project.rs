@@ -0,0 +1,98 @@
+use jlrs::prelude::*;
+use jlrs::util::JULIA;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn activate_missing_project() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let err = jlrs.activate_project("does/not/exist").unwrap_err();
+        match *err {
+            JlrsError::ProjectNotFound(ref path) => assert_eq!(path, "does/not/exist"),
+            _ => panic!("Unexpected error: {}", err),
+        }
+    });
+}
+
+#[test]
+fn activate_directory_without_project() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let dir = env::temp_dir().join("jlrs_no_project");
+        fs::create_dir_all(&dir).unwrap();
+        fs::remove_file(dir.join("Project.toml")).ok();
+        fs::remove_file(dir.join("JuliaProject.toml")).ok();
+
+        let err = jlrs.activate_project(&dir).unwrap_err();
+        match *err {
+            JlrsError::ProjectNotFound(ref path) => assert!(path.contains("jlrs_no_project")),
+            _ => panic!("Unexpected error: {}", err),
+        }
+    });
+}
+
+#[test]
+fn relative_project_path_is_made_absolute() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let dir = Path::new("target").join("jlrs_relative_project");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Project.toml"), "[deps]\n").unwrap();
+
+        jlrs.activate_project(&dir).unwrap();
+
+        jlrs.frame(1, |_global, frame| {
+            let active = Value::eval_string(frame, "Base.ACTIVE_PROJECT[]")?.unwrap();
+            let active = active.cast::<String>()?;
+            assert!(Path::new(&active).is_absolute());
+            assert!(active.ends_with("jlrs_relative_project"));
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn activate_and_instantiate_empty_project() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let dir = env::temp_dir().join("jlrs_empty_project");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Project.toml"), "[deps]\n").unwrap();
+
+        jlrs.activate_project(&dir).unwrap();
+        jlrs.instantiate_offline().unwrap();
+
+        jlrs.frame(1, |_global, frame| {
+            let active = Value::eval_string(frame, "Base.active_project()")?.unwrap();
+            assert!(active.cast::<String>()?.contains("jlrs_empty_project"));
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn missing_packages_are_reported() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let dir = env::temp_dir().join("jlrs_missing_packages");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("Project.toml"),
+            "[deps]\nNotARealPackage = \"e0a3a6b4-4c33-4b47-9e1c-7f1d5d0b1f1e\"\n",
+        )
+        .unwrap();
+
+        jlrs.activate_project(&dir).unwrap();
+        let err = jlrs.instantiate_offline().unwrap_err();
+        match *err {
+            JlrsError::MissingPackages(ref packages) => {
+                assert_eq!(packages, &vec!["NotARealPackage".to_string()])
+            }
+            _ => panic!("Unexpected error: {}", err),
+        }
+    });
+}
@@ -33,3 +33,23 @@ fn include_error() {
         assert!(jlrs.include("Cargo.toml").is_err());
     });
 }
+
+#[test]
+fn cannot_init_again_with_builder() {
+    JULIA.with(|_j| unsafe {
+        assert!(JuliaBuilder::new().stack_size(42).init().is_err());
+    });
+}
+
+#[test]
+fn builder_rejects_invalid_optimization_level() {
+    JULIA.with(|_j| unsafe {
+        match JuliaBuilder::new().optimization_level(4).init() {
+            Err(e) => match *e {
+                JlrsError::InvalidOption(_) => (),
+                _ => panic!("Expected InvalidOption"),
+            },
+            Ok(_) => panic!("Expected an error"),
+        }
+    });
+}
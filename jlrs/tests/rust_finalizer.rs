@@ -0,0 +1,67 @@
+use jlrs::prelude::*;
+use jlrs::traits::gc::{Gc, GcCollection};
+use jlrs::util::JULIA;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[test]
+fn rust_finalizer_is_called() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let called = Arc::new(AtomicUsize::new(0));
+
+        let c = called.clone();
+        jlrs.frame(1, |_global, frame| {
+            let v = Value::eval_string(frame, "Ref(1)")?.unwrap();
+            v.add_rust_finalizer(frame, move || {
+                c.fetch_add(1, Ordering::SeqCst);
+            })
+        })
+        .unwrap();
+
+        jlrs.frame(1, |_global, frame| {
+            unsafe { frame.gc_collect(GcCollection::Full) };
+            // Finalizers are run after the collection when Julia is called again.
+            Value::eval_string(frame, "GC.gc(); nothing")?.unwrap();
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(called.load(Ordering::SeqCst), 1);
+    });
+}
+
+#[test]
+fn rust_finalizer_is_called_by_finalize() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let called = Arc::new(AtomicUsize::new(0));
+
+        let c = called.clone();
+        jlrs.frame(1, |_global, frame| {
+            let v = Value::eval_string(frame, "Ref(2)")?.unwrap();
+            v.add_rust_finalizer(frame, move || {
+                c.fetch_add(1, Ordering::SeqCst);
+            })?;
+
+            unsafe { v.finalize() };
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(called.load(Ordering::SeqCst), 1);
+    });
+}
+
+#[test]
+fn rust_finalizer_for_immutable_is_error() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        jlrs.frame(1, |_global, frame| {
+            let v = Value::new(frame, 3u64)?;
+            assert!(v.add_rust_finalizer(frame, || ()).is_err());
+            Ok(())
+        })
+        .unwrap();
+    });
+}
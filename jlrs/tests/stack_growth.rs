@@ -0,0 +1,125 @@
+use jlrs::error::AllocError;
+use jlrs::prelude::*;
+use jlrs::traits::gc::{Gc, GcCollection};
+use jlrs::util::JULIA;
+use std::ffi::c_void;
+
+#[test]
+fn dynamic_frame_grows_beyond_stack_size() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        jlrs.set_stack_size(8);
+
+        let sum = jlrs
+            .dynamic_frame(|_global, frame| {
+                let mut values = Vec::new();
+                for i in 0..100u64 {
+                    values.push(Value::new(frame, i)?);
+                }
+
+                unsafe { frame.gc_collect(GcCollection::Full) };
+
+                let mut sum = 0;
+                for value in values {
+                    sum += value.cast::<u64>()?;
+                }
+
+                Ok(sum)
+            })
+            .unwrap();
+
+        assert_eq!(sum, 4950);
+        assert!(jlrs.stack_size() > 8);
+    });
+}
+
+#[test]
+fn static_frame_larger_than_stack_size() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        jlrs.set_stack_size(8);
+
+        let out = jlrs
+            .frame(64, |_global, frame| {
+                let values = Values::new(frame, [1u64; 64])?;
+                values.value(63)?.cast::<u64>()
+            })
+            .unwrap();
+
+        assert_eq!(out, 1);
+    });
+}
+
+#[test]
+fn values_survive_nested_growth() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        jlrs.set_stack_size(8);
+
+        jlrs.dynamic_frame(|_global, frame| {
+            let first = Value::new(frame, 1u64)?;
+            let output = frame.output()?;
+
+            let second = frame.dynamic_frame(|frame| {
+                let values = Values::new(frame, [2u64; 32])?;
+                let nested = frame.frame(16, |frame| {
+                    let v = Value::new(frame, 3u64)?;
+                    v.cast::<u64>()
+                })?;
+
+                assert_eq!(nested, 3);
+                values.value(31)?.cast::<u64>()
+            })?;
+
+            let many = Values::new(frame, [4u64; 16])?;
+            let last = frame.dynamic_frame(|frame| {
+                let v = Value::new(frame, 5u64)?;
+                Ok(v.extend(frame, output))
+            })?;
+
+            unsafe { frame.gc_collect(GcCollection::Full) };
+
+            assert_eq!(first.cast::<u64>()?, 1);
+            assert_eq!(second, 2);
+            assert_eq!(many.value(15)?.cast::<u64>()?, 4);
+            assert_eq!(last.cast::<u64>()?, 5);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+unsafe extern "C" fn exceed_max_stack_size() -> bool {
+    let mut ccall = JuliaBuilder::new().stack_size(8).max_stack_size(32).ccall();
+
+    let res = ccall.dynamic_frame(|_global, frame| {
+        for i in 0..64u64 {
+            Value::new(frame, i)?;
+        }
+
+        Ok(())
+    });
+
+    match res {
+        Err(e) => match *e {
+            JlrsError::AllocError(AllocError::StackOverflow(_, 32)) => true,
+            _ => false,
+        },
+        Ok(_) => false,
+    }
+}
+
+#[test]
+fn max_stack_size_is_respected() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        jlrs.frame(2, |_global, frame| {
+            let caller = Value::eval_string(frame, "p -> ccall(p, Bool, ())")?.unwrap();
+            let ptr = Value::new(frame, exceed_max_stack_size as *mut c_void)?;
+            let ok = caller.call1(frame, ptr)?.unwrap().cast::<bool>()?;
+            assert!(ok);
+            Ok(())
+        })
+        .unwrap();
+    });
+}
@@ -38,3 +38,35 @@ fn bounds_error() {
         .unwrap();
     });
 }
+
+#[test]
+fn parse_backtrace() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let backtrace = jlrs
+            .frame(0, |global, frame| {
+                frame.frame(6, |frame| {
+                    let idx = Value::new(frame, 4usize)?;
+                    let data = vec![1.0f64, 2., 3.];
+                    let array = Value::move_array(frame, data, 3)?;
+                    let func = Module::base(global)
+                        .function("getindex")?
+                        .attach_stacktrace(frame)?
+                        .unwrap();
+                    let out = func.call2(frame, array, idx)?.unwrap_err();
+                    let stacktrace = out.get_field(frame, "stacktrace")?;
+
+                    Backtrace::new(frame, stacktrace)
+                })
+            })
+            .unwrap();
+
+        let first = &backtrace.frames()[0];
+        assert!(!first.func().is_empty());
+        assert!(first.line() > 0);
+        assert_eq!(
+            backtrace.to_string().lines().count(),
+            backtrace.frames().len()
+        );
+    });
+}
@@ -0,0 +1,93 @@
+use jlrs::prelude::*;
+use jlrs::stdio::{FnWriter, Stream};
+use jlrs::util::JULIA;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn capture_stdout_and_stderr() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let (_, output) = jlrs
+            .capture_output(2, |_global, frame| {
+                Value::eval_string(frame, "println(\"to stdout\")")?.unwrap();
+                Value::eval_string(frame, "println(stderr, \"to stderr\")")?.unwrap();
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(output.stdout, "to stdout\n");
+        assert_eq!(output.stderr, "to stderr\n");
+    });
+}
+
+#[test]
+fn capture_tracing_call() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let (_, output) = jlrs
+            .capture_output(5, |global, frame| {
+                let idx = Value::new(frame, 4usize)?;
+                let data = vec![1.0f64, 2., 3.];
+                let array = Value::move_array(frame, data, 3)?;
+                let func = Module::base(global)
+                    .function("getindex")?
+                    .tracing_call(frame)?
+                    .unwrap();
+
+                assert!(func.call2(frame, array, idx)?.is_err());
+                Ok(())
+            })
+            .unwrap();
+
+        assert!(output.stdout.is_empty());
+        assert!(!output.stderr.is_empty());
+    });
+}
+
+#[test]
+fn output_is_restored() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        jlrs.capture_output(0, |_, _| Ok(())).unwrap();
+
+        let is_restored = jlrs
+            .frame(1, |_global, frame| {
+                Value::eval_string(frame, "!(stdout isa Main.Jlrs.RustIO)")?
+                    .unwrap()
+                    .cast::<bool>()
+            })
+            .unwrap();
+
+        assert!(is_restored);
+    });
+}
+
+#[test]
+fn redirect_to_writer() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        let collected = Arc::new(Mutex::new(Vec::new()));
+        let collected_clone = collected.clone();
+        let writer =
+            FnWriter(move |bytes: &[u8]| collected_clone.lock().unwrap().extend_from_slice(bytes));
+
+        jlrs.redirect_output(Stream::Stdout, Some(Box::new(writer)))
+            .unwrap();
+
+        jlrs.frame(1, |_global, frame| {
+            Value::eval_string(frame, "print(\"redirected\")")?.unwrap();
+            Ok(())
+        })
+        .unwrap();
+
+        jlrs.redirect_output(Stream::Stdout, None).unwrap();
+
+        jlrs.frame(1, |_global, frame| {
+            Value::eval_string(frame, "print(\"\")")?.unwrap();
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(&*collected.lock().unwrap(), b"redirected");
+    });
+}
@@ -0,0 +1,42 @@
+use jlrs::prelude::*;
+use jlrs::traits::gc::{Gc, GcCollection};
+use jlrs::util::JULIA;
+use jlrs::value::weak_ref::WeakRef;
+
+#[test]
+fn upgrade_live_value() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        jlrs.frame(3, |_global, frame| {
+            let v = Value::eval_string(frame, "Ref(1)")?.unwrap();
+            let weak_ref = WeakRef::new(frame, v)?;
+            assert!(weak_ref.as_value().is::<WeakRef>());
+
+            unsafe { frame.gc_collect(GcCollection::Full) };
+
+            let upgraded = weak_ref.upgrade(frame)?.unwrap();
+            assert!(upgraded.egal(v));
+            Ok(())
+        })
+        .unwrap();
+    });
+}
+
+#[test]
+fn upgrade_freed_value() {
+    JULIA.with(|j| {
+        let mut jlrs = j.borrow_mut();
+        jlrs.frame(2, |_global, frame| {
+            // Only the WeakRef is protected, the Ref it refers to can be freed.
+            let weak_ref = Value::eval_string(frame, "WeakRef(Ref(2))")?
+                .unwrap()
+                .cast::<WeakRef>()?;
+
+            unsafe { frame.gc_collect(GcCollection::Full) };
+
+            assert!(weak_ref.upgrade(frame)?.is_none());
+            Ok(())
+        })
+        .unwrap();
+    });
+}
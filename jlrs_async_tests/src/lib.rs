@@ -38,6 +38,62 @@ mod example {
         }
     }
 
+    struct PanickingTask {
+        sender: Sender<JlrsResult<f64>>,
+    }
+
+    #[async_trait(?Send)]
+    impl JuliaTask for PanickingTask {
+        type T = f64;
+        type R = Sender<JlrsResult<Self::T>>;
+
+        async fn run<'base>(
+            &mut self,
+            _global: Global<'base>,
+            frame: &mut AsyncFrame<'base>,
+        ) -> JlrsResult<Self::T> {
+            let _ = Value::new(frame, 1.0f64)?;
+            panic!("task panicked")
+        }
+
+        fn return_channel(&self) -> Option<&Sender<JlrsResult<Self::T>>> {
+            Some(&self.sender)
+        }
+    }
+
+    struct DefineFunctionTask {
+        sender: Sender<JlrsResult<f64>>,
+    }
+
+    #[async_trait(?Send)]
+    impl JuliaTask for DefineFunctionTask {
+        type T = f64;
+        type R = Sender<JlrsResult<Self::T>>;
+
+        async fn run<'base>(
+            &mut self,
+            global: Global<'base>,
+            frame: &mut AsyncFrame<'base>,
+        ) -> JlrsResult<Self::T> {
+            let func = Module::main(global).define_function(frame, "rustadd", |frame, args| {
+                let mut sum = 0.0f64;
+                for arg in args {
+                    sum += arg.cast::<f64>()?;
+                }
+
+                Value::new(frame, sum)
+            })?;
+
+            let a = Value::new(frame, 1.0f64)?;
+            let b = Value::new(frame, 2.0f64)?;
+            func.call2(frame, a, b)?.unwrap().cast::<f64>()
+        }
+
+        fn return_channel(&self) -> Option<&Sender<JlrsResult<Self::T>>> {
+            Some(&self.sender)
+        }
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -71,6 +127,35 @@ mod example {
             assert_eq!(receiver2.recv().unwrap().unwrap(), 30_000_006.0);
             assert_eq!(receiver1.recv().unwrap().unwrap(), 20_000_004.0);
 
+            let (sender3, receiver3) = crossbeam_channel::bounded(1);
+            julia
+                .try_new_task(PanickingTask { sender: sender3 })
+                .unwrap();
+
+            match *receiver3.recv().unwrap().unwrap_err() {
+                JlrsError::Panic(ref msg) => assert_eq!(msg, "task panicked"),
+                ref e => panic!("Expected a panic, got {}", e),
+            }
+
+            // The runtime must still be usable after a task has panicked.
+            let (sender4, receiver4) = crossbeam_channel::bounded(1);
+            julia
+                .try_new_task(MyTask {
+                    dims: 4,
+                    iters: 5,
+                    sender: sender4,
+                })
+                .unwrap();
+
+            assert_eq!(receiver4.recv().unwrap().unwrap(), 24.0);
+
+            let (sender5, receiver5) = crossbeam_channel::bounded(1);
+            julia
+                .try_new_task(DefineFunctionTask { sender: sender5 })
+                .unwrap();
+
+            assert_eq!(receiver5.recv().unwrap().unwrap(), 3.0);
+
             std::mem::drop(julia);
             handle
                 .join()
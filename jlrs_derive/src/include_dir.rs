@@ -0,0 +1,192 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+struct Source {
+    name: String,
+    path: PathBuf,
+    defines: Vec<String>,
+    uses: Vec<String>,
+}
+
+pub(crate) fn include_julia_dir_impl(input: TokenStream) -> TokenStream {
+    let dir = syn::parse_macro_input!(input as syn::LitStr);
+
+    let sources = match collect_sources(&dir.value()).and_then(sort_sources) {
+        Ok(sources) => sources,
+        Err(msg) => return syn::Error::new(dir.span(), msg).to_compile_error().into(),
+    };
+
+    let names = sources.iter().map(|s| &s.name);
+    let paths = sources.iter().map(|s| s.path.to_string_lossy().to_string());
+
+    let expanded = quote! {
+        {
+            const SOURCES: &[(&str, &str)] = &[#((#names, include_str!(#paths))),*];
+            SOURCES
+        }
+    };
+
+    expanded.into()
+}
+
+// Find all `.jl` files in `dir`, which is relative to the manifest directory of the crate that
+// invokes the macro.
+fn collect_sources(dir: &str) -> Result<Vec<Source>, String> {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").map_err(|e| e.to_string())?;
+    let root = Path::new(&manifest_dir).join(dir);
+    if !root.is_dir() {
+        return Err(format!("{} is not a directory", root.display()));
+    }
+
+    let mut paths = Vec::new();
+    walk(&root, &mut paths).map_err(|e| format!("cannot read {}: {}", root.display(), e))?;
+    paths.sort();
+
+    let prefix = dir.trim_end_matches('/');
+    paths
+        .into_iter()
+        .map(|path| {
+            let code = fs::read_to_string(&path)
+                .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+            let relative = path
+                .strip_prefix(&root)
+                .expect("Path is not in root")
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            let (defines, uses) = scan_modules(&code);
+            Ok(Source {
+                name: format!("{}/{}", prefix, relative),
+                path,
+                defines,
+                uses,
+            })
+        })
+        .collect()
+}
+
+fn walk(dir: &Path, paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(&path, paths)?;
+        } else if path.extension().and_then(|ext| ext.to_str()) == Some("jl") {
+            paths.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+// Returns the names of the modules that are defined in `code`, and the names of the modules that
+// are loaded with `using` or `import`. This is a line-based scan rather than a parser, it only
+// needs to find the dependencies between the files in a directory.
+fn scan_modules(code: &str) -> (Vec<String>, Vec<String>) {
+    let mut defines = Vec::new();
+    let mut uses = Vec::new();
+    let mut in_block_comment = false;
+
+    for line in code.lines() {
+        let line = line.trim();
+        if in_block_comment {
+            in_block_comment = !line.contains("=#");
+            continue;
+        }
+
+        if line.starts_with("#=") {
+            in_block_comment = !line.contains("=#");
+            continue;
+        }
+
+        let line = line.split('#').next().unwrap_or("").trim();
+        if let Some(rest) =
+            strip_keyword(line, "module").or_else(|| strip_keyword(line, "baremodule"))
+        {
+            if let Some(name) = identifier(rest) {
+                defines.push(name.to_string());
+            }
+        } else if let Some(rest) =
+            strip_keyword(line, "using").or_else(|| strip_keyword(line, "import"))
+        {
+            let modules = rest.split(':').next().unwrap_or("");
+            for item in modules.split(',') {
+                let item = item.trim().trim_start_matches('.');
+                let item = item.strip_prefix("Main.").unwrap_or(item);
+                if let Some(name) = identifier(item) {
+                    uses.push(name.to_string());
+                }
+            }
+        }
+    }
+
+    (defines, uses)
+}
+
+fn strip_keyword<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(keyword)?;
+    if rest.starts_with(char::is_whitespace) {
+        Some(rest.trim_start())
+    } else {
+        None
+    }
+}
+
+fn identifier(s: &str) -> Option<&str> {
+    let end = s
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '!'))
+        .unwrap_or(s.len());
+
+    if end == 0 {
+        None
+    } else {
+        Some(&s[..end])
+    }
+}
+
+// Sort the sources so that every file that defines a module is loaded before the files that use
+// it. Files that don't depend on each other are loaded in alphabetical order.
+fn sort_sources(sources: Vec<Source>) -> Result<Vec<Source>, String> {
+    let n = sources.len();
+    let mut dependencies = vec![BTreeSet::new(); n];
+    for (i, source) in sources.iter().enumerate() {
+        for used in source.uses.iter() {
+            for (j, other) in sources.iter().enumerate() {
+                if i != j && other.defines.contains(used) {
+                    dependencies[i].insert(j);
+                }
+            }
+        }
+    }
+
+    let mut order = Vec::with_capacity(n);
+    let mut done = vec![false; n];
+    while order.len() < n {
+        let next = (0..n).find(|&i| !done[i] && dependencies[i].iter().all(|&j| done[j]));
+        match next {
+            Some(i) => {
+                done[i] = true;
+                order.push(i);
+            }
+            None => {
+                let cycle = (0..n)
+                    .filter(|&i| !done[i])
+                    .map(|i| sources[i].name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                return Err(format!("cyclic dependency between {}", cycle));
+            }
+        }
+    }
+
+    let mut sources = sources.into_iter().map(Some).collect::<Vec<_>>();
+    Ok(order
+        .into_iter()
+        .map(|i| sources[i].take().expect("Source was already taken"))
+        .collect())
+}
@@ -1,5 +1,7 @@
 extern crate proc_macro;
 
+mod include_dir;
+
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{self, Meta};
@@ -89,6 +91,29 @@ pub fn into_julia_derive(input: TokenStream) -> TokenStream {
     impl_into_julia(&ast)
 }
 
+/// Embed all `.jl` files in a directory at compile time. The path is relative to the directory
+/// that contains the `Cargo.toml` of the crate that uses this macro, subdirectories are included
+/// too. This macro expands to a `&'static [(&'static str, &'static str)]` that contains the name
+/// and the contents of each file. The name is the path of the file relative to the directory
+/// that contains `Cargo.toml`, and can be used with `Julia::include_string` to load the file
+/// with correct file and line information.
+///
+/// The files are sorted in dependency order: if a file loads a module with `using` or `import`
+/// that's defined in another file, that file comes first. Files that don't depend on each other
+/// are sorted alphabetically. A cyclic dependency is a compile error.
+///
+/// ```ignore
+/// const SOURCES: &[(&str, &str)] = include_julia_dir!("julia");
+///
+/// for (name, code) in SOURCES {
+///     julia.include_string(code, name)?;
+/// }
+/// ```
+#[proc_macro]
+pub fn include_julia_dir(input: TokenStream) -> TokenStream {
+    include_dir::include_julia_dir_impl(input)
+}
+
 #[proc_macro_derive(JuliaStruct, attributes(jlrs))]
 pub fn julia_struct_derive(input: TokenStream) -> TokenStream {
     // Construct a representation of Rust code as a syntax tree